@@ -99,6 +99,22 @@ pub enum Commands {
         #[clap(long, short)]
         path: Option<String>,
     },
+    /// Decode the stored raw logs again into the event columns.
+    ///
+    /// This command re-decodes the topics and data stored by contracts with `store_raw_log`
+    /// using the current ABI without calling the RPC.
+    ///
+    /// Example:
+    /// `rindexer redecode` or `rindexer redecode --contract-name <CONTRACT_NAME>`
+    Redecode {
+        /// optional - Only re-decode the events of this contract
+        #[arg(long)]
+        contract_name: Option<String>,
+
+        /// optional - The path to run the command in, default will be where the command is run.
+        #[clap(long, short)]
+        path: Option<String>,
+    },
     /// Use phantom events to add your own events to contracts
     ///
     /// This command helps you use phantom events within rindexer.
@@ -146,7 +162,13 @@ pub enum StartSubcommands {
     ///
     /// Example:
     /// `rindexer start indexer`
-    Indexer,
+    Indexer {
+        #[clap(
+            long,
+            help = "Drop and resync tables whose schema no longer matches the ABI instead of refusing to start"
+        )]
+        allow_destructive: bool,
+    },
 
     /// Starts the GraphQL server based on the rindexer.yaml file.
     ///
@@ -168,6 +190,12 @@ pub enum StartSubcommands {
     All {
         #[clap(short, long, help = "Specify the port number for all services")]
         port: Option<String>,
+
+        #[clap(
+            long,
+            help = "Drop and resync tables whose schema no longer matches the ABI instead of refusing to start"
+        )]
+        allow_destructive: bool,
     },
 }
 
@@ -182,6 +182,7 @@ pub async fn handle_add_contract_command(
             dependency_events: None,
             reorg_safe_distance: None,
             generate_csv: None,
+            storage: None,
             streams: None,
             chat: None,
         });
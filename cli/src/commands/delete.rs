@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use rindexer::{
     drop_tables_for_indexer_sql,
     manifest::yaml::{read_manifest, YAML_CONFIG_NAME},
-    PostgresClient,
+    set_postgres_connection_details, PostgresClient,
 };
 use tokio::fs::remove_dir_all;
 
@@ -42,6 +42,7 @@ pub async fn handle_delete_command(
         );
 
         if postgres_delete == "yes" {
+            set_postgres_connection_details(manifest.storage.postgres_connection());
             let postgres_client = PostgresClient::new().await.map_err(|e| {
                 print_error_message(&format!("Could not connect to Postgres, make sure your connection string is mapping in the .env correctly: trace: {}", e));
                 e
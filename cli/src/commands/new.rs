@@ -161,9 +161,11 @@ pub fn handle_new_command(
             dependency_events: None,
             reorg_safe_distance: None,
             generate_csv: None,
+            storage: None,
             streams: None,
             chat: None,
         }],
+        entities: None,
         phantom: None,
         global: None,
         storage: Storage {
@@ -174,6 +176,8 @@ pub fn handle_new_command(
                     relationships: None,
                     indexes: None,
                     disable_create_tables: None,
+                    partitioning: None,
+                    connection: None,
                 })
             } else {
                 None
@@ -183,10 +187,20 @@ pub fn handle_new_command(
                     enabled: true,
                     path: "./generated_csv".to_string(),
                     disable_create_headers: None,
+                    delimiter: None,
+                    quote_style: None,
+                    max_blocks_per_file: None,
+                    max_file_size_mb: None,
+                    compression: None,
+                    fsync: None,
                 })
             } else {
                 None
             },
+            sqlite: None,
+            clickhouse: None,
+            parquet: None,
+            ndjson: None,
         },
         graphql: None,
     };
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use rindexer::{
+    manifest::yaml::{read_manifest, YAML_CONFIG_NAME},
+    redecode_raw_logs, set_postgres_connection_details, PostgresClient,
+};
+
+use crate::console::{print_error_message, print_success_message, print_warn_message};
+
+pub async fn handle_redecode_command(
+    project_path: PathBuf,
+    contract_name: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = read_manifest(&project_path.join(YAML_CONFIG_NAME)).map_err(|e| {
+        print_error_message(&format!("Could read the rindexer.yaml please make sure you are running the command with rindexer.yaml in root: trace: {}", e));
+        e
+    })?;
+
+    if !manifest.storage.postgres_enabled() {
+        print_error_message("Postgres storage is not enabled so there are no raw logs to re-decode. Please enable it in the YAML configuration file.");
+        return Ok(());
+    }
+
+    set_postgres_connection_details(manifest.storage.postgres_connection());
+    let postgres_client = PostgresClient::new().await.map_err(|e| {
+        print_error_message(&format!("Could not connect to Postgres, make sure your connection string is mapping in the .env correctly: trace: {}", e));
+        e
+    })?;
+
+    let tables =
+        redecode_raw_logs(&project_path, &manifest.to_indexer(), &postgres_client, contract_name)
+            .await
+            .map_err(|e| {
+                print_error_message(&format!("Could not re-decode the raw logs: trace: {}", e));
+                e
+            })?;
+
+    if tables.is_empty() {
+        print_warn_message("No contracts have store_raw_log enabled. Nothing to re-decode.");
+        return Ok(());
+    }
+
+    for table in &tables {
+        if table.skipped > 0 {
+            print_warn_message(&format!(
+                "{} - {} rows could not be decoded with the current ABI and were left as they are",
+                table.table_name, table.skipped
+            ));
+        }
+        print_success_message(&format!("{} - re-decoded {} rows", table.table_name, table.updated));
+    }
+
+    Ok(())
+}
@@ -5,8 +5,9 @@ use rindexer::{
         core::ProjectType,
         yaml::{read_manifest, YAML_CONFIG_NAME},
     },
-    rindexer_error, rindexer_info, setup_info_logger, start_rindexer_no_code,
-    GraphqlOverrideSettings, IndexerNoCodeDetails, PostgresClient, StartNoCodeDetails,
+    rindexer_error, rindexer_info, set_postgres_connection_details, setup_info_logger,
+    start_rindexer_no_code, GraphqlOverrideSettings, IndexerNoCodeDetails, PostgresClient,
+    StartNoCodeDetails,
 };
 
 use crate::{
@@ -125,6 +126,7 @@ pub async fn start(
     })?;
 
     if manifest.storage.postgres_enabled() {
+        set_postgres_connection_details(manifest.storage.postgres_connection());
         let client = PostgresClient::new().await;
         if client.is_err() {
             // find if docker-compose.yml is present in parent
@@ -156,15 +158,25 @@ pub async fn start(
                 .arg("--manifest-path")
                 .arg(project_cargo_manifest_path)
                 .arg(match command {
-                    StartSubcommands::Indexer => "-- --indexer".to_string(),
+                    StartSubcommands::Indexer { allow_destructive } => {
+                        if *allow_destructive {
+                            "-- --indexer --allow-destructive".to_string()
+                        } else {
+                            "-- --indexer".to_string()
+                        }
+                    }
                     StartSubcommands::Graphql { port } => match port {
                         Some(port) => format!("-- --graphql --port={}", port),
                         None => "-- --graphql".to_string(),
                     },
-                    StartSubcommands::All { port } => match port {
-                        Some(port) => format!("-- --port={}", port),
-                        None => "".to_string(),
-                    },
+                    StartSubcommands::All { port, allow_destructive } => {
+                        match (port, allow_destructive) {
+                            (Some(port), true) => format!("-- --port={} --allow-destructive", port),
+                            (Some(port), false) => format!("-- --port={}", port),
+                            (None, true) => "-- --allow-destructive".to_string(),
+                            (None, false) => "".to_string(),
+                        }
+                    }
                 })
                 .status()
                 .expect("Failed to execute cargo run.");
@@ -174,10 +186,13 @@ pub async fn start(
             }
         }
         ProjectType::NoCode => match command {
-            StartSubcommands::Indexer => {
+            StartSubcommands::Indexer { allow_destructive } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: true },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: true,
+                        allow_destructive_migrations: *allow_destructive,
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: false,
                         override_port: None,
@@ -192,7 +207,10 @@ pub async fn start(
             StartSubcommands::Graphql { port } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: false },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: false,
+                        allow_destructive_migrations: false,
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: true,
                         override_port: port.as_ref().and_then(|port| port.parse().ok()),
@@ -204,10 +222,13 @@ pub async fn start(
                     e
                 })?;
             }
-            StartSubcommands::All { port } => {
+            StartSubcommands::All { port, allow_destructive } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: true },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: true,
+                        allow_destructive_migrations: *allow_destructive,
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: true,
                         override_port: port.as_ref().and_then(|port| port.parse().ok()),
@@ -22,7 +22,7 @@ use crate::{
     commands::{
         add::handle_add_contract_command, codegen::handle_codegen_command,
         delete::handle_delete_command, new::handle_new_command, phantom::handle_phantom_commands,
-        start::start,
+        redecode::handle_redecode_command, start::start,
     },
     console::print_error_message,
 };
@@ -111,6 +111,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             load_env_from_path(&resolved_path);
             handle_delete_command(resolved_path).await
         }
+        Commands::Redecode { contract_name, path } => {
+            let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
+            load_env_from_path(&resolved_path);
+            handle_redecode_command(resolved_path, contract_name.as_deref()).await
+        }
         Commands::Phantom { subcommand, path } => {
             let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
             load_env_from_path(&resolved_path);
@@ -10,7 +10,7 @@ use crate::{
     },
     event::contract_setup::IndexingContractSetup,
     helpers::{camel_to_snake, get_full_path},
-    manifest::contract::Contract,
+    manifest::{contract::Contract, storage::StorageEvent},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,28 +78,36 @@ impl ABIInput {
         }
     }
 
+    /// Tuples (and arrays of tuples) are flattened into a property per component unless
+    /// `tuples_as_jsonb` is set, in which case they are a single `JSONB` property.
     pub fn generate_abi_name_properties(
         inputs: &[ABIInput],
         properties_type: &GenerateAbiPropertiesType,
         prefix: Option<&str>,
+        tuples_as_jsonb: bool,
     ) -> Vec<GenerateAbiNamePropertiesResult> {
         inputs
             .iter()
             .flat_map(|input| {
-                if let Some(components) = &input.components {
-                    ABIInput::generate_abi_name_properties(
+                let is_jsonb_tuple = tuples_as_jsonb && input.is_tuple();
+                match &input.components {
+                    Some(components) if !is_jsonb_tuple => ABIInput::generate_abi_name_properties(
                         components,
                         properties_type,
                         Some(&camel_to_snake(&input.name)),
-                    )
-                } else {
-                    match properties_type {
+                        tuples_as_jsonb,
+                    ),
+                    _ => match properties_type {
                         GenerateAbiPropertiesType::PostgresWithDataTypes => {
                             let value = format!(
                                 "\"{}{}\" {}",
                                 prefix.map_or_else(|| "".to_string(), |p| format!("{}_", p)),
                                 camel_to_snake(&input.name),
-                                solidity_type_to_db_type(&input.type_)
+                                if is_jsonb_tuple {
+                                    "JSONB".to_string()
+                                } else {
+                                    solidity_type_to_db_type(&input.type_)
+                                }
                             );
 
                             vec![GenerateAbiNamePropertiesResult::new(
@@ -135,11 +143,28 @@ impl ABIInput {
                                 &input.type_,
                             )]
                         }
-                    }
+                    },
                 }
             })
             .collect()
     }
+
+    /// Is a tuple or an array of tuples
+    pub fn is_tuple(&self) -> bool {
+        self.type_.starts_with("tuple")
+    }
+
+    /// Unnamed inputs are named by their position so their columns and json keys are stable
+    fn name_unnamed_inputs(inputs: &mut [ABIInput]) {
+        for (index, input) in inputs.iter_mut().enumerate() {
+            if input.name.is_empty() {
+                input.name = format!("param_{}", index);
+            }
+            if let Some(components) = &mut input.components {
+                ABIInput::name_unnamed_inputs(components);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -196,7 +221,10 @@ impl ABIItem {
         let full_path = get_full_path(project_path, &contract.abi)
             .map_err(|_| ReadAbiError::AbiPathDoesNotExist(contract.abi.clone()))?;
         let abi_str = fs::read_to_string(full_path)?;
-        let abi_items: Vec<ABIItem> = serde_json::from_str(&abi_str)?;
+        let mut abi_items: Vec<ABIItem> = serde_json::from_str(&abi_str)?;
+        for item in abi_items.iter_mut().filter(|item| item.type_ == "event") {
+            ABIInput::name_unnamed_inputs(&mut item.inputs);
+        }
 
         let filtered_abi_items = match &contract.include_events {
             Some(events) => abi_items
@@ -280,17 +308,21 @@ impl EventInfo {
         &self.struct_data
     }
 
-    pub fn csv_headers_for_event(&self) -> Vec<String> {
+    pub fn csv_headers_for_event(&self, storage_event: &StorageEvent) -> Vec<String> {
         let mut headers: Vec<String> = ABIInput::generate_abi_name_properties(
             &self.inputs,
             &GenerateAbiPropertiesType::CsvHeaderNames,
             None,
+            storage_event.is_tuples_as_jsonb(),
         )
         .into_iter()
-        .map(|m| m.value)
+        .map(|m| storage_event.column_name(&m.value))
         .collect();
 
         headers.insert(0, r#"contract_address"#.to_string());
+        headers.extend(storage_event.formatted_column_names(&self.inputs));
+        headers.extend(storage_event.computed_column_names());
+        headers.extend(storage_event.raw_log_column_names());
         headers.push(r#"tx_hash"#.to_string());
         headers.push(r#"block_number"#.to_string());
         headers.push(r#"block_hash"#.to_string());
@@ -335,11 +367,13 @@ pub enum GetAbiItemWithDbMapError {
     ParameterNotFound(String),
 }
 
+/// The input and its column, the column respects the `columns` renames of the storage event
 pub fn get_abi_item_with_db_map(
     abi_items: &[ABIItem],
-    event_name: &str,
+    storage_event: &StorageEvent,
     parameter_mapping: &[&str],
 ) -> Result<GetAbiItemWithDbMap, GetAbiItemWithDbMapError> {
+    let event_name = storage_event.event_name.as_str();
     let event_item = abi_items.iter().find(|item| item.name == event_name && item.type_ == "event");
 
     match event_item {
@@ -362,7 +396,7 @@ pub fn get_abi_item_with_db_map(
                         {
                             return Ok(GetAbiItemWithDbMap {
                                 abi_item: input.clone(),
-                                db_column_name,
+                                db_column_name: storage_event.column_name(&db_column_name),
                             });
                         } else {
                             current_inputs = match input.type_.as_str() {
@@ -0,0 +1,205 @@
+use std::env;
+
+use dotenv::dotenv;
+use reqwest::Client;
+use serde_json::{Map, Value};
+use tracing::error;
+
+use crate::database::postgres::sql_type_wrapper::{
+    map_ethereum_wrapper_to_typed_json_value, EthereumSqlTypeWrapper,
+};
+
+/// Rows sent in a single insert request, clickhouse prefers few large inserts over many small ones
+const MAX_ROWS_PER_INSERT: usize = 100_000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClickhouseConnectionError {
+    #[error("The clickhouse url is missing please check your environment: {0}")]
+    ClickhouseUrlMissing(#[from] env::VarError),
+
+    #[error(
+        "Can not connect to clickhouse please make sure your url and credentials are correct: {0}"
+    )]
+    CanNotConnectToClickhouse(ClickhouseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClickhouseError {
+    #[error("Request error: {0}")]
+    RequestError(#[from] reqwest::Error),
+
+    #[error("Clickhouse returned {0}: {1}")]
+    QueryError(u16, String),
+
+    #[error("Could not parse clickhouse response: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+}
+
+fn generate_json_each_row_body(
+    column_names: &[String],
+    rows: &[Vec<EthereumSqlTypeWrapper>],
+) -> String {
+    rows.iter()
+        .map(|row| {
+            let object: Map<String, Value> = column_names
+                .iter()
+                .zip(row)
+                .map(|(name, wrapper)| {
+                    (name.clone(), map_ethereum_wrapper_to_typed_json_value(wrapper))
+                })
+                .collect();
+            Value::Object(object).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub struct ClickhouseClient {
+    client: Client,
+    url: String,
+    user: String,
+    password: String,
+}
+
+impl ClickhouseClient {
+    pub async fn new() -> Result<Self, ClickhouseConnectionError> {
+        dotenv().ok();
+
+        let client = ClickhouseClient {
+            client: Client::new(),
+            url: env::var("CLICKHOUSE_URL")?,
+            user: env::var("CLICKHOUSE_USER").unwrap_or_else(|_| "default".to_string()),
+            password: env::var("CLICKHOUSE_PASSWORD").unwrap_or_default(),
+        };
+
+        client
+            .execute("SELECT 1")
+            .await
+            .map_err(ClickhouseConnectionError::CanNotConnectToClickhouse)?;
+
+        Ok(client)
+    }
+
+    /// Values are bound with clickhouse query parameters, referenced as `{name:Type}` in the query
+    async fn request(
+        &self,
+        query: &str,
+        params: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<String, ClickhouseError> {
+        let mut query_string = vec![("query".to_string(), query.to_string())];
+        query_string.extend(
+            params.iter().map(|(name, value)| (format!("param_{}", name), value.to_string())),
+        );
+
+        let response = self
+            .client
+            .post(&self.url)
+            .header("X-ClickHouse-User", &self.user)
+            .header("X-ClickHouse-Key", &self.password)
+            .query(&query_string)
+            .body(body.unwrap_or_default())
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+        if status.is_success() {
+            Ok(text)
+        } else {
+            error!("Clickhouse error: {} - {}", status, text);
+            Err(ClickhouseError::QueryError(status.as_u16(), text))
+        }
+    }
+
+    pub async fn execute(&self, query: &str) -> Result<(), ClickhouseError> {
+        self.request(query, &[], None).await?;
+        Ok(())
+    }
+
+    /// The http interface runs a single statement per request so they are run in order
+    pub async fn batch_execute(&self, queries: &[String]) -> Result<(), ClickhouseError> {
+        for query in queries {
+            self.execute(query).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn query(
+        &self,
+        query: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Vec<Map<String, Value>>, ClickhouseError> {
+        let text = self.request(&format!("{} FORMAT JSONEachRow", query), params, None).await?;
+
+        text.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).map_err(ClickhouseError::InvalidResponse))
+            .collect()
+    }
+
+    pub async fn insert_json_rows(
+        &self,
+        table_name: &str,
+        column_names: &[String],
+        body: String,
+    ) -> Result<(), ClickhouseError> {
+        let query = format!(
+            "INSERT INTO {} ({}) FORMAT JSONEachRow",
+            table_name,
+            column_names.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", ")
+        );
+        self.request(&query, &[], Some(body)).await?;
+        Ok(())
+    }
+
+    pub async fn bulk_insert(
+        &self,
+        table_name: &str,
+        column_names: &[String],
+        bulk_data: &[Vec<EthereumSqlTypeWrapper>],
+    ) -> Result<(), ClickhouseError> {
+        for chunk in bulk_data.chunks(MAX_ROWS_PER_INSERT) {
+            self.insert_json_rows(
+                table_name,
+                column_names,
+                generate_json_each_row_body(column_names, chunk),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, I256, U256, U64};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_json_each_row_body() {
+        let body = generate_json_each_row_body(
+            &[
+                "from".to_string(),
+                "value".to_string(),
+                "delta".to_string(),
+                "block_number".to_string(),
+            ],
+            &[vec![
+                EthereumSqlTypeWrapper::Address(Address::repeat_byte(1)),
+                EthereumSqlTypeWrapper::U256(U256::MAX),
+                EthereumSqlTypeWrapper::I256(I256::from(-5)),
+                EthereumSqlTypeWrapper::U64(U64::from(10)),
+            ]],
+        );
+
+        let row: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(row["from"], json!("0x0101010101010101010101010101010101010101"));
+        assert_eq!(row["value"], json!(U256::MAX.to_string()));
+        assert_eq!(row["delta"], json!("-5"));
+        assert_eq!(row["block_number"], json!(10));
+    }
+}
@@ -0,0 +1,332 @@
+use std::path::Path;
+
+use tracing::{error, info};
+
+use crate::{
+    abi::{ABIInput, ABIItem, EventInfo, GenerateAbiPropertiesType, ParamTypeError, ReadAbiError},
+    database::postgres::{
+        generate::{generate_indexer_contract_schema_name, EventTableColumn},
+        sql_type_wrapper::EthereumSqlTypeWrapper,
+    },
+    helpers::camel_to_snake,
+    indexer::Indexer,
+    manifest::storage::{ComputedColumnType, StorageEvent},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum GenerateTablesForIndexerClickhouseError {
+    #[error("{0}")]
+    ReadAbiError(#[from] ReadAbiError),
+
+    #[error("{0}")]
+    ParamTypeError(#[from] ParamTypeError),
+}
+
+pub fn ethereum_sql_type_wrapper_to_clickhouse_type(wrapper: &EthereumSqlTypeWrapper) -> String {
+    let array = |inner: &str| format!("Array({})", inner);
+
+    match wrapper {
+        EthereumSqlTypeWrapper::U8(_) => "UInt8".to_string(),
+        EthereumSqlTypeWrapper::VecU8(_) => array("UInt8"),
+        EthereumSqlTypeWrapper::U16(_) => "UInt16".to_string(),
+        EthereumSqlTypeWrapper::VecU16(_) => array("UInt16"),
+        EthereumSqlTypeWrapper::U32(_) => "UInt32".to_string(),
+        EthereumSqlTypeWrapper::VecU32(_) => array("UInt32"),
+        EthereumSqlTypeWrapper::U64(_) => "UInt64".to_string(),
+        EthereumSqlTypeWrapper::VecU64(_) => array("UInt64"),
+        EthereumSqlTypeWrapper::U128(_) => "UInt128".to_string(),
+        EthereumSqlTypeWrapper::VecU128(_) => array("UInt128"),
+        EthereumSqlTypeWrapper::U256(_) => "UInt256".to_string(),
+        EthereumSqlTypeWrapper::VecU256(_) => array("UInt256"),
+        // clickhouse has no integer wider than 256 bits
+        EthereumSqlTypeWrapper::U512(_) => "String".to_string(),
+        EthereumSqlTypeWrapper::VecU512(_) => array("String"),
+        EthereumSqlTypeWrapper::I8(_) => "Int8".to_string(),
+        EthereumSqlTypeWrapper::VecI8(_) => array("Int8"),
+        EthereumSqlTypeWrapper::I16(_) => "Int16".to_string(),
+        EthereumSqlTypeWrapper::VecI16(_) => array("Int16"),
+        EthereumSqlTypeWrapper::I32(_) => "Int32".to_string(),
+        EthereumSqlTypeWrapper::VecI32(_) => array("Int32"),
+        EthereumSqlTypeWrapper::I64(_) => "Int64".to_string(),
+        EthereumSqlTypeWrapper::VecI64(_) => array("Int64"),
+        EthereumSqlTypeWrapper::I128(_) => "Int128".to_string(),
+        EthereumSqlTypeWrapper::VecI128(_) => array("Int128"),
+        EthereumSqlTypeWrapper::I256(_) => "Int256".to_string(),
+        EthereumSqlTypeWrapper::VecI256(_) => array("Int256"),
+        // hashes and addresses are stored as 0x prefixed hex
+        EthereumSqlTypeWrapper::H128(_) => "FixedString(34)".to_string(),
+        EthereumSqlTypeWrapper::VecH128(_) => array("FixedString(34)"),
+        EthereumSqlTypeWrapper::H160(_) | EthereumSqlTypeWrapper::Address(_) => {
+            "FixedString(42)".to_string()
+        }
+        EthereumSqlTypeWrapper::VecH160(_) | EthereumSqlTypeWrapper::VecAddress(_) => {
+            array("FixedString(42)")
+        }
+        EthereumSqlTypeWrapper::H256(_) => "FixedString(66)".to_string(),
+        EthereumSqlTypeWrapper::VecH256(_) => array("FixedString(66)"),
+        EthereumSqlTypeWrapper::H512(_) => "FixedString(130)".to_string(),
+        EthereumSqlTypeWrapper::VecH512(_) => array("FixedString(130)"),
+        EthereumSqlTypeWrapper::Bool(_) => "Bool".to_string(),
+        EthereumSqlTypeWrapper::VecBool(_) => array("Bool"),
+        EthereumSqlTypeWrapper::String(_) | EthereumSqlTypeWrapper::Bytes(_) => {
+            "String".to_string()
+        }
+        EthereumSqlTypeWrapper::VecString(_) | EthereumSqlTypeWrapper::VecBytes(_) => {
+            array("String")
+        }
+        // tuples stored as json are kept as the raw json string
+        EthereumSqlTypeWrapper::JSONB(_) => "String".to_string(),
+        EthereumSqlTypeWrapper::Numeric(_) | EthereumSqlTypeWrapper::NullableString(_) => {
+            "Nullable(String)".to_string()
+        }
+        EthereumSqlTypeWrapper::NullableBool(_) => "Nullable(Bool)".to_string(),
+        EthereumSqlTypeWrapper::RawTopics(_) => array("FixedString(66)"),
+    }
+}
+
+pub fn generate_clickhouse_internal_event_table_name(
+    indexer_name: &str,
+    contract_name: &str,
+    event_name: &str,
+) -> String {
+    format!(
+        "rindexer_internal.`{}_{}`",
+        generate_indexer_contract_schema_name(indexer_name, contract_name),
+        camel_to_snake(event_name)
+    )
+}
+
+/// Takes the event table name from `Contract::event_table_name` so any override is kept
+pub fn generate_clickhouse_event_table_name(
+    indexer_name: &str,
+    contract_name: &str,
+    event_table_name: &str,
+) -> String {
+    format!(
+        "`{}`.`{}`",
+        generate_indexer_contract_schema_name(indexer_name, contract_name),
+        event_table_name
+    )
+}
+
+/// All the columns of an event table in the order they are created
+pub fn generate_clickhouse_event_table_columns(
+    event_info: &EventInfo,
+    storage_event: &StorageEvent,
+) -> Vec<EventTableColumn> {
+    let mut columns = vec![EventTableColumn {
+        name: "contract_address".to_string(),
+        data_type: "FixedString(42)".to_string(),
+    }];
+
+    columns.extend(
+        ABIInput::generate_abi_name_properties(
+            &event_info.inputs,
+            &GenerateAbiPropertiesType::PostgresColumnsNamesOnly,
+            None,
+            storage_event.is_tuples_as_jsonb(),
+        )
+        .into_iter()
+        .map(|property| EventTableColumn {
+            name: storage_event.column_name(&property.value),
+            data_type: property
+                .ethereum_sql_type_wrapper
+                .as_ref()
+                .map_or("String".to_string(), ethereum_sql_type_wrapper_to_clickhouse_type),
+        }),
+    );
+
+    // the decimals can differ per token so the formatted value is kept as a string
+    columns.extend(
+        storage_event
+            .formatted_column_names(&event_info.inputs)
+            .into_iter()
+            .map(|name| EventTableColumn { name, data_type: "Nullable(String)".to_string() }),
+    );
+
+    // numbers are strings like the formatted values
+    columns.extend(storage_event.computed_columns().iter().map(|computed_column| {
+        let data_type = match computed_column.type_ {
+            ComputedColumnType::Bool => "Nullable(Bool)",
+            ComputedColumnType::Number | ComputedColumnType::String => "Nullable(String)",
+        };
+        EventTableColumn { name: computed_column.name.clone(), data_type: data_type.to_string() }
+    }));
+
+    if storage_event.store_raw_log {
+        for (name, data_type) in [("topics", "Array(FixedString(66))"), ("data", "String")] {
+            columns.push(EventTableColumn {
+                name: name.to_string(),
+                data_type: data_type.to_string(),
+            });
+        }
+    }
+
+    for (name, data_type) in [
+        ("tx_hash", "FixedString(66)"),
+        ("block_number", "UInt64"),
+        ("block_hash", "FixedString(66)"),
+        ("network", "LowCardinality(String)"),
+        ("tx_index", "UInt64"),
+        ("log_index", "UInt256"),
+    ] {
+        columns.push(EventTableColumn { name: name.to_string(), data_type: data_type.to_string() });
+    }
+
+    columns
+}
+
+/// A batch which is retried inserts its rows again, the replacing engine keeps one row per log
+fn generate_event_table_sql(
+    event_info: &EventInfo,
+    table_name: &str,
+    storage_event: &StorageEvent,
+) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = ReplacingMergeTree ORDER BY (network, block_number, tx_hash, log_index)",
+        table_name,
+        generate_clickhouse_event_table_columns(event_info, storage_event)
+            .iter()
+            .map(|c| format!("`{}` {}", c.name, c.data_type))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Progress is only ever inserted, the highest block per network is the last synced block
+fn generate_internal_event_table_sql(table_name: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (`network` String, `last_synced_block` UInt64) ENGINE = ReplacingMergeTree(last_synced_block) ORDER BY network",
+        table_name
+    )
+}
+
+/// The event tables with the internal table holding the last synced block of their event
+pub fn generate_clickhouse_event_table_names(
+    project_path: &Path,
+    indexer: &Indexer,
+) -> Result<Vec<(String, String)>, GenerateTablesForIndexerClickhouseError> {
+    let mut table_names = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        for event_info in ABIItem::extract_event_names_and_signatures_from_abi(abi_items)? {
+            let storage_event = contract.storage_event(&event_info.name);
+            table_names.push((
+                generate_clickhouse_event_table_name(
+                    &indexer.name,
+                    &contract_name,
+                    &storage_event.event_table_name(),
+                ),
+                generate_clickhouse_internal_event_table_name(
+                    &indexer.name,
+                    &contract_name,
+                    &event_info.name,
+                ),
+            ));
+        }
+    }
+
+    Ok(table_names)
+}
+
+/// The clickhouse http interface runs a single statement per request
+pub fn generate_tables_for_indexer_clickhouse_sql(
+    project_path: &Path,
+    indexer: &Indexer,
+) -> Result<Vec<String>, GenerateTablesForIndexerClickhouseError> {
+    let mut sql = vec!["CREATE DATABASE IF NOT EXISTS rindexer_internal".to_string()];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
+        let database_name = generate_indexer_contract_schema_name(&indexer.name, &contract_name);
+        info!("Creating clickhouse database if not exists: {}", database_name);
+        sql.push(format!("CREATE DATABASE IF NOT EXISTS `{}`", database_name));
+
+        for event_info in &event_names {
+            let storage_event = contract.storage_event(&event_info.name);
+            let table_name = generate_clickhouse_event_table_name(
+                &indexer.name,
+                &contract_name,
+                &storage_event.event_table_name(),
+            );
+            info!("Creating clickhouse table if not exists: {}", table_name);
+            sql.push(generate_event_table_sql(event_info, &table_name, &storage_event));
+            sql.push(generate_internal_event_table_sql(
+                &generate_clickhouse_internal_event_table_name(
+                    &indexer.name,
+                    &contract_name,
+                    &event_info.name,
+                ),
+            ));
+        }
+    }
+
+    Ok(sql)
+}
+
+pub fn drop_tables_for_indexer_clickhouse_sql(
+    project_path: &Path,
+    indexer: &Indexer,
+) -> Vec<String> {
+    let mut sql = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        sql.push(format!(
+            "DROP DATABASE IF EXISTS `{}`",
+            generate_indexer_contract_schema_name(&indexer.name, &contract_name)
+        ));
+
+        match ABIItem::read_abi_items(project_path, contract) {
+            Ok(abi_items) => {
+                for abi_item in abi_items.iter() {
+                    sql.push(format!(
+                        "DROP TABLE IF EXISTS {}",
+                        generate_clickhouse_internal_event_table_name(
+                            &indexer.name,
+                            &contract_name,
+                            &abi_item.name
+                        )
+                    ));
+                }
+            }
+            Err(_) => {
+                error!(
+                    "Could not read ABI items for contract moving on clearing the other data up: {}",
+                    contract.name
+                );
+            }
+        }
+    }
+
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::postgres::sql_type_wrapper::solidity_type_to_ethereum_sql_type_wrapper;
+
+    fn clickhouse_type(abi_type: &str) -> String {
+        ethereum_sql_type_wrapper_to_clickhouse_type(
+            &solidity_type_to_ethereum_sql_type_wrapper(abi_type).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_solidity_types_to_clickhouse_types() {
+        assert_eq!(clickhouse_type("address"), "FixedString(42)");
+        assert_eq!(clickhouse_type("address[]"), "Array(FixedString(42))");
+        assert_eq!(clickhouse_type("uint24"), "UInt32");
+        assert_eq!(clickhouse_type("uint256"), "UInt256");
+        assert_eq!(clickhouse_type("int128"), "Int128");
+        assert_eq!(clickhouse_type("int256[]"), "Array(Int256)");
+        assert_eq!(clickhouse_type("bool"), "Bool");
+        assert_eq!(clickhouse_type("bytes32"), "String");
+        assert_eq!(clickhouse_type("string"), "String");
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::Path;
+
+use tracing::{debug, info, warn};
+
+use crate::{
+    database::clickhouse::{
+        client::{ClickhouseClient, ClickhouseConnectionError, ClickhouseError},
+        generate::{
+            drop_tables_for_indexer_clickhouse_sql, generate_clickhouse_event_table_names,
+            generate_tables_for_indexer_clickhouse_sql, GenerateTablesForIndexerClickhouseError,
+        },
+    },
+    manifest::core::Manifest,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetupClickhouseError {
+    #[error("{0}")]
+    ClickhouseConnection(#[from] ClickhouseConnectionError),
+
+    #[error("{0}")]
+    ClickhouseError(#[from] ClickhouseError),
+
+    #[error("Error creating tables for indexer: {0}")]
+    GeneratingTables(#[from] GenerateTablesForIndexerClickhouseError),
+}
+
+/// An event table which no longer exists while its event has synced blocks was renamed or dropped,
+/// the event is indexed again from its start block so the table is not missing its history
+async fn reset_orphaned_last_synced(
+    client: &ClickhouseClient,
+    table_names: &[(String, String)],
+) -> Result<(), SetupClickhouseError> {
+    for (table_name, internal_table_name) in table_names {
+        let exists = |rows: Vec<serde_json::Map<String, serde_json::Value>>| {
+            rows.first().and_then(|row| row.get("result")).map(|result| result.to_string()) ==
+                Some("1".to_string())
+        };
+        if exists(client.query(&format!("EXISTS TABLE {}", table_name), &[]).await?) ||
+            !exists(client.query(&format!("EXISTS TABLE {}", internal_table_name), &[]).await?)
+        {
+            continue;
+        }
+
+        let synced = client
+            .query(
+                &format!(
+                    "SELECT toString(count()) AS synced FROM {} WHERE last_synced_block > 0",
+                    internal_table_name
+                ),
+                &[],
+            )
+            .await?;
+        if synced.first().and_then(|row| row.get("synced")).and_then(|s| s.as_str()) != Some("0") {
+            warn!(
+                "Table {} does not exist but its event has already been indexed, the old table was renamed or dropped - the event is indexed again from its start block",
+                table_name
+            );
+            // progress is only ever inserted and read back as the highest block
+            client.execute(&format!("TRUNCATE TABLE {}", internal_table_name)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn setup_clickhouse(
+    project_path: &Path,
+    manifest: &Manifest,
+) -> Result<ClickhouseClient, SetupClickhouseError> {
+    info!("Setting up clickhouse");
+    let client = ClickhouseClient::new().await?;
+    let indexer = manifest.to_indexer();
+
+    if manifest.storage.clickhouse_drop_each_run() {
+        info!(
+            "`drop_each_run` enabled so dropping all clickhouse data for {} before starting",
+            &manifest.name
+        );
+        client
+            .batch_execute(&drop_tables_for_indexer_clickhouse_sql(project_path, &indexer))
+            .await?;
+        info!("Dropped all clickhouse data for {}", manifest.name);
+    }
+
+    reset_orphaned_last_synced(
+        &client,
+        &generate_clickhouse_event_table_names(project_path, &indexer)?,
+    )
+    .await?;
+
+    info!("Creating clickhouse tables for {}", manifest.name);
+    let sql = generate_tables_for_indexer_clickhouse_sql(project_path, &indexer)?;
+    debug!("{}", sql.join(";\n"));
+    client.batch_execute(&sql).await?;
+    info!("Created clickhouse tables for {}", manifest.name);
+
+    Ok(client)
+}
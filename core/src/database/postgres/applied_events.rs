@@ -0,0 +1,72 @@
+use ethers::types::{U256, U64};
+use tokio_postgres::types::ToSql;
+
+use crate::helpers::camel_to_snake;
+
+/// Records the events applied to each entity and rollup table so a batch retried after a later
+/// sink failed does not add its events a second time
+pub fn generate_applied_events_table_name(indexer_name: &str) -> String {
+    format!("rindexer_internal.{}_applied_events", camel_to_snake(indexer_name))
+}
+
+pub fn generate_applied_events_table_sql(indexer_name: &str) -> String {
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            "table_name" TEXT NOT NULL,
+            "network" VARCHAR(50) NOT NULL,
+            "block_number" NUMERIC NOT NULL,
+            "log_index" NUMERIC NOT NULL,
+            PRIMARY KEY ("table_name", "network", "block_number", "log_index")
+        );
+    "#,
+        generate_applied_events_table_name(indexer_name)
+    )
+}
+
+/// A condition which holds while the event at the block number and log index has not been applied
+/// to the table, the network, block number and log index are sql expressions
+pub(crate) fn generate_not_applied_sql(
+    indexer_name: &str,
+    table_name: &str,
+    network: &str,
+    block_number: &str,
+    log_index: &str,
+) -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM {} AS applied WHERE applied.\"table_name\" = '{}' \
+        AND applied.\"network\" = {} AND applied.\"block_number\" = {} AND applied.\"log_index\" = {})",
+        generate_applied_events_table_name(indexer_name),
+        table_name,
+        network,
+        block_number,
+        log_index
+    )
+}
+
+/// Marks the events as applied to the table, it must run in the same transaction as the
+/// statements applying them and after them so they still see the events as not applied
+pub fn applied_events_statement(
+    indexer_name: &str,
+    table_name: &str,
+    network: &str,
+    events: &[(U64, U256)],
+) -> (String, Vec<Box<dyn ToSql + Send + Sync>>) {
+    let sql = format!(
+        "INSERT INTO {} (\"table_name\", \"network\", \"block_number\", \"log_index\") \
+        SELECT $1::TEXT, $2::TEXT, e.block_number::NUMERIC, e.log_index::NUMERIC \
+        FROM unnest($3::TEXT[], $4::TEXT[]) AS e(block_number, log_index) \
+        ON CONFLICT DO NOTHING",
+        generate_applied_events_table_name(indexer_name)
+    );
+    let params: Vec<Box<dyn ToSql + Send + Sync>> = vec![
+        Box::new(table_name.to_string()),
+        Box::new(network.to_string()),
+        Box::new(
+            events.iter().map(|(block_number, _)| block_number.to_string()).collect::<Vec<_>>(),
+        ),
+        Box::new(events.iter().map(|(_, log_index)| log_index.to_string()).collect::<Vec<_>>()),
+    ];
+
+    (sql, params)
+}
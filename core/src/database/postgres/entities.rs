@@ -0,0 +1,280 @@
+use ethers::{
+    abi::{LogParam, Token},
+    types::{Address, I256, U256, U64},
+};
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    abi::ABIInput,
+    database::postgres::applied_events::generate_not_applied_sql,
+    helpers::camel_to_snake,
+    manifest::entity::{Entity, EntityColumn, EntityRule, EntityRuleOperation},
+};
+
+pub fn generate_entities_schema_name(indexer_name: &str) -> String {
+    format!("{}_entities", camel_to_snake(indexer_name))
+}
+
+pub fn generate_entity_tables_sql(indexer_name: &str, entities: &[Entity]) -> String {
+    let schema_name = generate_entities_schema_name(indexer_name);
+    let mut sql = format!("CREATE SCHEMA IF NOT EXISTS {};", schema_name);
+
+    for entity in entities {
+        let table_name = format!("{}.{}", schema_name, entity.name);
+        let key_columns: Vec<String> = entity
+            .keys
+            .iter()
+            .map(|key| format!("\"{}\" {} NOT NULL", key.name, column_db_type(key)))
+            .collect();
+        let key_names: Vec<String> =
+            entity.keys.iter().map(|key| format!("\"{}\"", key.name)).collect();
+
+        sql.push_str(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (\"network\" VARCHAR(50) NOT NULL, {}, \"block_number\" NUMERIC NOT NULL, PRIMARY KEY (\"network\", {}));",
+            table_name,
+            key_columns.join(", "),
+            key_names.join(", ")
+        ));
+
+        // columns added to an existing entity are created on the next start
+        for column in &entity.columns {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS \"{}\" {};",
+                table_name,
+                column.name,
+                column_db_type(column)
+            ));
+        }
+    }
+
+    sql
+}
+
+fn column_db_type(column: &EntityColumn) -> &'static str {
+    column.db_type().expect("Entity column types are validated with the manifest")
+}
+
+/// An entity rule ready to be applied to the events of its contract event
+#[derive(Debug, Clone)]
+pub struct EntityRuleStatement {
+    pub entity_name: String,
+    /// The entity table, the events applied to it are recorded under this name
+    pub table_name: String,
+    sql: String,
+    keys: Vec<String>,
+    value: String,
+}
+
+impl EntityRuleStatement {
+    fn new(indexer_name: &str, entity: &Entity, rule: EntityRule) -> Self {
+        EntityRuleStatement {
+            entity_name: entity.name.clone(),
+            table_name: format!("{}.{}", generate_entities_schema_name(indexer_name), entity.name),
+            sql: generate_entity_rule_sql(indexer_name, entity, &rule),
+            keys: rule.keys,
+            value: rule.value,
+        }
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// `None` when a key or the value has no value in the event
+    pub fn params(
+        &self,
+        event_inputs: &[ABIInput],
+        log_params: &[LogParam],
+        contract_address: Address,
+        network: &str,
+        block_number: U64,
+        log_index: U256,
+    ) -> Option<Vec<Box<dyn ToSql + Send + Sync>>> {
+        let mut params: Vec<Box<dyn ToSql + Send + Sync>> = vec![
+            Box::new(network.to_string()),
+            Box::new(block_number.to_string()),
+            Box::new(log_index.to_string()),
+        ];
+
+        for path in self.keys.iter().chain(std::iter::once(&self.value)) {
+            params.push(Box::new(source_value(event_inputs, log_params, contract_address, path)?));
+        }
+
+        Some(params)
+    }
+}
+
+/// The statements of every entity rule listening to the contract event
+pub fn generate_entity_rule_statements(
+    indexer_name: &str,
+    entities: &[Entity],
+    contract_name: &str,
+    event_name: &str,
+) -> Vec<EntityRuleStatement> {
+    entities
+        .iter()
+        .flat_map(|entity| {
+            entity
+                .event_rules(contract_name, event_name)
+                .into_iter()
+                .filter_map(|rule| EntityRule::parse(rule).ok())
+                .map(move |rule| EntityRuleStatement::new(indexer_name, entity, rule))
+        })
+        .collect()
+}
+
+/// Upserts the entity row unless the event was already applied to the entity, the params are the
+/// network, the block number, the log index, the keys and the value all as text
+fn generate_entity_rule_sql(indexer_name: &str, entity: &Entity, rule: &EntityRule) -> String {
+    let table_name = format!("{}.{}", generate_entities_schema_name(indexer_name), entity.name);
+    let column = entity
+        .find_column(&rule.column)
+        .expect("Entity rule columns are validated with the manifest");
+
+    let cast = |index: usize, column: &EntityColumn| {
+        format!("${}::TEXT::{}", index, column_db_type(column))
+    };
+    let key_values: Vec<String> =
+        entity.keys.iter().enumerate().map(|(i, key)| cast(i + 4, key)).collect();
+    let key_names: Vec<String> =
+        entity.keys.iter().map(|key| format!("\"{}\"", key.name)).collect();
+
+    // a decrement inserts the negated value so a new row starts below zero like it would after
+    // the update
+    let value = cast(entity.keys.len() + 4, column);
+    let value = match rule.operation {
+        EntityRuleOperation::Decrement => format!("-({})", value),
+        _ => value,
+    };
+    let update = match rule.operation {
+        EntityRuleOperation::Set => format!("EXCLUDED.\"{}\"", column.name),
+        _ => format!("COALESCE(existing.\"{0}\", 0) + EXCLUDED.\"{0}\"", column.name),
+    };
+
+    format!(
+        "INSERT INTO {table_name} AS existing (\"network\", {key_names}, \"{column}\", \"block_number\") \
+        SELECT $1::TEXT, {key_values}, {value}, $2::TEXT::NUMERIC WHERE {not_applied} \
+        ON CONFLICT (\"network\", {key_names}) DO UPDATE \
+        SET \"{column}\" = {update}, \"block_number\" = EXCLUDED.\"block_number\"",
+        table_name = table_name,
+        key_names = key_names.join(", "),
+        column = column.name,
+        key_values = key_values.join(", "),
+        value = value,
+        update = update,
+        not_applied = generate_not_applied_sql(
+            indexer_name,
+            &table_name,
+            "$1::TEXT",
+            "$2::TEXT::NUMERIC",
+            "$3::TEXT::NUMERIC"
+        ),
+    )
+}
+
+/// The value of an event input path or `contract_address` as text postgres casts into the column
+/// type
+pub(crate) fn source_value(
+    event_inputs: &[ABIInput],
+    log_params: &[LogParam],
+    contract_address: Address,
+    path: &str,
+) -> Option<String> {
+    if path == "contract_address" {
+        return Some(format!("{:?}", contract_address));
+    }
+
+    token_to_entity_value(find_token(event_inputs, log_params, path)?)
+}
+
+/// Log params are in input order, tuple fields are found by walking their components
+fn find_token<'a>(
+    event_inputs: &[ABIInput],
+    log_params: &'a [LogParam],
+    path: &str,
+) -> Option<&'a Token> {
+    let mut parts = path.split('.');
+    let first = parts.next()?;
+    let index = event_inputs.iter().position(|input| input.name == first)?;
+    let mut input = &event_inputs[index];
+    let mut token = &log_params.get(index)?.value;
+
+    for part in parts {
+        let components = input.components.as_ref()?;
+        let index = components.iter().position(|component| component.name == part)?;
+        input = &components[index];
+        token = match token {
+            Token::Tuple(tokens) => tokens.get(index)?,
+            _ => return None,
+        };
+    }
+
+    Some(token)
+}
+
+/// The text postgres casts into the entity column type
+fn token_to_entity_value(token: &Token) -> Option<String> {
+    match token {
+        Token::Address(address) => Some(format!("{:?}", address)),
+        Token::Uint(value) => Some(value.to_string()),
+        Token::Int(value) => Some(I256::from_raw(*value).to_string()),
+        Token::Bool(value) => Some(value.to_string()),
+        Token::String(value) => Some(value.clone()),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => {
+            Some(format!("\\x{}", hex::encode(bytes)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_rule_sql() {
+        let entity: Entity = serde_yaml::from_str(
+            r#"
+            name: balances
+            keys:
+              - name: holder
+                type: address
+            columns:
+              - name: balance
+                type: uint256
+            events:
+              - contract_name: Token
+                event_name: Transfer
+                rules:
+                  - balance[from] -= value
+            "#,
+        )
+        .unwrap();
+
+        let statements =
+            generate_entity_rule_statements("MyIndexer", &[entity], "Token", "Transfer");
+        assert_eq!(statements.len(), 1);
+        assert_eq!(
+            statements[0].sql(),
+            "INSERT INTO my_indexer_entities.balances AS existing (\"network\", \"holder\", \"balance\", \"block_number\") \
+             SELECT $1::TEXT, $4::TEXT::CHAR(42), -($5::TEXT::NUMERIC), $2::TEXT::NUMERIC \
+             WHERE NOT EXISTS (SELECT 1 FROM rindexer_internal.my_indexer_applied_events AS applied \
+             WHERE applied.\"table_name\" = 'my_indexer_entities.balances' AND applied.\"network\" = $1::TEXT \
+             AND applied.\"block_number\" = $2::TEXT::NUMERIC AND applied.\"log_index\" = $3::TEXT::NUMERIC) \
+             ON CONFLICT (\"network\", \"holder\") DO UPDATE \
+             SET \"balance\" = COALESCE(existing.\"balance\", 0) + EXCLUDED.\"balance\", \
+             \"block_number\" = EXCLUDED.\"block_number\""
+        );
+    }
+
+    #[test]
+    fn test_token_to_entity_value() {
+        let minus_one = I256::from(-1).into_raw();
+        assert_eq!(token_to_entity_value(&Token::Int(minus_one)), Some("-1".to_string()));
+        assert_eq!(
+            token_to_entity_value(&Token::FixedBytes(vec![0xab, 0x01])),
+            Some("\\xab01".to_string())
+        );
+        assert_eq!(token_to_entity_value(&Token::Array(vec![])), None);
+    }
+}
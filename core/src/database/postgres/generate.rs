@@ -1,33 +1,69 @@
 use std::path::Path;
 
+use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
 use crate::{
     abi::{ABIInput, ABIItem, EventInfo, GenerateAbiPropertiesType, ParamTypeError, ReadAbiError},
+    database::postgres::{
+        applied_events::{generate_applied_events_table_name, generate_applied_events_table_sql},
+        entities::{generate_entities_schema_name, generate_entity_tables_sql},
+        partitions::{
+            generate_network_partition_sql, generate_partitions_table_name,
+            generate_partitions_table_sql,
+        },
+        retention::{generate_retention_table_name, generate_retention_table_sql},
+        rollups::generate_rollup_tables_sql,
+        scripts::generate_script_tables_sql,
+    },
     helpers::camel_to_snake,
     indexer::Indexer,
-    manifest::contract::Contract,
+    manifest::{
+        contract::Contract,
+        storage::{ComputedColumnType, PostgresPartitioning, StorageEvent},
+    },
     types::code::Code,
 };
 
-fn generate_columns(inputs: &[ABIInput], property_type: &GenerateAbiPropertiesType) -> Vec<String> {
-    ABIInput::generate_abi_name_properties(inputs, property_type, None)
+fn generate_columns(
+    inputs: &[ABIInput],
+    property_type: &GenerateAbiPropertiesType,
+    tuples_as_jsonb: bool,
+) -> Vec<String> {
+    ABIInput::generate_abi_name_properties(inputs, property_type, None, tuples_as_jsonb)
         .into_iter()
         .map(|m| m.value)
         .collect()
 }
 
-pub fn generate_columns_with_data_types(inputs: &[ABIInput]) -> Vec<String> {
-    generate_columns(inputs, &GenerateAbiPropertiesType::PostgresWithDataTypes)
+fn generate_columns_with_data_types(inputs: &[ABIInput], tuples_as_jsonb: bool) -> Vec<String> {
+    generate_columns(inputs, &GenerateAbiPropertiesType::PostgresWithDataTypes, tuples_as_jsonb)
 }
 
-fn generate_columns_names_only(inputs: &[ABIInput]) -> Vec<String> {
-    generate_columns(inputs, &GenerateAbiPropertiesType::PostgresColumnsNamesOnly)
+/// The input columns with the `columns` renames of the storage event applied
+pub fn generate_columns_names_only(
+    inputs: &[ABIInput],
+    storage_event: &StorageEvent,
+) -> Vec<String> {
+    generate_columns(
+        inputs,
+        &GenerateAbiPropertiesType::PostgresColumnsNamesOnly,
+        storage_event.is_tuples_as_jsonb(),
+    )
+    .into_iter()
+    .map(|column| storage_event.column_name(&column))
+    .collect()
 }
 
-pub fn generate_column_names_only_with_base_properties(inputs: &[ABIInput]) -> Vec<String> {
+pub fn generate_column_names_only_with_base_properties(
+    inputs: &[ABIInput],
+    storage_event: &StorageEvent,
+) -> Vec<String> {
     let mut column_names: Vec<String> = vec!["contract_address".to_string()];
-    column_names.extend(generate_columns_names_only(inputs));
+    column_names.extend(generate_columns_names_only(inputs, storage_event));
+    column_names.extend(storage_event.formatted_column_names(inputs));
+    column_names.extend(storage_event.computed_column_names());
+    column_names.extend(storage_event.raw_log_column_names());
     column_names.extend(vec![
         "tx_hash".to_string(),
         "block_number".to_string(),
@@ -39,37 +75,151 @@ pub fn generate_column_names_only_with_base_properties(inputs: &[ABIInput]) -> V
     column_names
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventTableColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+impl EventTableColumn {
+    fn new(name: &str, data_type: &str) -> Self {
+        EventTableColumn { name: name.to_string(), data_type: data_type.to_string() }
+    }
+
+    pub fn definition_sql(&self) -> String {
+        format!("\"{}\" {}", self.name, self.data_type)
+    }
+}
+
+/// All the columns of an event table in the order they are created
+pub fn generate_event_table_columns(
+    event_info: &EventInfo,
+    storage_event: &StorageEvent,
+) -> Vec<EventTableColumn> {
+    let tuples_as_jsonb = storage_event.is_tuples_as_jsonb();
+    let mut columns = vec![
+        EventTableColumn::new("rindexer_id", "SERIAL PRIMARY KEY NOT NULL"),
+        EventTableColumn::new("contract_address", "CHAR(66) NOT NULL"),
+    ];
+
+    let names = generate_columns(
+        &event_info.inputs,
+        &GenerateAbiPropertiesType::PostgresColumnsNamesOnly,
+        tuples_as_jsonb,
+    );
+    let definitions = generate_columns_with_data_types(&event_info.inputs, tuples_as_jsonb);
+    for (name, definition) in names.iter().zip(definitions.iter()) {
+        let data_type = definition
+            .strip_prefix(&format!("\"{}\" ", name))
+            .expect("Column definition should start with the column name");
+        columns.push(EventTableColumn::new(&storage_event.column_name(name), data_type));
+    }
+
+    // null when the decimals of the token could not be read
+    for name in storage_event.formatted_column_names(&event_info.inputs) {
+        columns.push(EventTableColumn::new(&name, "NUMERIC"));
+    }
+
+    // null when the expression could not be evaluated
+    for computed_column in storage_event.computed_columns() {
+        let data_type = match computed_column.type_ {
+            ComputedColumnType::Number => "NUMERIC",
+            ComputedColumnType::Bool => "BOOLEAN",
+            ComputedColumnType::String => "TEXT",
+        };
+        columns.push(EventTableColumn::new(&computed_column.name, data_type));
+    }
+
+    if storage_event.store_raw_log {
+        columns.push(EventTableColumn::new("topics", "BYTEA[]"));
+        columns.push(EventTableColumn::new("data", "BYTEA"));
+    }
+
+    columns.extend(vec![
+        EventTableColumn::new("tx_hash", "CHAR(66) NOT NULL"),
+        EventTableColumn::new("block_number", "NUMERIC NOT NULL"),
+        EventTableColumn::new("block_hash", "CHAR(66) NOT NULL"),
+        EventTableColumn::new("network", "VARCHAR(50) NOT NULL"),
+        EventTableColumn::new("tx_index", "NUMERIC NOT NULL"),
+        EventTableColumn::new("log_index", "VARCHAR(78) NOT NULL"),
+    ]);
+
+    columns
+}
+
+/// A partitioned table needs the partition key in its primary key
+pub fn generate_event_table_create_sql(
+    table_name: &str,
+    columns: &[EventTableColumn],
+    partitioning: Option<&PostgresPartitioning>,
+) -> String {
+    let Some(partitioning) = partitioning else {
+        return format!(
+            "CREATE TABLE IF NOT EXISTS {} ({});",
+            table_name,
+            columns.iter().map(|c| c.definition_sql()).collect::<Vec<_>>().join(", ")
+        );
+    };
+
+    let mut definitions: Vec<String> = columns
+        .iter()
+        .map(|c| match c.name.as_str() {
+            "rindexer_id" => {
+                EventTableColumn::new("rindexer_id", "SERIAL NOT NULL").definition_sql()
+            }
+            _ => c.definition_sql(),
+        })
+        .collect();
+    definitions
+        .push(format!("PRIMARY KEY (\"rindexer_id\", \"{}\")", partitioning.partition_key()));
+
+    let partition_by = match partitioning {
+        PostgresPartitioning::BlockNumber { .. } => "RANGE (\"block_number\")",
+        PostgresPartitioning::Network => "LIST (\"network\")",
+    };
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} ({}) PARTITION BY {};",
+        table_name,
+        definitions.join(", "),
+        partition_by
+    )
+}
+
 fn generate_event_table_sql_with_comments(
+    indexer_name: &str,
     abi_inputs: &[EventInfo],
-    contract_name: &str,
+    contract: &Contract,
     schema_name: &str,
     apply_full_name_comment_for_events: Vec<String>,
+    partitioning: Option<&PostgresPartitioning>,
 ) -> String {
     abi_inputs
         .iter()
         .map(|event_info| {
-            let table_name = format!("{}.{}", schema_name, camel_to_snake(&event_info.name));
+            let storage_event = contract.storage_event(&event_info.name);
+            let table_name = format!("{}.{}", schema_name, storage_event.event_table_name());
             info!("Creating table if not exists: {}", table_name);
-            let event_columns = if event_info.inputs.is_empty() {
-                "".to_string()
-            } else {
-                generate_columns_with_data_types(&event_info.inputs).join(", ") + ","
-            };
-
-            let create_table_sql = format!(
-                "CREATE TABLE IF NOT EXISTS {} (\
-                rindexer_id SERIAL PRIMARY KEY NOT NULL, \
-                contract_address CHAR(66) NOT NULL, \
-                {} \
-                tx_hash CHAR(66) NOT NULL, \
-                block_number NUMERIC NOT NULL, \
-                block_hash CHAR(66) NOT NULL, \
-                network VARCHAR(50) NOT NULL, \
-                tx_index NUMERIC NOT NULL, \
-                log_index VARCHAR(78) NOT NULL\
-            );",
-                table_name, event_columns
-            );
+            let columns = generate_event_table_columns(event_info, &storage_event);
+
+            let mut create_table_sql =
+                generate_event_table_create_sql(&table_name, &columns, partitioning);
+            create_table_sql.push_str(&generate_rollup_tables_sql(
+                schema_name,
+                &event_info.inputs,
+                &storage_event,
+            ));
+            create_table_sql.push_str(&generate_script_tables_sql(schema_name, &storage_event));
+
+            if partitioning == Some(&PostgresPartitioning::Network) {
+                for details in &contract.details {
+                    create_table_sql.push_str(&generate_network_partition_sql(
+                        indexer_name,
+                        &table_name,
+                        &details.network,
+                    ));
+                }
+            }
 
             if !apply_full_name_comment_for_events.contains(&event_info.name) {
                 return create_table_sql;
@@ -78,7 +228,7 @@ fn generate_event_table_sql_with_comments(
             // smart comments needed to avoid clashing of order by graphql names
             let table_comment = format!(
                 "COMMENT ON TABLE {} IS E'@name {}{}';",
-                table_name, contract_name, event_info.name
+                table_name, contract.name, event_info.name
             );
 
             format!("{}\n{}", create_table_sql, table_comment)
@@ -125,8 +275,8 @@ pub enum GenerateTablesForIndexerSqlError {
     ParamTypeError(#[from] ParamTypeError),
 }
 
-/// If any event names match the whole table name should be exposed differently on graphql
-/// to avoid clashing of graphql namings
+/// If any event tables have the same name in another contract the whole table name should be
+/// exposed differently on graphql to avoid clashing of graphql namings
 fn find_clashing_event_names(
     project_path: &Path,
     current_contract: &Contract,
@@ -145,7 +295,10 @@ fn find_clashing_event_names(
             ABIItem::extract_event_names_and_signatures_from_abi(other_abi_items)?;
 
         for event_name in current_event_names {
-            if other_event_names.iter().any(|e| e.name == event_name.name) &&
+            let table_name = current_contract.event_table_name(&event_name.name);
+            if other_event_names
+                .iter()
+                .any(|e| other_contract.event_table_name(&e.name) == table_name) &&
                 !clashing_events.contains(&event_name.name)
             {
                 clashing_events.push(event_name.name.clone());
@@ -159,8 +312,12 @@ fn find_clashing_event_names(
 pub fn generate_tables_for_indexer_sql(
     project_path: &Path,
     indexer: &Indexer,
+    global_partitioning: Option<&PostgresPartitioning>,
 ) -> Result<Code, GenerateTablesForIndexerSqlError> {
     let mut sql = "CREATE SCHEMA IF NOT EXISTS rindexer_internal;".to_string();
+    sql.push_str(&generate_partitions_table_sql(&indexer.name));
+    sql.push_str(&generate_retention_table_sql(&indexer.name));
+    sql.push_str(&generate_applied_events_table_sql(&indexer.name));
 
     for contract in &indexer.contracts {
         let contract_name = contract.before_modify_name_if_filter_readonly();
@@ -176,14 +333,20 @@ pub fn generate_tables_for_indexer_sql(
             find_clashing_event_names(project_path, contract, &indexer.contracts, &event_names)?;
 
         sql.push_str(&generate_event_table_sql_with_comments(
+            &indexer.name,
             &event_names,
-            &contract.name,
+            contract,
             &schema_name,
             event_matching_name_on_other,
+            contract.postgres_partitioning(global_partitioning),
         ));
         sql.push_str(&generate_internal_event_table_sql(&event_names, &schema_name, networks));
     }
 
+    if let Some(entities) = indexer.entities.as_ref().filter(|entities| !entities.is_empty()) {
+        sql.push_str(&generate_entity_tables_sql(&indexer.name, entities));
+    }
+
     sql.push_str(&format!(
         r#"
         CREATE TABLE IF NOT EXISTS rindexer_internal.{indexer_name}_last_known_relationship_dropping_sql (
@@ -207,19 +370,25 @@ pub fn generate_tables_for_indexer_sql(
     Ok(Code::new(sql))
 }
 
+/// Takes the event table name from `Contract::event_table_name` so any override is kept
 pub fn generate_event_table_full_name(
     indexer_name: &str,
     contract_name: &str,
-    event_name: &str,
+    event_table_name: &str,
 ) -> String {
     let schema_name = generate_indexer_contract_schema_name(indexer_name, contract_name);
-    format!("{}.{}", schema_name, camel_to_snake(event_name))
+    format!("{}.{}", schema_name, event_table_name)
 }
 
 pub fn generate_event_table_columns_names_sql(column_names: &[String]) -> String {
     column_names.iter().map(|name| format!("\"{}\"", name)).collect::<Vec<String>>().join(", ")
 }
 
+/// Holds the columns each event table was last created or migrated with
+pub fn generate_applied_schemas_table_name(indexer_name: &str) -> String {
+    format!("rindexer_internal.{}_applied_schemas", camel_to_snake(indexer_name))
+}
+
 pub fn generate_indexer_contract_schema_name(indexer_name: &str, contract_name: &str) -> String {
     format!("{}_{}", camel_to_snake(indexer_name), camel_to_snake(contract_name))
 }
@@ -229,7 +398,27 @@ pub fn drop_tables_for_indexer_sql(project_path: &Path, indexer: &Indexer) -> Co
         "DROP TABLE IF EXISTS rindexer_internal.{}_last_known_indexes_dropping_sql CASCADE;",
         camel_to_snake(&indexer.name)
     );
+    sql.push_str(&format!(
+        "DROP TABLE IF EXISTS {} CASCADE;",
+        generate_applied_schemas_table_name(&indexer.name)
+    ));
+    sql.push_str(&format!(
+        "DROP TABLE IF EXISTS {} CASCADE;",
+        generate_partitions_table_name(&indexer.name)
+    ));
+    sql.push_str(&format!(
+        "DROP TABLE IF EXISTS {} CASCADE;",
+        generate_retention_table_name(&indexer.name)
+    ));
+    sql.push_str(&format!(
+        "DROP TABLE IF EXISTS {} CASCADE;",
+        generate_applied_events_table_name(&indexer.name)
+    ));
     sql.push_str(format!("DROP TABLE IF EXISTS rindexer_internal.{}_last_known_relationship_dropping_sql CASCADE;", camel_to_snake(&indexer.name)).as_str());
+    sql.push_str(&format!(
+        "DROP SCHEMA IF EXISTS {} CASCADE;",
+        generate_entities_schema_name(&indexer.name)
+    ));
 
     for contract in &indexer.contracts {
         let contract_name = contract.before_modify_name_if_filter_readonly();
@@ -267,13 +456,9 @@ pub fn solidity_type_to_db_type(abi_type: &str) -> String {
         "bool" => "BOOLEAN",
         "string" => "TEXT",
         t if t.starts_with("bytes") => "BYTEA",
-        t if t.starts_with("int") || t.starts_with("uint") => {
-            // Handling fixed-size integers (intN and uintN where N can be 8 to 256 in steps of 8)
-            let (prefix, size): (&str, usize) = if t.starts_with("int") {
-                ("int", t[3..].parse().expect("Invalid intN type"))
-            } else {
-                ("uint", t[4..].parse().expect("Invalid uintN type"))
-            };
+        t if t.starts_with("uint") => {
+            // Handling fixed-size integers (uintN where N can be 8 to 256 in steps of 8)
+            let size: usize = t[4..].parse().expect("Invalid uintN type");
 
             match size {
                 8 | 16 => "SMALLINT",
@@ -281,7 +466,21 @@ pub fn solidity_type_to_db_type(abi_type: &str) -> String {
                 40 | 48 | 56 | 64 | 72 | 80 | 88 | 96 | 104 | 112 | 120 | 128 => "NUMERIC",
                 136 | 144 | 152 | 160 | 168 | 176 | 184 | 192 | 200 | 208 | 216 | 224 | 232 |
                 240 | 248 | 256 => "VARCHAR(78)",
-                _ => panic!("Unsupported {}N size: {}", prefix, size),
+                _ => panic!("Unsupported uintN size: {}", size),
+            }
+        }
+        t if t.starts_with("int") => {
+            // Handling fixed-size signed integers (intN where N can be 8 to 256 in steps of 8),
+            // NUMERIC is used for anything above int64 so negative values keep their sign
+            let size: usize = t[3..].parse().expect("Invalid intN type");
+
+            match size {
+                8 | 16 => "SMALLINT",
+                24 | 32 => "INTEGER",
+                40 | 48 | 56 | 64 => "BIGINT",
+                72 | 80 | 88 | 96 | 104 | 112 | 120 | 128 | 136 | 144 | 152 | 160 | 168 | 176 |
+                184 | 192 | 200 | 208 | 216 | 224 | 232 | 240 | 248 | 256 => "NUMERIC",
+                _ => panic!("Unsupported intN size: {}", size),
             }
         }
         _ => panic!("Unsupported type: {}", base_type),
@@ -300,3 +499,162 @@ pub fn solidity_type_to_db_type(abi_type: &str) -> String {
         sql_type.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partitioned_event_table_create_sql() {
+        let columns = vec![
+            EventTableColumn::new("rindexer_id", "SERIAL PRIMARY KEY NOT NULL"),
+            EventTableColumn::new("block_number", "NUMERIC NOT NULL"),
+        ];
+
+        assert_eq!(
+            generate_event_table_create_sql("indexer_erc20.transfer", &columns, None),
+            r#"CREATE TABLE IF NOT EXISTS indexer_erc20.transfer ("rindexer_id" SERIAL PRIMARY KEY NOT NULL, "block_number" NUMERIC NOT NULL);"#
+        );
+        assert_eq!(
+            generate_event_table_create_sql(
+                "indexer_erc20.transfer",
+                &columns,
+                Some(&PostgresPartitioning::BlockNumber { blocks_per_partition: 100 })
+            ),
+            r#"CREATE TABLE IF NOT EXISTS indexer_erc20.transfer ("rindexer_id" SERIAL NOT NULL, "block_number" NUMERIC NOT NULL, PRIMARY KEY ("rindexer_id", "block_number")) PARTITION BY RANGE ("block_number");"#
+        );
+    }
+
+    #[test]
+    fn test_event_table_columns_with_renames() {
+        let input = |name: &str, type_: &str| ABIInput {
+            indexed: None,
+            name: name.to_string(),
+            type_: type_.to_string(),
+            components: None,
+        };
+        let inputs = vec![input("from", "address"), input("param_1", "uint256")];
+        let event_info = EventInfo::new(
+            ABIItem {
+                inputs: inputs.clone(),
+                name: "Transfer".to_string(),
+                type_: "event".to_string(),
+            },
+            "address,uint256".to_string(),
+        );
+
+        let storage_event: StorageEvent = serde_yaml::from_str(
+            r#"
+            event_name: Transfer
+            table_name: token_transfers
+            columns:
+              from: sender
+              param_1: amount
+            format:
+              param_1:
+                decimals: 6
+            "#,
+        )
+        .unwrap();
+        assert_eq!(storage_event.event_table_name(), "token_transfers");
+        assert!(storage_event.validate_naming(&inputs).is_ok());
+
+        let columns: Vec<String> = generate_event_table_columns(&event_info, &storage_event)
+            .into_iter()
+            .map(|column| column.name)
+            .collect();
+        assert_eq!(columns[2..5], ["sender", "amount", "amount_formatted"]);
+        assert_eq!(
+            generate_column_names_only_with_base_properties(&inputs, &storage_event)[1..4],
+            ["sender", "amount", "amount_formatted"]
+        );
+
+        let clashing: StorageEvent =
+            serde_yaml::from_str("event_name: Transfer\ncolumns:\n  from: network").unwrap();
+        assert!(clashing.validate_naming(&inputs).is_err());
+    }
+
+    #[test]
+    fn test_event_table_columns_with_raw_log() {
+        let input = |name: &str| ABIInput {
+            indexed: None,
+            name: name.to_string(),
+            type_: "uint256".to_string(),
+            components: None,
+        };
+        let inputs = vec![input("value")];
+        let event_info = EventInfo::new(
+            ABIItem {
+                inputs: inputs.clone(),
+                name: "Deposit".to_string(),
+                type_: "event".to_string(),
+            },
+            "uint256".to_string(),
+        );
+        let storage_event = StorageEvent { store_raw_log: true, ..StorageEvent::new("Deposit") };
+
+        let columns = generate_event_table_columns(&event_info, &storage_event);
+        assert_eq!(columns[3], EventTableColumn::new("topics", "BYTEA[]"));
+        assert_eq!(columns[4], EventTableColumn::new("data", "BYTEA"));
+        assert_eq!(
+            generate_column_names_only_with_base_properties(&inputs, &storage_event)[1..4],
+            ["value", "topics", "data"]
+        );
+
+        // an input named like a raw log column would clash
+        assert!(storage_event.validate_naming(&[input("data")]).is_err());
+    }
+
+    #[test]
+    fn test_event_table_columns_with_computed_columns() {
+        let input = |name: &str| ABIInput {
+            indexed: None,
+            name: name.to_string(),
+            type_: "uint256".to_string(),
+            components: None,
+        };
+        let inputs = vec![input("shares"), input("assets")];
+        let event_info = EventInfo::new(
+            ABIItem {
+                inputs: inputs.clone(),
+                name: "Swap".to_string(),
+                type_: "event".to_string(),
+            },
+            "uint256,uint256".to_string(),
+        );
+        let storage_event: StorageEvent = serde_yaml::from_str(
+            r#"
+            event_name: Swap
+            computed_columns:
+              - name: price
+                expression: assets * 1e18 / shares
+                type: number
+                decimals: 18
+              - name: is_large
+                expression: shares > 1e18
+                type: bool
+            "#,
+        )
+        .unwrap();
+        let storage_event = StorageEvent { store_raw_log: true, ..storage_event };
+
+        let columns = generate_event_table_columns(&event_info, &storage_event);
+        assert_eq!(columns[4], EventTableColumn::new("price", "NUMERIC"));
+        assert_eq!(columns[5], EventTableColumn::new("is_large", "BOOLEAN"));
+        assert_eq!(
+            generate_column_names_only_with_base_properties(&inputs, &storage_event)[1..6],
+            ["shares", "assets", "price", "is_large", "topics"]
+        );
+        assert!(storage_event.validate_naming(&inputs).is_ok());
+
+        let invalid = |computed_column: &str| {
+            let mut storage_event = storage_event.clone();
+            storage_event.computed_columns =
+                Some(vec![serde_yaml::from_str(computed_column).unwrap()]);
+            storage_event.validate_naming(&inputs).is_err()
+        };
+        assert!(invalid("{ name: shares, expression: assets, type: number }"));
+        assert!(invalid("{ name: ratio, expression: assets /, type: number }"));
+        assert!(invalid("{ name: flag, expression: assets > 0, type: bool, decimals: 2 }"));
+    }
+}
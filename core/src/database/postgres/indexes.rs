@@ -5,9 +5,15 @@ use tracing::info;
 
 use crate::{
     abi::{get_abi_item_with_db_map, ABIItem, GetAbiItemWithDbMapError, ReadAbiError},
-    database::postgres::client::{PostgresClient, PostgresConnectionError, PostgresError},
+    database::postgres::{
+        client::{PostgresClient, PostgresConnectionError, PostgresError},
+        generate::generate_event_table_full_name,
+    },
     helpers::camel_to_snake,
-    manifest::{contract::Contract, storage::PostgresIndexes},
+    manifest::{
+        contract::Contract,
+        storage::{PostgresIndexes, PostgresPartitioning},
+    },
     types::code::Code,
 };
 
@@ -15,6 +21,8 @@ use crate::{
 pub struct PostgresIndexResult {
     db_table_name: String,
     db_table_columns: Vec<String>,
+    /// Indexes can not be created or dropped concurrently on partitioned tables
+    partitioned: bool,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -37,9 +45,10 @@ impl PostgresIndexResult {
         // CONCURRENTLY is used to avoid locking the table for writes
         Code::new(format!(
             r#"
-                CREATE INDEX CONCURRENTLY {index_name}
+                CREATE INDEX {concurrently}{index_name}
                 ON {db_table_name} ({db_table_columns});
             "#,
+            concurrently = self.concurrently(),
             index_name = self.index_name(),
             db_table_name = self.db_table_name,
             db_table_columns = self.db_table_columns.join(", "),
@@ -55,7 +64,8 @@ impl PostgresIndexResult {
 
         Code::new(format!(
             // CONCURRENTLY is used to avoid locking the table for writes
-            "DROP INDEX CONCURRENTLY IF EXISTS {}.{};",
+            "DROP INDEX {}IF EXISTS {}.{};",
+            self.concurrently(),
             // get schema else drop won't work
             self.db_table_name.split('.').next().unwrap_or_else(|| panic!(
                 "Failed to split and then get schema for table: {}",
@@ -65,6 +75,14 @@ impl PostgresIndexResult {
         ))
     }
 
+    fn concurrently(&self) -> &'static str {
+        if self.partitioned {
+            ""
+        } else {
+            "CONCURRENTLY "
+        }
+    }
+
     pub fn index_name(&self) -> String {
         format!(
             "idx_{db_table_name}_{db_table_columns}",
@@ -197,6 +215,7 @@ pub async fn prepare_indexes(
     manifest_name: &str,
     postgres_indexes: &PostgresIndexes,
     contracts: &[Contract],
+    global_partitioning: Option<&PostgresPartitioning>,
 ) -> Result<Vec<PostgresIndexResult>, PrepareIndexesError> {
     let mut index_results: Vec<PostgresIndexResult> = vec![];
     let mut dropping_sql: Vec<Code> = vec![];
@@ -206,19 +225,20 @@ pub async fn prepare_indexes(
     if let Some(global_injected_parameters) = &postgres_indexes.global_injected_parameters {
         for contract in contracts {
             let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+            let partitioned = contract.postgres_partitioning(global_partitioning).is_some();
 
             for abi_item in abi_items {
-                let db_table_name = format!(
-                    "{}_{}.{}",
-                    camel_to_snake(manifest_name),
-                    camel_to_snake(&contract.name),
-                    camel_to_snake(&abi_item.name)
+                let db_table_name = generate_event_table_full_name(
+                    manifest_name,
+                    &contract.name,
+                    &contract.event_table_name(&abi_item.name),
                 );
 
                 for global_parameter_column_name in global_injected_parameters {
                     let index_result = PostgresIndexResult {
                         db_table_name: db_table_name.clone(),
                         db_table_columns: vec![global_parameter_column_name.clone()],
+                        partitioned,
                     };
                     dropping_sql.push(index_result.drop_index_sql());
                     index_results.push(index_result);
@@ -240,20 +260,21 @@ pub async fn prepare_indexes(
                 }
                 Some(contract) => {
                     let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+                    let partitioned = contract.postgres_partitioning(global_partitioning).is_some();
 
                     if let Some(injected_parameters) = &contract_event_indexes.injected_parameters {
                         for abi_item in &abi_items {
-                            let db_table_name = format!(
-                                "{}_{}.{}",
-                                camel_to_snake(manifest_name),
-                                camel_to_snake(&contract.name),
-                                camel_to_snake(&abi_item.name)
+                            let db_table_name = generate_event_table_full_name(
+                                manifest_name,
+                                &contract.name,
+                                &contract.event_table_name(&abi_item.name),
                             );
 
                             for injected_parameter in injected_parameters {
                                 let index_result = PostgresIndexResult {
                                     db_table_name: db_table_name.clone(),
                                     db_table_columns: vec![injected_parameter.clone()],
+                                    partitioned,
                                 };
                                 dropping_sql.push(index_result.drop_index_sql());
                                 index_results.push(index_result);
@@ -262,11 +283,11 @@ pub async fn prepare_indexes(
                     }
 
                     for event_indexes in &contract_event_indexes.events {
-                        let db_table_name = format!(
-                            "{}_{}.{}",
-                            camel_to_snake(manifest_name),
-                            camel_to_snake(&contract.name),
-                            camel_to_snake(&event_indexes.name)
+                        let storage_event = contract.storage_event(&event_indexes.name);
+                        let db_table_name = generate_event_table_full_name(
+                            manifest_name,
+                            &contract.name,
+                            &storage_event.event_table_name(),
                         );
 
                         if let Some(injected_parameters) = &event_indexes.injected_parameters {
@@ -274,6 +295,7 @@ pub async fn prepare_indexes(
                                 let index_result = PostgresIndexResult {
                                     db_table_name: db_table_name.clone(),
                                     db_table_columns: vec![injected_parameter.clone()],
+                                    partitioned,
                                 };
                                 dropping_sql.push(index_result.drop_index_sql());
                                 index_results.push(index_result);
@@ -285,7 +307,7 @@ pub async fn prepare_indexes(
                             for parameter in &index.event_input_names {
                                 let abi_parameter = get_abi_item_with_db_map(
                                     &abi_items,
-                                    &event_indexes.name,
+                                    &storage_event,
                                     &parameter.split('.').collect::<Vec<&str>>(),
                                 )?;
                                 db_table_columns.push(abi_parameter.db_column_name);
@@ -294,6 +316,7 @@ pub async fn prepare_indexes(
                             let index_result = PostgresIndexResult {
                                 db_table_name: db_table_name.clone(),
                                 db_table_columns,
+                                partitioned,
                             };
                             dropping_sql.push(index_result.drop_index_sql());
                             index_results.push(index_result);
@@ -0,0 +1,758 @@
+use std::{fmt, path::Path};
+
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    abi::ABIItem,
+    database::postgres::{
+        applied_events::generate_applied_events_table_sql,
+        client::{PostgresClient, PostgresError},
+        generate::{
+            generate_applied_schemas_table_name, generate_event_table_columns,
+            generate_indexer_contract_schema_name, EventTableColumn,
+            GenerateTablesForIndexerSqlError,
+        },
+        partitions::{
+            generate_partition_name, generate_partitions_table_name, generate_partitions_table_sql,
+        },
+        retention::{generate_retention_table_name, generate_retention_table_sql},
+    },
+    helpers::camel_to_snake,
+    indexer::Indexer,
+    manifest::storage::PostgresPartitioning,
+};
+
+#[derive(Debug)]
+pub struct EventTableSchema {
+    pub table_name: String,
+    /// The rindexer_internal table holding the last synced block for the event
+    pub internal_table_name: String,
+    pub columns: Vec<EventTableColumn>,
+    pub partition_by: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SchemaChange {
+    AddColumn(EventTableColumn),
+    DropColumn(EventTableColumn),
+    ChangeColumnType { name: String, from: String, to: String },
+    ChangePartitioning { from: Option<String>, to: Option<String> },
+}
+
+impl SchemaChange {
+    /// Breaking changes can not be applied without losing data or rewriting existing rows
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            // existing rows have no value to put in a NOT NULL column
+            SchemaChange::AddColumn(column) => column.data_type.contains("NOT NULL"),
+            SchemaChange::ChangeColumnType { from, to, .. } => {
+                column_type_conversion(from, to).is_none()
+            }
+            // rows can not be moved in or out of a partitioned table in place
+            SchemaChange::DropColumn(_) | SchemaChange::ChangePartitioning { .. } => true,
+        }
+    }
+}
+
+/// Signed integers used to be stored as the decimal of their 256 bit two's complement, these
+/// read them back with their sign while the column is converted
+const SIGNED_INTEGER_FUNCTIONS_SQL: &str = r#"
+CREATE OR REPLACE FUNCTION pg_temp.rindexer_signed_integer(value TEXT) RETURNS NUMERIC AS $$
+    SELECT CASE
+        WHEN value::NUMERIC >= 2::NUMERIC ^ 255 THEN value::NUMERIC - 2::NUMERIC ^ 256
+        ELSE value::NUMERIC
+    END
+$$ LANGUAGE SQL IMMUTABLE;
+CREATE OR REPLACE FUNCTION pg_temp.rindexer_signed_integers(value TEXT[]) RETURNS NUMERIC[] AS $$
+    SELECT CASE WHEN value IS NULL THEN NULL ELSE COALESCE(
+        (SELECT array_agg(pg_temp.rindexer_signed_integer(v) ORDER BY i) FROM unnest(value) WITH ORDINALITY AS t(v, i)),
+        '{}'
+    ) END
+$$ LANGUAGE SQL IMMUTABLE;"#;
+
+/// The `USING` expression converting a column in place when the type changed because signed
+/// integers were widened, `int40` to `int64` moved from NUMERIC to BIGINT and `int136` to
+/// `int256` from VARCHAR(78) to NUMERIC. Values which did not fit BIGINT could never be indexed.
+fn column_type_conversion(from: &str, to: &str) -> Option<&'static str> {
+    // live array types have no length
+    let from = match (from.find('('), from.find(')')) {
+        (Some(start), Some(end)) => format!("{}{}", &from[..start], &from[end + 1..]),
+        _ => from.to_string(),
+    };
+
+    match (from.as_str(), to) {
+        ("NUMERIC", "BIGINT") => Some("{column}::BIGINT"),
+        ("NUMERIC[]", "BIGINT[]") => Some("{column}::BIGINT[]"),
+        ("VARCHAR", "NUMERIC") => Some("pg_temp.rindexer_signed_integer({column})"),
+        ("VARCHAR[]", "NUMERIC[]") => Some("pg_temp.rindexer_signed_integers({column})"),
+        _ => None,
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::AddColumn(column) => {
+                write!(f, "column `{}` added as {}", column.name, column.data_type)
+            }
+            SchemaChange::DropColumn(column) => write!(f, "column `{}` removed", column.name),
+            SchemaChange::ChangeColumnType { name, from, to } => {
+                write!(f, "column `{}` changed from {} to {}", name, from, to)
+            }
+            SchemaChange::ChangePartitioning { from, to } => write!(
+                f,
+                "partitioning changed from {} to {}",
+                from.as_deref().unwrap_or("none"),
+                to.as_deref().unwrap_or("none")
+            ),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SchemaMigrationError {
+    #[error("{0}")]
+    GenerateTablesForIndexerSqlError(#[from] GenerateTablesForIndexerSqlError),
+
+    #[error("{0}")]
+    PostgresError(#[from] PostgresError),
+
+    #[error("Could not read the applied schema for table {0}: {1}")]
+    InvalidAppliedSchema(String, serde_json::Error),
+
+    #[error("Table {0} no longer matches the ABI and can not be migrated without losing data: {1} - start the indexer with `--allow-destructive` to drop the table and resync the event")]
+    BreakingSchemaChange(String, String),
+}
+
+pub fn generate_event_table_schemas(
+    project_path: &Path,
+    indexer: &Indexer,
+    global_partitioning: Option<&PostgresPartitioning>,
+) -> Result<Vec<EventTableSchema>, GenerateTablesForIndexerSqlError> {
+    let mut schemas = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
+        let schema_name = generate_indexer_contract_schema_name(&indexer.name, &contract_name);
+        let partition_by =
+            contract.postgres_partitioning(global_partitioning).map(|p| p.describe());
+
+        for event_info in &event_names {
+            let event_name = camel_to_snake(&event_info.name);
+            let storage_event = contract.storage_event(&event_info.name);
+            schemas.push(EventTableSchema {
+                table_name: format!("{}.{}", schema_name, storage_event.event_table_name()),
+                internal_table_name: format!("rindexer_internal.{}_{}", schema_name, event_name),
+                columns: generate_event_table_columns(event_info, &storage_event),
+                partition_by: partition_by.clone(),
+            });
+        }
+    }
+
+    Ok(schemas)
+}
+
+/// Column order is ignored as inserts always name the columns they write
+pub fn diff_event_table_columns(
+    applied: &[EventTableColumn],
+    current: &[EventTableColumn],
+) -> Vec<SchemaChange> {
+    let mut changes = vec![];
+
+    for column in current {
+        match applied.iter().find(|a| a.name == column.name) {
+            None => changes.push(SchemaChange::AddColumn(column.clone())),
+            Some(applied_column) if applied_column.data_type != column.data_type => {
+                changes.push(SchemaChange::ChangeColumnType {
+                    name: column.name.clone(),
+                    from: applied_column.data_type.clone(),
+                    to: column.data_type.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    for column in applied {
+        if !current.iter().any(|c| c.name == column.name) {
+            changes.push(SchemaChange::DropColumn(column.clone()));
+        }
+    }
+
+    changes
+}
+
+/// The type of a live column in the form the generated columns use, `information_schema` does
+/// not keep the length of array elements so arrays are compared without it
+fn live_column_data_type(
+    data_type: &str,
+    udt_name: &str,
+    max_length: Option<i32>,
+    nullable: bool,
+) -> String {
+    let base_type = |name: &str| match name {
+        "character" | "bpchar" => "CHAR".to_string(),
+        "character varying" | "varchar" => "VARCHAR".to_string(),
+        "smallint" | "int2" => "SMALLINT".to_string(),
+        "integer" | "int4" => "INTEGER".to_string(),
+        "bigint" | "int8" => "BIGINT".to_string(),
+        "boolean" | "bool" => "BOOLEAN".to_string(),
+        other => other.to_uppercase(),
+    };
+
+    let data_type = match (data_type, max_length) {
+        ("ARRAY", _) => format!("{}[]", base_type(udt_name.trim_start_matches('_'))),
+        (data_type, Some(max_length)) => format!("{}({})", base_type(data_type), max_length),
+        (data_type, None) => base_type(data_type),
+    };
+
+    if nullable {
+        data_type
+    } else {
+        format!("{} NOT NULL", data_type)
+    }
+}
+
+/// The generated type in the form `live_column_data_type` reads it back from postgres
+fn comparable_data_type(data_type: &str) -> String {
+    if data_type.starts_with("SERIAL") {
+        return "INTEGER NOT NULL".to_string();
+    }
+
+    match (data_type.find('('), data_type.find(")[]")) {
+        (Some(start), Some(end)) => format!("{}{}", &data_type[..start], &data_type[end + 1..]),
+        _ => data_type.to_string(),
+    }
+}
+
+/// Tables created before schemas were recorded are diffed against their columns in postgres,
+/// added columns keep the definition they are created with
+pub fn diff_live_event_table_columns(
+    live: &[EventTableColumn],
+    current: &[EventTableColumn],
+) -> Vec<SchemaChange> {
+    let comparable: Vec<EventTableColumn> = current
+        .iter()
+        .map(|column| EventTableColumn {
+            name: column.name.clone(),
+            data_type: comparable_data_type(&column.data_type),
+        })
+        .collect();
+
+    diff_event_table_columns(live, &comparable)
+        .into_iter()
+        .map(|change| match change {
+            SchemaChange::AddColumn(column) => SchemaChange::AddColumn(
+                current.iter().find(|c| c.name == column.name).cloned().unwrap_or(column),
+            ),
+            change => change,
+        })
+        .collect()
+}
+
+/// The columns and partition key of a table in postgres, none when the table does not exist
+async fn read_live_event_table(
+    client: &PostgresClient,
+    table_name: &str,
+) -> Result<Option<(Vec<EventTableColumn>, Option<String>)>, PostgresError> {
+    let (schema_name, table_name) = table_name.split_once('.').unwrap_or(("public", table_name));
+
+    let rows = client
+        .query(
+            r#"
+            SELECT column_name::TEXT, data_type::TEXT, udt_name::TEXT,
+                   character_maximum_length::INT4, is_nullable::TEXT
+            FROM information_schema.columns
+            WHERE table_schema = $1 AND table_name = $2
+            ORDER BY ordinal_position
+            "#,
+            &[&schema_name, &table_name],
+        )
+        .await?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let columns = rows
+        .iter()
+        .map(|row| EventTableColumn {
+            name: row.get(0),
+            data_type: live_column_data_type(
+                row.get(1),
+                row.get(2),
+                row.get(3),
+                row.get::<_, &str>(4) == "YES",
+            ),
+        })
+        .collect();
+
+    let partition_by: Option<String> = client
+        .query(
+            r#"
+            SELECT pg_get_partkeydef(c.oid)
+            FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1 AND c.relname = $2
+            "#,
+            &[&schema_name, &table_name],
+        )
+        .await?
+        .first()
+        .and_then(|row| row.get(0));
+
+    Ok(Some((columns, partition_by)))
+}
+
+fn generate_migration_sql(
+    schema: &EventTableSchema,
+    changes: &[SchemaChange],
+    allow_destructive: bool,
+    partitions_table_name: &str,
+) -> Result<String, SchemaMigrationError> {
+    let breaking: Vec<String> =
+        changes.iter().filter(|c| c.is_breaking()).map(|c| c.to_string()).collect();
+
+    if breaking.is_empty() {
+        let mut sql = vec![];
+        for change in changes {
+            match change {
+                SchemaChange::AddColumn(column) => {
+                    info!("Migrating table {}: {}", schema.table_name, change);
+                    sql.push(format!(
+                        "ALTER TABLE IF EXISTS {} ADD COLUMN IF NOT EXISTS {};",
+                        schema.table_name,
+                        column.definition_sql()
+                    ));
+                }
+                SchemaChange::ChangeColumnType { name, from, to } => {
+                    let Some(conversion) = column_type_conversion(from, to) else { continue };
+                    info!("Migrating table {}: {}", schema.table_name, change);
+                    if conversion.starts_with("pg_temp.") &&
+                        !sql.iter().any(|s| s == SIGNED_INTEGER_FUNCTIONS_SQL)
+                    {
+                        sql.insert(0, SIGNED_INTEGER_FUNCTIONS_SQL.to_string());
+                    }
+                    let column = format!("\"{}\"", name);
+                    sql.push(format!(
+                        "ALTER TABLE IF EXISTS {} ALTER COLUMN {} TYPE {} USING {};",
+                        schema.table_name,
+                        column,
+                        to,
+                        conversion.replace("{column}", &column)
+                    ));
+                }
+                _ => {}
+            }
+        }
+        return Ok(sql.join("\n"));
+    }
+
+    if !allow_destructive {
+        return Err(SchemaMigrationError::BreakingSchemaChange(
+            schema.table_name.clone(),
+            breaking.join(", "),
+        ));
+    }
+
+    // dropping the last synced table as well means the event resyncs from the start block once
+    // the tables are created again, the partitions are dropped with the table
+    warn!(
+        "Dropping table {} to resync as `--allow-destructive` is set: {}",
+        schema.table_name,
+        breaking.join(", ")
+    );
+    Ok(format!(
+        "DROP TABLE IF EXISTS {table_name} CASCADE;\nDROP TABLE IF EXISTS {} CASCADE;\nDELETE FROM {} WHERE \"table_name\" = '{table_name}';",
+        schema.internal_table_name,
+        partitions_table_name,
+        table_name = schema.table_name,
+    ))
+}
+
+/// Renames an event table whose `table_name` changed along with its partitions, the last synced
+/// block is kept by event so indexing carries on where the old table stopped
+async fn generate_rename_sql(
+    client: &PostgresClient,
+    indexer_name: &str,
+    from: &str,
+    to: &str,
+) -> Result<String, PostgresError> {
+    let unqualified = |table_name: &str| -> String {
+        table_name.split_once('.').map_or(table_name, |(_, name)| name).to_string()
+    };
+    let partitions_table = generate_partitions_table_name(indexer_name);
+
+    let mut sql = vec![format!("ALTER TABLE {} RENAME TO {};", from, unqualified(to))];
+
+    let partitions = client
+        .query(
+            &format!(
+                "SELECT \"partition_name\", COALESCE(\"network\", \"from_block\"::TEXT) FROM {} WHERE \"table_name\" = $1",
+                partitions_table
+            ),
+            &[&from],
+        )
+        .await?;
+    for partition in partitions {
+        let partition_name: String = partition.get(0);
+        let renamed = generate_partition_name(to, partition.get(1));
+        sql.push(format!(
+            "ALTER TABLE {} RENAME TO {};\nUPDATE {} SET \"partition_name\" = '{}', \"table_name\" = '{}' WHERE \"partition_name\" = '{}';",
+            partition_name,
+            unqualified(&renamed),
+            partitions_table,
+            renamed,
+            to,
+            partition_name
+        ));
+    }
+
+    sql.push(format!(
+        "UPDATE {} SET \"table_name\" = '{}' WHERE \"table_name\" = '{}';\nDELETE FROM {} WHERE \"table_name\" = '{}';",
+        generate_retention_table_name(indexer_name),
+        to,
+        from,
+        generate_applied_schemas_table_name(indexer_name),
+        from
+    ));
+
+    Ok(sql.join("\n"))
+}
+
+/// A table which no longer exists while its event has synced blocks was dropped or renamed
+/// without rindexer knowing the old name, it is indexed again from the start block
+async fn reset_orphaned_last_synced(
+    client: &PostgresClient,
+    schema: &EventTableSchema,
+) -> Result<Option<String>, PostgresError> {
+    let has_internal_table: bool = client
+        .query_one("SELECT to_regclass($1) IS NOT NULL", &[&schema.internal_table_name])
+        .await?
+        .get(0);
+    if !has_internal_table {
+        return Ok(None);
+    }
+
+    let synced: i64 = client
+        .query_one(
+            &format!(
+                "SELECT COUNT(*) FROM {} WHERE \"last_synced_block\" > 0",
+                schema.internal_table_name
+            ),
+            &[],
+        )
+        .await?
+        .get(0);
+    if synced == 0 {
+        return Ok(None);
+    }
+
+    warn!(
+        "Table {} does not exist but its event has already been indexed, the old table was renamed or dropped without rindexer knowing - the event is indexed again from its start block",
+        schema.table_name
+    );
+    Ok(Some(format!("UPDATE {} SET \"last_synced_block\" = 0;", schema.internal_table_name)))
+}
+
+/// Diffs the current event tables against the schemas they were last applied with and migrates
+/// them, this must run before the tables are created so dropped tables are created again.
+pub async fn migrate_event_tables(
+    client: &PostgresClient,
+    project_path: &Path,
+    indexer: &Indexer,
+    allow_destructive: bool,
+    global_partitioning: Option<&PostgresPartitioning>,
+) -> Result<Vec<EventTableSchema>, SchemaMigrationError> {
+    let applied_schemas_table_name = generate_applied_schemas_table_name(&indexer.name);
+    let partitions_table_name = generate_partitions_table_name(&indexer.name);
+    client
+        .batch_execute(&format!(
+            r#"
+            CREATE SCHEMA IF NOT EXISTS rindexer_internal;
+            CREATE TABLE IF NOT EXISTS {applied_schemas_table_name} (
+                "table_name" TEXT PRIMARY KEY,
+                "columns" JSONB NOT NULL
+            );
+            ALTER TABLE {applied_schemas_table_name} ADD COLUMN IF NOT EXISTS "partition_by" TEXT;
+            ALTER TABLE {applied_schemas_table_name} ADD COLUMN IF NOT EXISTS "internal_table_name" TEXT;
+            {}
+            {}
+            {}
+        "#,
+            generate_partitions_table_sql(&indexer.name),
+            generate_retention_table_sql(&indexer.name),
+            generate_applied_events_table_sql(&indexer.name)
+        ))
+        .await?;
+
+    let rows = client
+        .query(
+            &format!(
+                r#"SELECT "table_name", "columns", "partition_by", "internal_table_name" FROM {}"#,
+                applied_schemas_table_name
+            ),
+            &[],
+        )
+        .await?;
+
+    let schemas = generate_event_table_schemas(project_path, indexer, global_partitioning)?;
+
+    let mut sql = vec![];
+    for schema in &schemas {
+        let mut applied = rows.iter().find(|row| row.get::<_, String>(0) == schema.table_name);
+
+        // a table recorded for the same event which is no longer used had its `table_name` changed
+        if applied.is_none() && read_live_event_table(client, &schema.table_name).await?.is_none() {
+            let renamed_from = rows.iter().find(|row| {
+                let table_name: String = row.get(0);
+                row.get::<_, Option<String>>(3).as_ref() == Some(&schema.internal_table_name) &&
+                    !schemas.iter().any(|s| s.table_name == table_name)
+            });
+            if let Some(renamed_from) = renamed_from {
+                let from: String = renamed_from.get(0);
+                if read_live_event_table(client, &from).await?.is_some() {
+                    info!(
+                        "Renaming table {} to {} as its table_name changed",
+                        from, schema.table_name
+                    );
+                    sql.push(
+                        generate_rename_sql(client, &indexer.name, &from, &schema.table_name)
+                            .await?,
+                    );
+                    applied = Some(renamed_from);
+                }
+            }
+        }
+
+        let changes = match applied {
+            Some(applied) => {
+                let applied_columns: Vec<EventTableColumn> =
+                    serde_json::from_value(applied.get::<_, Value>(1)).map_err(|e| {
+                        SchemaMigrationError::InvalidAppliedSchema(schema.table_name.clone(), e)
+                    })?;
+
+                let mut changes = diff_event_table_columns(&applied_columns, &schema.columns);
+                let applied_partition_by: Option<String> = applied.get(2);
+                if applied_partition_by != schema.partition_by {
+                    changes.push(SchemaChange::ChangePartitioning {
+                        from: applied_partition_by,
+                        to: schema.partition_by.clone(),
+                    });
+                }
+                changes
+            }
+            None => match read_live_event_table(client, &schema.table_name).await? {
+                Some((live_columns, live_partition_by)) => {
+                    let mut changes = diff_live_event_table_columns(&live_columns, &schema.columns);
+                    // postgres only knows the partition key, not how many blocks a partition holds
+                    let same_partitioning = match (&live_partition_by, &schema.partition_by) {
+                        (Some(live), Some(partition_by)) => partition_by.starts_with(live.as_str()),
+                        (live, partition_by) => live.is_none() && partition_by.is_none(),
+                    };
+                    if !same_partitioning {
+                        changes.push(SchemaChange::ChangePartitioning {
+                            from: live_partition_by,
+                            to: schema.partition_by.clone(),
+                        });
+                    }
+                    changes
+                }
+                // the table is created from the current schema
+                None => {
+                    sql.extend(reset_orphaned_last_synced(client, schema).await?);
+                    continue;
+                }
+            },
+        };
+
+        if !changes.is_empty() {
+            sql.push(generate_migration_sql(
+                schema,
+                &changes,
+                allow_destructive,
+                &partitions_table_name,
+            )?);
+        }
+    }
+
+    if !sql.is_empty() {
+        client.batch_execute(&sql.join("\n")).await?;
+    }
+
+    Ok(schemas)
+}
+
+pub async fn record_applied_schemas(
+    client: &PostgresClient,
+    indexer: &Indexer,
+    schemas: &[EventTableSchema],
+) -> Result<(), PostgresError> {
+    let query = format!(
+        r#"
+        INSERT INTO {} ("table_name", "columns", "partition_by", "internal_table_name")
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT ("table_name") DO UPDATE
+        SET "columns" = EXCLUDED."columns", "partition_by" = EXCLUDED."partition_by",
+            "internal_table_name" = EXCLUDED."internal_table_name"
+        "#,
+        generate_applied_schemas_table_name(&indexer.name)
+    );
+
+    for schema in schemas {
+        let columns = serde_json::to_value(&schema.columns)
+            .expect("Event table columns should always serialize");
+        client
+            .execute(
+                &query,
+                &[&schema.table_name, &columns, &schema.partition_by, &schema.internal_table_name],
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str) -> EventTableColumn {
+        EventTableColumn { name: name.to_string(), data_type: data_type.to_string() }
+    }
+
+    fn schema() -> EventTableSchema {
+        EventTableSchema {
+            table_name: "indexer_erc20.transfer".to_string(),
+            internal_table_name: "rindexer_internal.indexer_erc20_transfer".to_string(),
+            columns: vec![],
+            partition_by: None,
+        }
+    }
+
+    const PARTITIONS_TABLE: &str = "rindexer_internal.indexer_partitions";
+
+    #[test]
+    fn test_additive_changes_are_migrated() {
+        let applied = vec![column("from", "CHAR(42)"), column("to", "CHAR(42)")];
+        let current =
+            vec![column("from", "CHAR(42)"), column("memo", "TEXT"), column("to", "CHAR(42)")];
+
+        let changes = diff_event_table_columns(&applied, &current);
+        assert_eq!(changes, vec![SchemaChange::AddColumn(column("memo", "TEXT"))]);
+        assert_eq!(
+            generate_migration_sql(&schema(), &changes, false, PARTITIONS_TABLE).unwrap(),
+            r#"ALTER TABLE IF EXISTS indexer_erc20.transfer ADD COLUMN IF NOT EXISTS "memo" TEXT;"#
+        );
+    }
+
+    #[test]
+    fn test_breaking_changes_are_refused_unless_destructive() {
+        let applied = vec![column("value", "VARCHAR(78)"), column("memo", "TEXT")];
+        let current = vec![column("value", "BYTEA"), column("tx_index", "NUMERIC NOT NULL")];
+
+        let changes = diff_event_table_columns(&applied, &current);
+        assert_eq!(changes.len(), 3);
+        assert!(changes.iter().all(|c| c.is_breaking()));
+
+        match generate_migration_sql(&schema(), &changes, false, PARTITIONS_TABLE) {
+            Err(SchemaMigrationError::BreakingSchemaChange(table, reason)) => {
+                assert_eq!(table, "indexer_erc20.transfer");
+                assert!(reason.contains("column `value` changed from VARCHAR(78) to BYTEA"));
+                assert!(reason.contains("column `memo` removed"));
+            }
+            other => panic!("expected breaking schema change got {:?}", other),
+        }
+
+        let sql = generate_migration_sql(&schema(), &changes, true, PARTITIONS_TABLE).unwrap();
+        assert!(sql.contains("DROP TABLE IF EXISTS indexer_erc20.transfer CASCADE;"));
+        assert!(sql.contains("DROP TABLE IF EXISTS rindexer_internal.indexer_erc20_transfer"));
+        assert!(sql.contains(
+            r#"DELETE FROM rindexer_internal.indexer_partitions WHERE "table_name" = 'indexer_erc20.transfer';"#
+        ));
+    }
+
+    #[test]
+    fn test_widened_signed_integers_are_converted_in_place() {
+        let applied = vec![
+            column("amount", "NUMERIC"),
+            column("amounts", "NUMERIC[]"),
+            column("delta", "VARCHAR(78)"),
+            column("deltas", "VARCHAR(78)[]"),
+        ];
+        let current = vec![
+            column("amount", "BIGINT"),
+            column("amounts", "BIGINT[]"),
+            column("delta", "NUMERIC"),
+            column("deltas", "NUMERIC[]"),
+        ];
+
+        let changes = diff_event_table_columns(&applied, &current);
+        assert_eq!(changes.len(), 4);
+        assert!(changes.iter().all(|c| !c.is_breaking()));
+
+        let sql = generate_migration_sql(&schema(), &changes, false, PARTITIONS_TABLE).unwrap();
+        assert!(sql.starts_with(SIGNED_INTEGER_FUNCTIONS_SQL));
+        assert_eq!(sql.matches("CREATE OR REPLACE FUNCTION").count(), 2);
+        for statement in [
+            r#"ALTER TABLE IF EXISTS indexer_erc20.transfer ALTER COLUMN "amount" TYPE BIGINT USING "amount"::BIGINT;"#,
+            r#"ALTER TABLE IF EXISTS indexer_erc20.transfer ALTER COLUMN "amounts" TYPE BIGINT[] USING "amounts"::BIGINT[];"#,
+            r#"ALTER TABLE IF EXISTS indexer_erc20.transfer ALTER COLUMN "delta" TYPE NUMERIC USING pg_temp.rindexer_signed_integer("delta");"#,
+            r#"ALTER TABLE IF EXISTS indexer_erc20.transfer ALTER COLUMN "deltas" TYPE NUMERIC[] USING pg_temp.rindexer_signed_integers("deltas");"#,
+        ] {
+            assert!(sql.contains(statement), "{}", sql);
+        }
+
+        // live array columns have no length
+        assert!(!SchemaChange::ChangeColumnType {
+            name: "deltas".to_string(),
+            from: "VARCHAR[]".to_string(),
+            to: "NUMERIC[]".to_string(),
+        }
+        .is_breaking());
+    }
+
+    #[test]
+    fn test_live_columns_are_diffed() {
+        let live = vec![
+            column("rindexer_id", &live_column_data_type("integer", "int4", None, false)),
+            column("from", &live_column_data_type("character", "bpchar", Some(42), true)),
+            column("values", &live_column_data_type("ARRAY", "_varchar", None, true)),
+            column("value", &live_column_data_type("character varying", "varchar", Some(78), true)),
+            column(
+                "network",
+                &live_column_data_type("character varying", "varchar", Some(50), false),
+            ),
+        ];
+        let current = vec![
+            column("rindexer_id", "SERIAL PRIMARY KEY NOT NULL"),
+            column("from", "CHAR(42)"),
+            column("values", "VARCHAR(78)[]"),
+            column("value", "NUMERIC"),
+            column("value_formatted", "NUMERIC"),
+            column("network", "VARCHAR(50) NOT NULL"),
+        ];
+
+        assert_eq!(
+            diff_live_event_table_columns(&live, &current),
+            vec![
+                SchemaChange::ChangeColumnType {
+                    name: "value".to_string(),
+                    from: "VARCHAR(78)".to_string(),
+                    to: "NUMERIC".to_string(),
+                },
+                SchemaChange::AddColumn(column("value_formatted", "NUMERIC")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partitioning_change_is_breaking() {
+        let change =
+            SchemaChange::ChangePartitioning { from: None, to: Some("LIST (network)".to_string()) };
+        assert!(change.is_breaking());
+        assert_eq!(change.to_string(), "partitioning changed from none to LIST (network)");
+    }
+}
@@ -1,6 +1,14 @@
+pub mod applied_events;
 pub mod client;
+pub mod entities;
 pub mod generate;
 pub mod indexes;
+pub mod migrations;
+pub mod partitions;
+pub mod redecode;
 pub mod relationship;
+pub mod retention;
+pub mod rollups;
+pub mod scripts;
 pub mod setup;
 pub mod sql_type_wrapper;
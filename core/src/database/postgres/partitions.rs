@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use ethers::utils::keccak256;
+use lazy_static::lazy_static;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::{
+    database::postgres::client::{PostgresClient, PostgresError},
+    helpers::camel_to_snake,
+    manifest::storage::PostgresPartitioning,
+};
+
+lazy_static! {
+    // partitions already created by this process so each batch does not run the DDL again
+    static ref CREATED_PARTITIONS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Postgres truncates longer identifiers so two partitions could end up with the same name
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+/// Records every partition created for the event tables of the indexer
+pub fn generate_partitions_table_name(indexer_name: &str) -> String {
+    format!("rindexer_internal.{}_partitions", camel_to_snake(indexer_name))
+}
+
+pub fn generate_partitions_table_sql(indexer_name: &str) -> String {
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            "partition_name" TEXT PRIMARY KEY,
+            "table_name" TEXT NOT NULL,
+            "partition_by" TEXT NOT NULL,
+            "network" TEXT,
+            "from_block" NUMERIC,
+            "to_block" NUMERIC
+        );
+    "#,
+        generate_partitions_table_name(indexer_name)
+    )
+}
+
+/// Partitions live in `rindexer_internal` so graphql only exposes the parent table
+pub(crate) fn generate_partition_name(table_name: &str, suffix: &str) -> String {
+    let suffix: String = suffix
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+
+    let table = table_name.replace('.', "_");
+    let suffix = format!("_part_{}", suffix);
+    if table.len() + suffix.len() <= MAX_IDENTIFIER_LENGTH {
+        return format!("rindexer_internal.{}{}", table, suffix);
+    }
+
+    // long names keep a readable prefix and a short hash of the full table name to stay unique
+    let hash = hex::encode(&keccak256(table_name.as_bytes())[..4]);
+    let prefix_length = MAX_IDENTIFIER_LENGTH.saturating_sub(suffix.len() + hash.len() + 1);
+    let prefix: String = table.chars().take(prefix_length).collect();
+    let name = format!("{}_{}{}", prefix, hash, suffix);
+
+    format!("rindexer_internal.{}", &name[..name.len().min(MAX_IDENTIFIER_LENGTH)])
+}
+
+pub fn generate_network_partition_sql(
+    indexer_name: &str,
+    table_name: &str,
+    network: &str,
+) -> String {
+    let partition_name = generate_partition_name(table_name, network);
+    let network = network.replace('\'', "''");
+
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {partition_name} PARTITION OF {table_name} FOR VALUES IN ('{network}');
+        INSERT INTO {partitions_table} ("partition_name", "table_name", "partition_by", "network")
+        VALUES ('{partition_name}', '{table_name}', 'network', '{network}')
+        ON CONFLICT ("partition_name") DO NOTHING;
+    "#,
+        partitions_table = generate_partitions_table_name(indexer_name),
+    )
+}
+
+/// The `[from, to)` partition ranges covering the blocks
+pub fn block_partition_ranges(
+    from_block: u64,
+    to_block: u64,
+    blocks_per_partition: u64,
+) -> Vec<(u64, u64)> {
+    let blocks_per_partition = blocks_per_partition.max(1);
+    let mut start = from_block - from_block % blocks_per_partition;
+    let mut ranges = vec![];
+
+    while start <= to_block {
+        ranges.push((start, start + blocks_per_partition));
+        start += blocks_per_partition;
+    }
+
+    ranges
+}
+
+pub fn generate_block_partition_sql(
+    indexer_name: &str,
+    table_name: &str,
+    from_block: u64,
+    to_block: u64,
+) -> String {
+    let partition_name = generate_partition_name(table_name, &from_block.to_string());
+
+    // a partition detached by the retention is still a standalone table so it is attached again
+    format!(
+        r#"
+        DO $$
+        BEGIN
+            IF to_regclass('{partition_name}') IS NULL THEN
+                CREATE TABLE {partition_name} PARTITION OF {table_name} FOR VALUES FROM ({from_block}) TO ({to_block});
+            ELSIF NOT EXISTS (
+                SELECT 1 FROM pg_inherits
+                WHERE inhrelid = '{partition_name}'::regclass AND inhparent = '{table_name}'::regclass
+            ) THEN
+                ALTER TABLE {table_name} ATTACH PARTITION {partition_name} FOR VALUES FROM ({from_block}) TO ({to_block});
+            END IF;
+        END $$;
+        INSERT INTO {partitions_table} ("partition_name", "table_name", "partition_by", "from_block", "to_block")
+        VALUES ('{partition_name}', '{table_name}', 'block_number', {from_block}, {to_block})
+        ON CONFLICT ("partition_name") DO NOTHING;
+    "#,
+        partitions_table = generate_partitions_table_name(indexer_name),
+    )
+}
+
+/// Forgets a partition detached by the retention so it is attached again if its blocks are
+/// indexed again
+pub async fn forget_created_partition(partition_name: &str) {
+    CREATED_PARTITIONS.lock().await.remove(partition_name);
+}
+
+/// Creates the block range partitions the blocks about to be indexed are written to, network
+/// partitions are all known upfront so they are created with the tables
+pub async fn ensure_block_partitions(
+    client: &PostgresClient,
+    indexer_name: &str,
+    table_name: &str,
+    partitioning: &PostgresPartitioning,
+    from_block: u64,
+    to_block: u64,
+) -> Result<(), PostgresError> {
+    let PostgresPartitioning::BlockNumber { blocks_per_partition } = partitioning else {
+        return Ok(());
+    };
+
+    // held while creating so concurrent batches never race on the same partition
+    let mut created = CREATED_PARTITIONS.lock().await;
+
+    for (start, end) in block_partition_ranges(from_block, to_block, *blocks_per_partition) {
+        let partition_name = generate_partition_name(table_name, &start.to_string());
+        if created.contains(&partition_name) {
+            continue;
+        }
+
+        client
+            .batch_execute(&generate_block_partition_sql(indexer_name, table_name, start, end))
+            .await?;
+        info!("Created partition {} for blocks {} to {}", partition_name, start, end - 1);
+        created.insert(partition_name);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_partition_ranges() {
+        assert_eq!(block_partition_ranges(10, 20, 100), vec![(0, 100)]);
+        assert_eq!(
+            block_partition_ranges(1_999_990, 2_000_010, 1_000_000),
+            vec![(1_000_000, 2_000_000), (2_000_000, 3_000_000)]
+        );
+    }
+
+    #[test]
+    fn test_partition_names() {
+        assert_eq!(
+            generate_partition_name("indexer_erc20.transfer", "base-sepolia"),
+            "rindexer_internal.indexer_erc20_transfer_part_base_sepolia"
+        );
+        assert!(generate_block_partition_sql("Indexer", "indexer_erc20.transfer", 0, 100)
+            .contains("CREATE TABLE rindexer_internal.indexer_erc20_transfer_part_0 PARTITION OF indexer_erc20.transfer FOR VALUES FROM (0) TO (100)"));
+        assert!(generate_block_partition_sql("Indexer", "indexer_erc20.transfer", 0, 100)
+            .contains("ALTER TABLE indexer_erc20.transfer ATTACH PARTITION rindexer_internal.indexer_erc20_transfer_part_0 FOR VALUES FROM (0) TO (100)"));
+
+        let long_table = "my_very_long_indexer_name_uniswap_v3_pool_factory.swap_with_fees";
+        let name = generate_partition_name(long_table, "21000000");
+        let name = name.strip_prefix("rindexer_internal.").unwrap();
+        assert!(name.len() <= MAX_IDENTIFIER_LENGTH);
+        assert!(name.starts_with("my_very_long_indexer_name_uniswap_v3_"));
+        assert!(name.ends_with("_part_21000000"));
+        assert_ne!(
+            generate_partition_name(long_table, "21000000"),
+            generate_partition_name(&format!("{}_v2", long_table), "21000000")
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::{fs, path::Path, str::FromStr};
+
+use ethers::{
+    abi::{Abi, Event},
+    types::{Address, Bytes, Log, H256, U256, U64},
+};
+use serde_json::Value;
+use tokio_postgres::{types::ToSql, Row};
+
+use crate::{
+    abi::{ABIItem, EventInfo, ParamTypeError, ReadAbiError},
+    database::postgres::{
+        client::{PostgresClient, PostgresError},
+        generate::{generate_columns_names_only, generate_indexer_contract_schema_name},
+        sql_type_wrapper::{
+            map_ethereum_wrapper_to_json, map_ethereum_wrapper_to_json_value,
+            map_log_params_to_ethereum_wrapper, EthereumSqlTypeWrapper, ScaledAmount,
+        },
+    },
+    event::callback_registry::TxInformation,
+    helpers::get_full_path,
+    indexer::{
+        log_helpers::parse_log,
+        no_code::{evaluate_computed_columns, parse_computed_columns},
+        Indexer,
+    },
+    manifest::storage::StorageEvent,
+};
+
+/// How many rows are decoded and updated in a single transaction
+const REDECODE_PAGE_SIZE: i64 = 1000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RedecodeRawLogsError {
+    #[error("{0}")]
+    ReadAbiError(#[from] ReadAbiError),
+
+    #[error("{0}")]
+    ParamTypeError(#[from] ParamTypeError),
+
+    #[error("{0}")]
+    PostgresError(#[from] PostgresError),
+
+    #[error("Event name not found in ABI for contract: {0} - event: {1}")]
+    EventNameNotFoundInAbi(String, String),
+
+    #[error("Contract {0} not found or it does not have store_raw_log enabled")]
+    ContractNotFound(String),
+}
+
+#[derive(Debug)]
+pub struct RedecodedEventTable {
+    pub table_name: String,
+    pub updated: u64,
+    /// Rows whose raw log no longer matches the event in the ABI
+    pub skipped: u64,
+}
+
+/// Decodes the stored topics and data of every contract with `store_raw_log` again with the
+/// current ABI and writes the result into the input columns, no RPC calls are made.
+///
+/// Formatted columns with fixed `decimals` and the computed columns are recomputed from the new
+/// values, `decimals_from` columns are left as they are as their decimals are read from the chain.
+pub async fn redecode_raw_logs(
+    project_path: &Path,
+    indexer: &Indexer,
+    client: &PostgresClient,
+    contract_name: Option<&str>,
+) -> Result<Vec<RedecodedEventTable>, RedecodeRawLogsError> {
+    let contracts: Vec<_> = indexer
+        .contracts
+        .iter()
+        .filter(|contract| {
+            contract.storage.as_ref().is_some_and(|storage| storage.is_store_raw_log())
+        })
+        .filter(|contract| contract_name.is_none_or(|name| contract.name == name))
+        .collect();
+
+    if let Some(name) = contract_name {
+        if contracts.is_empty() {
+            return Err(RedecodeRawLogsError::ContractNotFound(name.to_string()));
+        }
+    }
+
+    let mut tables = vec![];
+    for contract in contracts {
+        let full_path = get_full_path(project_path, &contract.abi)
+            .map_err(|_| ReadAbiError::AbiPathDoesNotExist(contract.abi.clone()))?;
+        let abi_str = fs::read_to_string(full_path).map_err(ReadAbiError::from)?;
+        let abi: Abi = serde_json::from_str(&abi_str).map_err(ReadAbiError::from)?;
+
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
+        let schema_name = generate_indexer_contract_schema_name(
+            &indexer.name,
+            &contract.before_modify_name_if_filter_readonly(),
+        );
+
+        for event_info in &event_names {
+            let event = abi
+                .events_by_name(&event_info.name)
+                .ok()
+                .and_then(|events| events.first())
+                .ok_or_else(|| {
+                    RedecodeRawLogsError::EventNameNotFoundInAbi(
+                        contract.name.clone(),
+                        event_info.name.clone(),
+                    )
+                })?;
+
+            let storage_event = contract.storage_event(&event_info.name);
+            let table_name = format!("{}.{}", schema_name, storage_event.event_table_name());
+            tables.push(
+                redecode_event_table(
+                    client,
+                    &contract.name,
+                    &table_name,
+                    event,
+                    event_info,
+                    &storage_event,
+                )
+                .await?,
+            );
+        }
+    }
+
+    Ok(tables)
+}
+
+async fn redecode_event_table(
+    client: &PostgresClient,
+    contract_name: &str,
+    table_name: &str,
+    event: &Event,
+    event_info: &EventInfo,
+    storage_event: &StorageEvent,
+) -> Result<RedecodedEventTable, PostgresError> {
+    let mut result =
+        RedecodedEventTable { table_name: table_name.to_string(), updated: 0, skipped: 0 };
+
+    let mut columns = generate_columns_names_only(&event_info.inputs, storage_event);
+    if columns.is_empty() {
+        return Ok(result);
+    }
+
+    let formatted_inputs = storage_event.formatted_inputs(&event_info.inputs);
+    let formatted_column_names = storage_event.formatted_column_names(&event_info.inputs);
+    columns.extend(
+        formatted_inputs
+            .iter()
+            .zip(&formatted_column_names)
+            .filter(|((_, input_format), _)| input_format.decimals.is_some())
+            .map(|(_, name)| name.clone()),
+    );
+    let computed_columns = parse_computed_columns(storage_event);
+    columns.extend(computed_columns.iter().map(|(column, _)| column.name.clone()));
+
+    // the transaction information and formatted values are read back for the computed columns
+    let select_sql = format!(
+        "SELECT rindexer_id, topics, data, contract_address, tx_hash, block_number::TEXT, \
+         block_hash, network, tx_index::TEXT, log_index{} FROM {} \
+         WHERE topics IS NOT NULL AND rindexer_id > $1 \
+         ORDER BY rindexer_id LIMIT {}",
+        formatted_column_names
+            .iter()
+            .map(|name| format!(", \"{}\"::TEXT", name))
+            .collect::<String>(),
+        table_name,
+        REDECODE_PAGE_SIZE
+    );
+    let update_sql = format!(
+        "UPDATE {} SET {} WHERE rindexer_id = ${}",
+        table_name,
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("\"{}\" = ${}", column, i + 1))
+            .collect::<Vec<_>>()
+            .join(", "),
+        columns.len() + 1
+    );
+
+    let mut last_rindexer_id: i32 = 0;
+    loop {
+        let rows = client.query(&select_sql, &[&last_rindexer_id]).await?;
+        let Some(last_row) = rows.last() else {
+            break;
+        };
+        last_rindexer_id = last_row.get(0);
+
+        let mut params_list: Vec<Vec<Box<dyn ToSql + Send + Sync>>> = vec![];
+        for row in &rows {
+            let rindexer_id: i32 = row.get(0);
+            let topics: Vec<Vec<u8>> = row.get(1);
+            let data: Option<Vec<u8>> = row.get(2);
+
+            // topic0 is always there for a non anonymous event
+            if topics.is_empty() || topics.iter().any(|topic| topic.len() != 32) {
+                result.skipped += 1;
+                continue;
+            }
+
+            let log = Log {
+                topics: topics.iter().map(|topic| H256::from_slice(topic)).collect(),
+                data: Bytes::from(data.unwrap_or_default()),
+                ..Default::default()
+            };
+            let Some(parsed_log) = parse_log(event, &log) else {
+                result.skipped += 1;
+                continue;
+            };
+
+            let mut wrappers = map_log_params_to_ethereum_wrapper(
+                &event_info.inputs,
+                &parsed_log.params,
+                storage_event.is_tuples_as_jsonb(),
+            );
+
+            let mut event_result = map_ethereum_wrapper_to_json(
+                &event_info.inputs,
+                &wrappers,
+                &read_tx_information(row),
+                false,
+            );
+            for (index, (input, input_format)) in formatted_inputs.iter().enumerate() {
+                let formatted = match input_format.decimals {
+                    Some(decimals) => {
+                        let token = event_info
+                            .inputs
+                            .iter()
+                            .position(|i| i.name == input.name)
+                            .and_then(|position| parsed_log.params.get(position))
+                            .map(|param| &param.value);
+                        let amount =
+                            token.and_then(|token| ScaledAmount::from_token(token, decimals));
+                        wrappers.push(EthereumSqlTypeWrapper::Numeric(amount));
+                        amount
+                    }
+                    None => row
+                        .get::<_, Option<&str>>(10 + index)
+                        .and_then(ScaledAmount::from_decimal_str),
+                };
+                if let Value::Object(event_result) = &mut event_result {
+                    event_result.insert(
+                        format!("{}_formatted", input.name),
+                        map_ethereum_wrapper_to_json_value(&EthereumSqlTypeWrapper::Numeric(
+                            formatted,
+                        )),
+                    );
+                }
+            }
+            wrappers.extend(evaluate_computed_columns(
+                contract_name,
+                &event_info.name,
+                &computed_columns,
+                &mut event_result,
+            ));
+
+            let mut params: Vec<Box<dyn ToSql + Send + Sync>> = wrappers
+                .into_iter()
+                .map(|wrapper| Box::new(wrapper) as Box<dyn ToSql + Send + Sync>)
+                .collect();
+            params.push(Box::new(rindexer_id));
+            params_list.push(params);
+        }
+
+        result.updated += params_list.len() as u64;
+        client.batch_insert(&update_sql, params_list).await?;
+
+        if (rows.len() as i64) < REDECODE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// The transaction information of a stored event, computed columns can read it like when indexing
+fn read_tx_information(row: &Row) -> TxInformation {
+    let text = |index: usize| row.get::<_, String>(index).trim().to_string();
+
+    TxInformation {
+        address: Address::from_str(&text(3)).unwrap_or_default(),
+        transaction_hash: H256::from_str(&text(4)).unwrap_or_default(),
+        block_number: U64::from_dec_str(&text(5)).unwrap_or_default(),
+        block_hash: H256::from_str(&text(6)).unwrap_or_default(),
+        network: text(7),
+        transaction_index: U64::from_dec_str(&text(8)).unwrap_or_default(),
+        log_index: U256::from_dec_str(&text(9)).unwrap_or_default(),
+    }
+}
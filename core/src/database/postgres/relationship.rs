@@ -5,7 +5,10 @@ use tracing::info;
 
 use crate::{
     abi::{get_abi_item_with_db_map, ABIInput, ABIItem, GetAbiItemWithDbMapError, ReadAbiError},
-    database::postgres::client::{PostgresClient, PostgresConnectionError, PostgresError},
+    database::postgres::{
+        client::{PostgresClient, PostgresConnectionError, PostgresError},
+        generate::generate_event_table_full_name,
+    },
     helpers::camel_to_snake,
     manifest::{contract::Contract, storage::ForeignKeys},
     types::code::Code,
@@ -379,7 +382,7 @@ pub async fn create_relationships(
                         foreign_key.event_input_name.split('.').collect::<Vec<&str>>();
                     let abi_parameter = get_abi_item_with_db_map(
                         &abi_items,
-                        &foreign_key.event_name,
+                        &contract.storage_event(&foreign_key.event_name),
                         &parameter_mapping,
                     )?;
 
@@ -399,7 +402,7 @@ pub async fn create_relationships(
                         linked_key.event_input_name.split('.').collect::<Vec<&str>>();
                     let linked_abi_parameter = get_abi_item_with_db_map(
                         &linked_abi_items,
-                        &linked_key.event_name,
+                        &linked_key_contract.storage_event(&linked_key.event_name),
                         &linked_parameter_mapping,
                     )?;
 
@@ -418,23 +421,21 @@ pub async fn create_relationships(
                     let relationship = Relationship {
                         contract_name: foreign_key.contract_name.clone(),
                         event: foreign_key.event_name.clone(),
-                        db_table_column: camel_to_snake(&abi_parameter.db_column_name),
-                        db_table_name: format!(
-                            "{}_{}.{}",
-                            camel_to_snake(manifest_name),
-                            camel_to_snake(&contract.name),
-                            camel_to_snake(&foreign_key.event_name)
+                        db_table_column: abi_parameter.db_column_name,
+                        db_table_name: generate_event_table_full_name(
+                            manifest_name,
+                            &contract.name,
+                            &contract.event_table_name(&foreign_key.event_name),
                         ),
                         abi_input: abi_parameter.abi_item,
                         linked_to: LinkTo {
                             contract_name: linked_key.contract_name.clone(),
                             event: linked_key.event_name.clone(),
-                            db_table_column: camel_to_snake(&linked_abi_parameter.db_column_name),
-                            db_table_name: format!(
-                                "{}_{}.{}",
-                                camel_to_snake(manifest_name),
-                                camel_to_snake(&linked_key_contract.name),
-                                camel_to_snake(&linked_key.event_name)
+                            db_table_column: linked_abi_parameter.db_column_name,
+                            db_table_name: generate_event_table_full_name(
+                                manifest_name,
+                                &linked_key_contract.name,
+                                &linked_key_contract.event_table_name(&linked_key.event_name),
                             ),
                             abi_input: linked_abi_parameter.abi_item,
                         },
@@ -0,0 +1,305 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::{providers::ProviderError, types::U64};
+use tracing::{error, info};
+
+use crate::{
+    database::postgres::{
+        client::{PostgresClient, PostgresError},
+        partitions::{forget_created_partition, generate_partitions_table_name},
+    },
+    helpers::camel_to_snake,
+    manifest::retention::{Retention, RetentionMode},
+    provider::JsonRpcCachedProvider,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RetentionError {
+    #[error("{0}")]
+    PostgresError(#[from] PostgresError),
+
+    #[error("{0}")]
+    ProviderError(#[from] ProviderError),
+}
+
+/// Records up to which block each event table was pruned on each network so a restart never
+/// backfills the pruned range
+pub fn generate_retention_table_name(indexer_name: &str) -> String {
+    format!("rindexer_internal.{}_retention", camel_to_snake(indexer_name))
+}
+
+pub fn generate_retention_table_sql(indexer_name: &str) -> String {
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            "table_name" TEXT NOT NULL,
+            "network" TEXT NOT NULL,
+            "pruned_to_block" NUMERIC NOT NULL,
+            "pruned_at" TIMESTAMPTZ NOT NULL DEFAULT now(),
+            PRIMARY KEY ("table_name", "network")
+        );
+    "#,
+        generate_retention_table_name(indexer_name)
+    )
+}
+
+fn generate_retention_delete_sql(table_name: &str) -> String {
+    format!(
+        "DELETE FROM {} WHERE \"network\" = $1::TEXT AND \"block_number\" < $2::TEXT::NUMERIC",
+        table_name
+    )
+}
+
+fn generate_record_retention_sql(indexer_name: &str) -> String {
+    let retention_table = generate_retention_table_name(indexer_name);
+    format!(
+        "INSERT INTO {0} AS retention (\"table_name\", \"network\", \"pruned_to_block\", \"pruned_at\") \
+         VALUES ($1::TEXT, $2::TEXT, $3::TEXT::NUMERIC, now()) \
+         ON CONFLICT (\"table_name\", \"network\") DO UPDATE SET \
+         \"pruned_to_block\" = GREATEST(retention.\"pruned_to_block\", EXCLUDED.\"pruned_to_block\"), \
+         \"pruned_at\" = now()",
+        retention_table
+    )
+}
+
+/// The first block still kept in the event table on the network, `None` when it was never pruned
+pub async fn get_retention_pruned_block(
+    client: &PostgresClient,
+    indexer_name: &str,
+    table_name: &str,
+    network: &str,
+) -> Result<Option<U64>, PostgresError> {
+    let row = client
+        .query_one_or_none(
+            &format!(
+                "SELECT \"pruned_to_block\"::TEXT FROM {} WHERE \"table_name\" = $1 AND \"network\" = $2",
+                generate_retention_table_name(indexer_name)
+            ),
+            &[&table_name, &network],
+        )
+        .await?;
+
+    Ok(row.and_then(|row| U64::from_dec_str(row.get::<_, String>(0).as_str()).ok()))
+}
+
+/// The event tables of a contract with a retention policy
+pub struct RetentionTables {
+    pub contract_name: String,
+    pub retention: Retention,
+    pub table_names: Vec<String>,
+    pub networks: Vec<(String, Arc<JsonRpcCachedProvider>)>,
+}
+
+/// The first block at or after the timestamp between `low` and `high`, found with a binary
+/// search over the block timestamps
+async fn first_block_at(
+    provider: &JsonRpcCachedProvider,
+    timestamp: u64,
+    mut low: U64,
+    mut high: U64,
+) -> Result<U64, ProviderError> {
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if provider.get_block_timestamp(middle).await? < timestamp.into() {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Rows below the returned block are pruned, when both limits are set rows are only kept while
+/// they are inside both
+async fn retention_cutoff(
+    retention: &Retention,
+    provider: &JsonRpcCachedProvider,
+    pruned_to_block: Option<U64>,
+) -> Result<Option<U64>, ProviderError> {
+    let latest_block = provider.get_block_number().await?;
+    let mut cutoff =
+        retention.max_blocks.map(|max_blocks| latest_block.saturating_sub(max_blocks.into()));
+
+    if let Some(max_age) = retention.max_age() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let timestamp = now.saturating_sub(max_age).as_secs();
+        let low = pruned_to_block.unwrap_or_default().min(latest_block);
+        let by_age = first_block_at(provider, timestamp, low, latest_block).await?;
+        cutoff = Some(cutoff.map_or(by_age, |cutoff| cutoff.max(by_age)));
+    }
+
+    Ok(cutoff.filter(|cutoff| Some(*cutoff) > pruned_to_block))
+}
+
+async fn record_pruned_block(
+    client: &PostgresClient,
+    indexer_name: &str,
+    table_name: &str,
+    network: &str,
+    pruned_to_block: U64,
+) -> Result<(), PostgresError> {
+    client
+        .execute(
+            &generate_record_retention_sql(indexer_name),
+            &[&table_name, &network, &pruned_to_block.to_string()],
+        )
+        .await?;
+    Ok(())
+}
+
+async fn delete_old_rows(
+    client: &PostgresClient,
+    indexer_name: &str,
+    table_name: &str,
+    network: &str,
+    cutoff: U64,
+) -> Result<u64, PostgresError> {
+    let transaction = client.transaction().await?;
+    let deleted = transaction
+        .transaction
+        .execute(&generate_retention_delete_sql(table_name), &[&network, &cutoff.to_string()])
+        .await?;
+    transaction
+        .transaction
+        .execute(
+            &generate_record_retention_sql(indexer_name),
+            &[&table_name, &network, &cutoff.to_string()],
+        )
+        .await?;
+    transaction.transaction.commit().await?;
+
+    Ok(deleted)
+}
+
+/// Detaches the block partitions which end at or before the cutoff, they are kept as standalone
+/// tables and forgotten by the partitions table. Returns the end of the last partition detached.
+async fn detach_old_partitions(
+    client: &PostgresClient,
+    indexer_name: &str,
+    table_name: &str,
+    cutoff: U64,
+) -> Result<Option<U64>, PostgresError> {
+    let partitions_table = generate_partitions_table_name(indexer_name);
+    let rows = client
+        .query(
+            &format!(
+                "SELECT \"partition_name\", \"to_block\"::TEXT FROM {} \
+                 WHERE \"table_name\" = $1 AND \"partition_by\" = 'block_number' AND \"to_block\" <= $2::TEXT::NUMERIC \
+                 ORDER BY \"to_block\"",
+                partitions_table
+            ),
+            &[&table_name, &cutoff.to_string()],
+        )
+        .await?;
+
+    let mut detached_to = None;
+    for row in rows {
+        let partition_name: String = row.get(0);
+        client
+            .batch_execute(&format!(
+                "ALTER TABLE {} DETACH PARTITION {}; DELETE FROM {} WHERE \"partition_name\" = '{}';",
+                table_name, partition_name, partitions_table, partition_name
+            ))
+            .await?;
+        forget_created_partition(&partition_name).await;
+        info!("Detached partition {} from {}", partition_name, table_name);
+        detached_to = U64::from_dec_str(row.get::<_, String>(1).as_str()).ok().or(detached_to);
+    }
+
+    Ok(detached_to)
+}
+
+async fn prune_tables(
+    client: &PostgresClient,
+    indexer_name: &str,
+    tables: &RetentionTables,
+) -> Result<(), RetentionError> {
+    let mut cutoffs = vec![];
+    for table_name in &tables.table_names {
+        for (network, provider) in &tables.networks {
+            let pruned_to_block =
+                get_retention_pruned_block(client, indexer_name, table_name, network).await?;
+            let cutoff = retention_cutoff(&tables.retention, provider, pruned_to_block).await?;
+            cutoffs.push((table_name, network, cutoff));
+        }
+    }
+
+    match tables.retention.mode() {
+        RetentionMode::Delete => {
+            for (table_name, network, cutoff) in cutoffs {
+                let Some(cutoff) = cutoff else {
+                    continue;
+                };
+                let deleted =
+                    delete_old_rows(client, indexer_name, table_name, network, cutoff).await?;
+                if deleted > 0 {
+                    info!(
+                        "{} - pruned {} rows before block {} on {} from {}",
+                        tables.contract_name, deleted, cutoff, network, table_name
+                    );
+                }
+            }
+        }
+        RetentionMode::Detach => {
+            // partitions hold the blocks of every network so they are only detached once every
+            // network is past them
+            for table_name in &tables.table_names {
+                let table_cutoffs: Vec<Option<U64>> = cutoffs
+                    .iter()
+                    .filter(|(name, ..)| *name == table_name)
+                    .map(|(_, _, cutoff)| *cutoff)
+                    .collect();
+                let Some(cutoff) = table_cutoffs.iter().copied().min().flatten() else {
+                    continue;
+                };
+
+                if let Some(detached_to) =
+                    detach_old_partitions(client, indexer_name, table_name, cutoff).await?
+                {
+                    for (network, _) in &tables.networks {
+                        record_pruned_block(client, indexer_name, table_name, network, detached_to)
+                            .await?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prunes the tables on the retention interval for as long as rindexer runs
+pub async fn run_retention(
+    client: Arc<PostgresClient>,
+    indexer_name: String,
+    tables: RetentionTables,
+) {
+    let mut interval = tokio::time::interval(tables.retention.interval());
+    loop {
+        interval.tick().await;
+        if let Err(e) = prune_tables(&client, &indexer_name, &tables).await {
+            error!("{} - Error applying retention: {}", tables.contract_name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_sql() {
+        assert_eq!(
+            generate_retention_delete_sql("indexer_erc20.transfer"),
+            "DELETE FROM indexer_erc20.transfer WHERE \"network\" = $1::TEXT AND \"block_number\" < $2::TEXT::NUMERIC"
+        );
+        assert!(generate_record_retention_sql("Indexer")
+            .starts_with("INSERT INTO rindexer_internal.indexer_retention AS retention"));
+        assert!(generate_retention_table_sql("Indexer")
+            .contains("PRIMARY KEY (\"table_name\", \"network\")"));
+    }
+}
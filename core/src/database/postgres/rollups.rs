@@ -0,0 +1,324 @@
+use ethers::{
+    abi::LogParam,
+    types::{Address, U256, U64},
+};
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    abi::ABIInput,
+    database::postgres::{applied_events::generate_not_applied_sql, entities::source_value},
+    manifest::{
+        rollup::{Rollup, RollupFunction},
+        storage::StorageEvent,
+    },
+};
+
+fn group_by_db_type(rollup: &Rollup, event_inputs: &[ABIInput], path: &str) -> &'static str {
+    rollup
+        .group_by_db_type(event_inputs, path)
+        .expect("Rollup group by inputs are validated with the manifest")
+}
+
+fn aggregate_db_type(function: RollupFunction) -> &'static str {
+    match function {
+        RollupFunction::Count => "BIGINT",
+        _ => "NUMERIC",
+    }
+}
+
+/// One table per rollup in the contract schema keyed by the network, the time bucket and the
+/// group by columns
+pub fn generate_rollup_tables_sql(
+    schema_name: &str,
+    event_inputs: &[ABIInput],
+    storage_event: &StorageEvent,
+) -> String {
+    storage_event
+        .rollups()
+        .iter()
+        .map(|rollup| {
+            let group_by_names: Vec<String> =
+                rollup.group_by_column_names().iter().map(|name| format!("\"{}\"", name)).collect();
+            let mut definitions = vec![
+                "\"network\" VARCHAR(50) NOT NULL".to_string(),
+                "\"bucket\" TIMESTAMP NOT NULL".to_string(),
+            ];
+            definitions.extend(rollup.group_by().iter().zip(&group_by_names).map(
+                |(path, name)| {
+                    format!("{} {} NOT NULL", name, group_by_db_type(rollup, event_inputs, path))
+                },
+            ));
+            definitions.extend(rollup.aggregates.iter().map(|aggregate| {
+                format!("\"{}\" {} NOT NULL", aggregate.name, aggregate_db_type(aggregate.function))
+            }));
+            definitions.push(format!(
+                "PRIMARY KEY ({})",
+                ["\"network\"".to_string(), "\"bucket\"".to_string()]
+                    .iter()
+                    .chain(&group_by_names)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.{} ({});",
+                schema_name,
+                rollup.name,
+                definitions.join(", ")
+            )
+        })
+        .collect()
+}
+
+/// A rollup ready to be applied to a batch of events of its contract event
+#[derive(Debug, Clone)]
+pub struct RollupStatement {
+    pub rollup_name: String,
+    /// The rollup table, the events applied to it are recorded under this name
+    pub table_name: String,
+    sql: String,
+    /// The group by paths then the inputs of the aggregates which take one
+    sources: Vec<String>,
+}
+
+impl RollupStatement {
+    fn new(
+        indexer_name: &str,
+        schema_name: &str,
+        event_inputs: &[ABIInput],
+        rollup: &Rollup,
+    ) -> Self {
+        RollupStatement {
+            rollup_name: rollup.name.clone(),
+            table_name: format!("{}.{}", schema_name, rollup.name),
+            sql: generate_rollup_sql(indexer_name, schema_name, event_inputs, rollup),
+            sources: rollup
+                .group_by()
+                .iter()
+                .chain(rollup.aggregates.iter().filter_map(|aggregate| aggregate.input.as_ref()))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The values an event adds to the rollup, `None` when one of them has no value in the event
+    pub fn row(
+        &self,
+        event_inputs: &[ABIInput],
+        log_params: &[LogParam],
+        contract_address: Address,
+    ) -> Option<Vec<String>> {
+        self.sources
+            .iter()
+            .map(|path| source_value(event_inputs, log_params, contract_address, path))
+            .collect()
+    }
+
+    /// The network, the block timestamps, block numbers and log indexes of the rows and then one
+    /// text array per source
+    pub fn params(
+        &self,
+        network: &str,
+        rows: &[(U256, U64, U256, Vec<String>)],
+    ) -> Vec<Box<dyn ToSql + Send + Sync>> {
+        let mut params: Vec<Box<dyn ToSql + Send + Sync>> = vec![
+            Box::new(network.to_string()),
+            Box::new(rows.iter().map(|(timestamp, ..)| timestamp.to_string()).collect::<Vec<_>>()),
+            Box::new(
+                rows.iter()
+                    .map(|(_, block_number, ..)| block_number.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+            Box::new(
+                rows.iter().map(|(_, _, log_index, _)| log_index.to_string()).collect::<Vec<_>>(),
+            ),
+        ];
+        for index in 0..self.sources.len() {
+            params.push(Box::new(
+                rows.iter().map(|(.., values)| values[index].clone()).collect::<Vec<_>>(),
+            ));
+        }
+
+        params
+    }
+}
+
+pub fn generate_rollup_statements(
+    indexer_name: &str,
+    schema_name: &str,
+    event_inputs: &[ABIInput],
+    storage_event: &StorageEvent,
+) -> Vec<RollupStatement> {
+    storage_event
+        .rollups()
+        .iter()
+        .map(|rollup| RollupStatement::new(indexer_name, schema_name, event_inputs, rollup))
+        .collect()
+}
+
+/// Aggregates the batch in postgres and merges it into the existing buckets, the events are
+/// passed as text arrays and unnested so a batch is a single statement whatever its size. Events
+/// already applied to the rollup are left out so a retried batch is not counted twice.
+fn generate_rollup_sql(
+    indexer_name: &str,
+    schema_name: &str,
+    event_inputs: &[ABIInput],
+    rollup: &Rollup,
+) -> String {
+    let group_by_names = rollup.group_by_column_names();
+    let group_by_values: Vec<String> = rollup
+        .group_by()
+        .iter()
+        .enumerate()
+        .map(|(i, path)| format!("e.s{}::{}", i, group_by_db_type(rollup, event_inputs, path)))
+        .collect();
+
+    let mut source_index = group_by_names.len();
+    let mut aggregate_values = vec![];
+    let mut updates = vec![];
+    for aggregate in &rollup.aggregates {
+        let value = match aggregate.function {
+            RollupFunction::Count => "COUNT(*)".to_string(),
+            function => {
+                let name = match function {
+                    RollupFunction::Sum => "SUM",
+                    RollupFunction::Min => "MIN",
+                    _ => "MAX",
+                };
+                source_index += 1;
+                format!("{}(e.s{}::NUMERIC)", name, source_index - 1)
+            }
+        };
+        aggregate_values.push(value);
+
+        let update = match aggregate.function {
+            RollupFunction::Count | RollupFunction::Sum => "existing.\"{0}\" + EXCLUDED.\"{0}\"",
+            RollupFunction::Min => "LEAST(existing.\"{0}\", EXCLUDED.\"{0}\")",
+            RollupFunction::Max => "GREATEST(existing.\"{0}\", EXCLUDED.\"{0}\")",
+        };
+        updates.push(format!(
+            "\"{}\" = {}",
+            aggregate.name,
+            update.replace("{0}", &aggregate.name)
+        ));
+    }
+
+    let key_names: Vec<String> = ["network", "bucket"]
+        .iter()
+        .map(|name| name.to_string())
+        .chain(group_by_names)
+        .map(|name| format!("\"{}\"", name))
+        .collect();
+    let column_names: Vec<String> = key_names
+        .iter()
+        .cloned()
+        .chain(rollup.aggregates.iter().map(|aggregate| format!("\"{}\"", aggregate.name)))
+        .collect();
+    let source_names: Vec<String> = ["block_timestamp", "block_number", "log_index"]
+        .iter()
+        .map(|name| name.to_string())
+        .chain((0..source_index).map(|i| format!("s{}", i)))
+        .collect();
+    let arrays: Vec<String> =
+        (0..source_names.len()).map(|i| format!("${}::TEXT[]", i + 2)).collect();
+    let group_by_positions: Vec<String> =
+        (0..group_by_values.len() + 1).map(|i| (i + 2).to_string()).collect();
+
+    format!(
+        "INSERT INTO {schema_name}.{table_name} AS existing ({column_names}) \
+        SELECT $1::TEXT, date_trunc('{interval}', to_timestamp(e.block_timestamp::BIGINT) AT TIME ZONE 'UTC'), \
+        {values} \
+        FROM unnest({arrays}) AS e({source_names}) \
+        WHERE {not_applied} \
+        GROUP BY {group_by_positions} \
+        ON CONFLICT ({key_names}) DO UPDATE SET {updates}",
+        schema_name = schema_name,
+        table_name = rollup.name,
+        column_names = column_names.join(", "),
+        interval = rollup.interval.as_str(),
+        values = group_by_values.into_iter().chain(aggregate_values).collect::<Vec<_>>().join(", "),
+        arrays = arrays.join(", "),
+        source_names = source_names.join(", "),
+        group_by_positions = group_by_positions.join(", "),
+        key_names = key_names.join(", "),
+        updates = updates.join(", "),
+        not_applied = generate_not_applied_sql(
+            indexer_name,
+            &format!("{}.{}", schema_name, rollup.name),
+            "$1::TEXT",
+            "e.block_number::NUMERIC",
+            "e.log_index::NUMERIC"
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_sql() {
+        let input = |name: &str, type_: &str| ABIInput {
+            indexed: None,
+            name: name.to_string(),
+            type_: type_.to_string(),
+            components: None,
+        };
+        let inputs = vec![input("to", "address"), input("value", "uint256")];
+        let storage_event: StorageEvent = serde_yaml::from_str(
+            r#"
+            event_name: Transfer
+            rollups:
+              - name: hourly_volume
+                interval: hour
+                group_by:
+                  - to
+                aggregates:
+                  - name: transfers
+                    function: count
+                  - name: volume
+                    function: sum
+                    input: value
+                  - name: smallest
+                    function: min
+                    input: value
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            generate_rollup_tables_sql("my_indexer_token", &inputs, &storage_event),
+            "CREATE TABLE IF NOT EXISTS my_indexer_token.hourly_volume (\"network\" VARCHAR(50) NOT NULL, \
+             \"bucket\" TIMESTAMP NOT NULL, \"to\" CHAR(42) NOT NULL, \"transfers\" BIGINT NOT NULL, \
+             \"volume\" NUMERIC NOT NULL, \"smallest\" NUMERIC NOT NULL, \
+             PRIMARY KEY (\"network\", \"bucket\", \"to\"));"
+        );
+
+        let statements =
+            generate_rollup_statements("MyIndexer", "my_indexer_token", &inputs, &storage_event);
+        assert_eq!(statements.len(), 1);
+        assert_eq!(
+            statements[0].sql(),
+            "INSERT INTO my_indexer_token.hourly_volume AS existing \
+             (\"network\", \"bucket\", \"to\", \"transfers\", \"volume\", \"smallest\") \
+             SELECT $1::TEXT, date_trunc('hour', to_timestamp(e.block_timestamp::BIGINT) AT TIME ZONE 'UTC'), \
+             e.s0::CHAR(42), COUNT(*), SUM(e.s1::NUMERIC), MIN(e.s2::NUMERIC) \
+             FROM unnest($2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[], $7::TEXT[]) \
+             AS e(block_timestamp, block_number, log_index, s0, s1, s2) \
+             WHERE NOT EXISTS (SELECT 1 FROM rindexer_internal.my_indexer_applied_events AS applied \
+             WHERE applied.\"table_name\" = 'my_indexer_token.hourly_volume' AND applied.\"network\" = $1::TEXT \
+             AND applied.\"block_number\" = e.block_number::NUMERIC AND applied.\"log_index\" = e.log_index::NUMERIC) \
+             GROUP BY 2, 3 \
+             ON CONFLICT (\"network\", \"bucket\", \"to\") DO UPDATE SET \
+             \"transfers\" = existing.\"transfers\" + EXCLUDED.\"transfers\", \
+             \"volume\" = existing.\"volume\" + EXCLUDED.\"volume\", \
+             \"smallest\" = LEAST(existing.\"smallest\", EXCLUDED.\"smallest\")"
+        );
+        assert_eq!(statements[0].params("ethereum", &[]).len(), 7);
+    }
+}
@@ -0,0 +1,146 @@
+use ethers::types::{H256, U64};
+use serde_json::{Map, Value};
+use tokio_postgres::types::ToSql;
+
+use crate::manifest::{entity::EntityColumn, script::ScriptTable, storage::StorageEvent};
+
+fn column_db_type(column: &EntityColumn) -> &'static str {
+    column.db_type().expect("Script table column types are validated with the manifest")
+}
+
+/// The tables the script of the event emits into, columns added to an existing table are created
+/// on the next start
+pub fn generate_script_tables_sql(schema_name: &str, storage_event: &StorageEvent) -> String {
+    let Some(script) = &storage_event.script else {
+        return String::new();
+    };
+
+    let mut sql = String::new();
+    for table in script.tables() {
+        let table_name = format!("{}.{}", schema_name, table.name);
+        sql.push_str(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (\"rindexer_id\" SERIAL PRIMARY KEY NOT NULL, \"network\" VARCHAR(50) NOT NULL, \"block_number\" NUMERIC NOT NULL, \"tx_hash\" CHAR(66) NOT NULL);",
+            table_name
+        ));
+        for column in &table.columns {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS \"{}\" {};",
+                table_name,
+                column.name,
+                column_db_type(column)
+            ));
+        }
+    }
+
+    sql
+}
+
+/// Text postgres casts into the column type, `0x` hex is turned into the bytea hex format and
+/// columns missing from the row are null
+fn script_value_to_text(value: &Value, column: &EntityColumn) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) if column_db_type(column) == "BYTEA" => {
+            Some(s.strip_prefix("0x").map_or_else(|| s.clone(), |hex| format!("\\x{}", hex)))
+        }
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// The insert of a row emitted by a script, every value is passed as text and cast in postgres
+pub fn generate_script_row_insert(
+    schema_name: &str,
+    table: &ScriptTable,
+    row: &Map<String, Value>,
+    network: &str,
+    block_number: U64,
+    tx_hash: H256,
+) -> Result<(String, Vec<Box<dyn ToSql + Send + Sync>>), String> {
+    let mut column_names = vec!["\"network\"".to_string(), "\"block_number\"".to_string()];
+    column_names.push("\"tx_hash\"".to_string());
+    let mut values =
+        vec!["$1::TEXT".to_string(), "$2::TEXT::NUMERIC".to_string(), "$3::TEXT".to_string()];
+    let mut params: Vec<Box<dyn ToSql + Send + Sync>> = vec![
+        Box::new(network.to_string()),
+        Box::new(block_number.to_string()),
+        Box::new(format!("{:?}", tx_hash)),
+    ];
+
+    for (name, value) in row {
+        let column = table
+            .find_column(name)
+            .ok_or_else(|| format!("{} is not a column of table {}", name, table.name))?;
+        column_names.push(format!("\"{}\"", column.name));
+        values.push(format!("${}::TEXT::{}", params.len() + 1, column_db_type(column)));
+        params.push(Box::new(script_value_to_text(value, column)));
+    }
+
+    let sql = format!(
+        "INSERT INTO {}.{} ({}) VALUES ({})",
+        schema_name,
+        table.name,
+        column_names.join(", "),
+        values.join(", ")
+    );
+
+    Ok((sql, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_script_row_insert() {
+        let table: ScriptTable = serde_yaml::from_str(
+            r#"
+            name: large_transfers
+            columns:
+              - name: holder
+                type: address
+              - name: amount
+                type: uint256
+              - name: memo
+                type: bytes
+            "#,
+        )
+        .unwrap();
+        let row =
+            json!({ "holder": "0x0338ce5020c447f7e668dc2ef778025ce3982662", "memo": "0xab01" });
+
+        let (sql, params) = generate_script_row_insert(
+            "my_indexer_token",
+            &table,
+            row.as_object().unwrap(),
+            "ethereum",
+            U64::from(10),
+            H256::zero(),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO my_indexer_token.large_transfers (\"network\", \"block_number\", \"tx_hash\", \"holder\", \"memo\") \
+             VALUES ($1::TEXT, $2::TEXT::NUMERIC, $3::TEXT, $4::TEXT::CHAR(42), $5::TEXT::BYTEA)"
+        );
+        assert_eq!(params.len(), 5);
+        assert_eq!(
+            script_value_to_text(&json!("0xab01"), &table.columns[2]),
+            Some("\\xab01".to_string())
+        );
+        assert_eq!(script_value_to_text(&json!(12), &table.columns[1]), Some("12".to_string()));
+
+        let unknown = json!({ "missing": 1 });
+        assert!(generate_script_row_insert(
+            "my_indexer_token",
+            &table,
+            unknown.as_object().unwrap(),
+            "ethereum",
+            U64::from(10),
+            H256::zero(),
+        )
+        .is_err());
+    }
+}
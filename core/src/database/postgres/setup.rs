@@ -6,6 +6,7 @@ use crate::{
     database::postgres::{
         client::{PostgresClient, PostgresConnectionError, PostgresError},
         generate::{generate_tables_for_indexer_sql, GenerateTablesForIndexerSqlError},
+        migrations::{migrate_event_tables, record_applied_schemas, SchemaMigrationError},
     },
     drop_tables_for_indexer_sql,
     manifest::core::{Manifest, ProjectType},
@@ -21,11 +22,15 @@ pub enum SetupPostgresError {
 
     #[error("Error creating tables for indexer: {0}")]
     GeneratingTables(#[from] GenerateTablesForIndexerSqlError),
+
+    #[error("Error migrating tables for indexer: {0}")]
+    MigratingTables(#[from] SchemaMigrationError),
 }
 
 pub async fn setup_postgres(
     project_path: &Path,
     manifest: &Manifest,
+    allow_destructive_migrations: bool,
 ) -> Result<PostgresClient, SetupPostgresError> {
     info!("Setting up postgres");
     let client = PostgresClient::new().await?;
@@ -45,10 +50,22 @@ pub async fn setup_postgres(
             info!("Dropped all data for {}", manifest.name);
         }
 
+        let indexer = manifest.to_indexer();
+        let partitioning = manifest.storage.postgres_partitioning();
+        let schemas = migrate_event_tables(
+            &client,
+            project_path,
+            &indexer,
+            allow_destructive_migrations,
+            partitioning,
+        )
+        .await?;
+
         info!("Creating tables for {}", manifest.name);
-        let sql = generate_tables_for_indexer_sql(project_path, &manifest.to_indexer())?;
+        let sql = generate_tables_for_indexer_sql(project_path, &indexer, partitioning)?;
         debug!("{}", sql);
         client.batch_execute(sql.as_str()).await?;
+        record_applied_schemas(&client, &indexer, &schemas).await?;
         info!("Created tables for {}", manifest.name);
     }
 
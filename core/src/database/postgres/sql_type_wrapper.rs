@@ -4,7 +4,7 @@ use bytes::BytesMut;
 use ethers::{
     abi::{Int, LogParam, Token},
     addressbook::Address,
-    prelude::{Bytes, H128, H160, H256, H512, U128, U256, U512, U64},
+    prelude::{Bytes, H128, H160, H256, H512, I256, U128, U256, U512, U64},
 };
 use rust_decimal::Decimal;
 use serde_json::{json, Value};
@@ -41,10 +41,102 @@ pub enum EthereumSqlTypeWrapper {
     VecU16(Vec<u16>),
     U8(u8),
     VecU8(Vec<u8>),
+    I8(i8),
+    VecI8(Vec<i8>),
+    I16(i16),
+    VecI16(Vec<i16>),
+    I32(i32),
+    VecI32(Vec<i32>),
+    I64(i64),
+    VecI64(Vec<i64>),
+    I128(i128),
+    VecI128(Vec<i128>),
+    I256(I256),
+    VecI256(Vec<I256>),
     String(String),
     VecString(Vec<String>),
     Bytes(Bytes),
     VecBytes(Vec<Bytes>),
+    JSONB(Value),
+    /// A formatted amount or computed number, null when its decimals are unknown or its
+    /// expression could not be evaluated
+    Numeric(Option<ScaledAmount>),
+    /// A computed bool, null when its expression could not be evaluated
+    NullableBool(Option<bool>),
+    /// A computed string, null when its expression could not be evaluated
+    NullableString(Option<String>),
+    /// The topics of a raw log stored as a bytea array
+    RawTopics(Vec<H256>),
+}
+
+/// An integer amount shown with a number of decimals, `1500000` with 6 decimals is `1.5`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaledAmount {
+    /// The magnitude so the full uint256 range fits
+    pub value: U256,
+    pub negative: bool,
+    pub decimals: u8,
+}
+
+impl ScaledAmount {
+    /// Only integer tokens can be scaled
+    pub fn from_token(token: &Token, decimals: u8) -> Option<Self> {
+        match token {
+            Token::Uint(value) => Some(ScaledAmount { value: *value, negative: false, decimals }),
+            Token::Int(value) => {
+                let value = I256::from_raw(*value);
+                Some(ScaledAmount {
+                    value: value.unsigned_abs(),
+                    negative: value.is_negative(),
+                    decimals,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The whole and fractional digits, trailing zeros of the fraction are dropped
+    fn split_digits(&self) -> (String, String) {
+        let digits = self.value.to_string();
+        let decimals = self.decimals as usize;
+        let digits = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+
+        (whole.to_string(), fraction.trim_end_matches('0').to_string())
+    }
+
+    /// Reads an amount back from its decimal string, the digits after the point are its decimals
+    pub fn from_decimal_str(value: &str) -> Option<Self> {
+        let (negative, value) = match value.strip_prefix('-') {
+            Some(value) => (true, value),
+            None => (false, value),
+        };
+        let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let fraction = fraction.trim_end_matches('0');
+        if whole.is_empty() && fraction.is_empty() ||
+            !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let value = U256::from_dec_str(&format!("{}{}", whole, fraction)).ok()?;
+        Some(ScaledAmount {
+            value,
+            negative: negative && !value.is_zero(),
+            decimals: u8::try_from(fraction.len()).ok()?,
+        })
+    }
+
+    pub fn to_decimal_string(self) -> String {
+        let (whole, fraction) = self.split_digits();
+        let sign = if self.negative { "-" } else { "" };
+
+        if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{}", sign, whole, fraction)
+        }
+    }
 }
 
 impl EthereumSqlTypeWrapper {
@@ -76,6 +168,23 @@ impl EthereumSqlTypeWrapper {
             EthereumSqlTypeWrapper::VecU16(_) => "VecU16",
             EthereumSqlTypeWrapper::U8(_) => "U8",
             EthereumSqlTypeWrapper::VecU8(_) => "VecU8",
+            EthereumSqlTypeWrapper::I8(_) => "I8",
+            EthereumSqlTypeWrapper::VecI8(_) => "VecI8",
+            EthereumSqlTypeWrapper::I16(_) => "I16",
+            EthereumSqlTypeWrapper::VecI16(_) => "VecI16",
+            EthereumSqlTypeWrapper::I32(_) => "I32",
+            EthereumSqlTypeWrapper::VecI32(_) => "VecI32",
+            EthereumSqlTypeWrapper::I64(_) => "I64",
+            EthereumSqlTypeWrapper::VecI64(_) => "VecI64",
+            EthereumSqlTypeWrapper::I128(_) => "I128",
+            EthereumSqlTypeWrapper::VecI128(_) => "VecI128",
+            EthereumSqlTypeWrapper::I256(_) => "I256",
+            EthereumSqlTypeWrapper::VecI256(_) => "VecI256",
+            EthereumSqlTypeWrapper::JSONB(_) => "JSONB",
+            EthereumSqlTypeWrapper::Numeric(_) => "Numeric",
+            EthereumSqlTypeWrapper::NullableBool(_) => "NullableBool",
+            EthereumSqlTypeWrapper::NullableString(_) => "NullableString",
+            EthereumSqlTypeWrapper::RawTopics(_) => "RawTopics",
             EthereumSqlTypeWrapper::String(_) => "String",
             EthereumSqlTypeWrapper::VecString(_) => "VecString",
             EthereumSqlTypeWrapper::Bytes(_) => "Bytes",
@@ -117,6 +226,24 @@ impl EthereumSqlTypeWrapper {
             EthereumSqlTypeWrapper::VecU32(_) => PgType::INT2_ARRAY,
             EthereumSqlTypeWrapper::U8(_) => PgType::INT2,
             EthereumSqlTypeWrapper::VecU8(_) => PgType::INT2_ARRAY,
+            EthereumSqlTypeWrapper::I8(_) => PgType::INT2,
+            EthereumSqlTypeWrapper::VecI8(_) => PgType::INT2_ARRAY,
+            EthereumSqlTypeWrapper::I16(_) => PgType::INT2,
+            EthereumSqlTypeWrapper::VecI16(_) => PgType::INT2_ARRAY,
+            EthereumSqlTypeWrapper::I32(_) => PgType::INT4,
+            EthereumSqlTypeWrapper::VecI32(_) => PgType::INT4_ARRAY,
+            EthereumSqlTypeWrapper::I64(_) => PgType::INT8,
+            EthereumSqlTypeWrapper::VecI64(_) => PgType::INT8_ARRAY,
+            // NUMERIC keeps the sign and full precision which int256 needs
+            EthereumSqlTypeWrapper::I128(_) => PgType::NUMERIC,
+            EthereumSqlTypeWrapper::VecI128(_) => PgType::NUMERIC_ARRAY,
+            EthereumSqlTypeWrapper::I256(_) => PgType::NUMERIC,
+            EthereumSqlTypeWrapper::VecI256(_) => PgType::NUMERIC_ARRAY,
+            EthereumSqlTypeWrapper::JSONB(_) => PgType::JSONB,
+            EthereumSqlTypeWrapper::Numeric(_) => PgType::NUMERIC,
+            EthereumSqlTypeWrapper::NullableBool(_) => PgType::BOOL,
+            EthereumSqlTypeWrapper::NullableString(_) => PgType::TEXT,
+            EthereumSqlTypeWrapper::RawTopics(_) => PgType::BYTEA_ARRAY,
         }
     }
 }
@@ -312,6 +439,70 @@ impl ToSql for EthereumSqlTypeWrapper {
                     int_values.to_sql(_ty, out)
                 }
             }
+            EthereumSqlTypeWrapper::I8(value) => {
+                // postgres has no single byte integer so int8 is stored as SMALLINT
+                let int_value: i16 = *value as i16;
+                int_value.to_sql(_ty, out)
+            }
+            EthereumSqlTypeWrapper::VecI8(values) => {
+                let int_values: Vec<i16> = values.iter().map(|&s| s as i16).collect();
+                if int_values.is_empty() {
+                    Ok(IsNull::Yes)
+                } else {
+                    int_values.to_sql(_ty, out)
+                }
+            }
+            EthereumSqlTypeWrapper::I16(value) => value.to_sql(_ty, out),
+            EthereumSqlTypeWrapper::VecI16(values) => {
+                if values.is_empty() {
+                    Ok(IsNull::Yes)
+                } else {
+                    values.to_sql(_ty, out)
+                }
+            }
+            EthereumSqlTypeWrapper::I32(value) => value.to_sql(_ty, out),
+            EthereumSqlTypeWrapper::VecI32(values) => {
+                if values.is_empty() {
+                    Ok(IsNull::Yes)
+                } else {
+                    values.to_sql(_ty, out)
+                }
+            }
+            EthereumSqlTypeWrapper::I64(value) => value.to_sql(_ty, out),
+            EthereumSqlTypeWrapper::VecI64(values) => {
+                if values.is_empty() {
+                    Ok(IsNull::Yes)
+                } else {
+                    values.to_sql(_ty, out)
+                }
+            }
+            EthereumSqlTypeWrapper::I128(value) => {
+                serialize_numeric(&I256::from(*value), out);
+                Ok(IsNull::No)
+            }
+            EthereumSqlTypeWrapper::VecI128(values) => {
+                let values: Vec<I256> = values.iter().map(|&v| I256::from(v)).collect();
+                serialize_vec_numeric(&values, out)
+            }
+            EthereumSqlTypeWrapper::I256(value) => {
+                serialize_numeric(value, out);
+                Ok(IsNull::No)
+            }
+            EthereumSqlTypeWrapper::VecI256(values) => serialize_vec_numeric(values, out),
+            EthereumSqlTypeWrapper::JSONB(value) => value.to_sql(_ty, out),
+            EthereumSqlTypeWrapper::Numeric(value) => match value {
+                Some(amount) => {
+                    serialize_scaled_numeric(amount, out);
+                    Ok(IsNull::No)
+                }
+                None => Ok(IsNull::Yes),
+            },
+            EthereumSqlTypeWrapper::NullableBool(value) => value.to_sql(_ty, out),
+            EthereumSqlTypeWrapper::NullableString(value) => value.to_sql(_ty, out),
+            EthereumSqlTypeWrapper::RawTopics(values) => {
+                let topics: Vec<&[u8]> = values.iter().map(|topic| topic.as_bytes()).collect();
+                topics.to_sql(_ty, out)
+            }
         }
     }
 
@@ -349,12 +540,8 @@ pub fn solidity_type_to_ethereum_sql_type_wrapper(
         } else {
             EthereumSqlTypeWrapper::Bytes(Bytes::new())
         }),
-        t if t.starts_with("int") || t.starts_with("uint") => {
-            let size: usize = if t.starts_with("int") {
-                t[3..].parse().unwrap_or(256)
-            } else {
-                t[4..].parse().unwrap_or(256)
-            };
+        t if t.starts_with("uint") => {
+            let size: usize = t[4..].parse().unwrap_or(256);
 
             Some(match size {
                 8 => {
@@ -403,6 +590,56 @@ pub fn solidity_type_to_ethereum_sql_type_wrapper(
                 _ => return None,
             })
         }
+        t if t.starts_with("int") => {
+            let size: usize = t[3..].parse().unwrap_or(256);
+
+            Some(match size {
+                8 => {
+                    if is_array {
+                        EthereumSqlTypeWrapper::VecI8(Vec::new())
+                    } else {
+                        EthereumSqlTypeWrapper::I8(0)
+                    }
+                }
+                16 => {
+                    if is_array {
+                        EthereumSqlTypeWrapper::VecI16(Vec::new())
+                    } else {
+                        EthereumSqlTypeWrapper::I16(0)
+                    }
+                }
+                24 | 32 => {
+                    if is_array {
+                        EthereumSqlTypeWrapper::VecI32(Vec::new())
+                    } else {
+                        EthereumSqlTypeWrapper::I32(0)
+                    }
+                }
+                40 | 48 | 56 | 64 => {
+                    if is_array {
+                        EthereumSqlTypeWrapper::VecI64(Vec::new())
+                    } else {
+                        EthereumSqlTypeWrapper::I64(0)
+                    }
+                }
+                72 | 80 | 88 | 96 | 104 | 112 | 120 | 128 => {
+                    if is_array {
+                        EthereumSqlTypeWrapper::VecI128(Vec::new())
+                    } else {
+                        EthereumSqlTypeWrapper::I128(0)
+                    }
+                }
+                136 | 144 | 152 | 160 | 168 | 176 | 184 | 192 | 200 | 208 | 216 | 224 | 232 |
+                240 | 248 | 256 => {
+                    if is_array {
+                        EthereumSqlTypeWrapper::VecI256(Vec::new())
+                    } else {
+                        EthereumSqlTypeWrapper::I256(I256::zero())
+                    }
+                }
+                _ => return None,
+            })
+        }
         _ => None,
     }
 }
@@ -410,11 +647,20 @@ pub fn solidity_type_to_ethereum_sql_type_wrapper(
 pub fn map_log_params_to_ethereum_wrapper(
     abi_inputs: &[ABIInput],
     params: &[LogParam],
+    tuples_as_jsonb: bool,
 ) -> Vec<EthereumSqlTypeWrapper> {
     let mut wrappers = vec![];
 
     for (index, param) in params.iter().enumerate() {
         if let Some(abi_input) = abi_inputs.get(index) {
+            if tuples_as_jsonb && abi_input.is_tuple() {
+                wrappers.push(EthereumSqlTypeWrapper::JSONB(map_token_to_json(
+                    abi_input,
+                    &param.value,
+                )));
+                continue;
+            }
+
             match &param.value {
                 Token::Tuple(tuple) => {
                     wrappers.extend(process_tuple(
@@ -437,6 +683,46 @@ pub fn map_log_params_to_ethereum_wrapper(
     wrappers
 }
 
+fn map_tuple_to_json(abi_inputs: &[ABIInput], tokens: &[Token]) -> Value {
+    let mut result = serde_json::Map::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        if let Some(abi_input) = abi_inputs.get(index) {
+            result.insert(abi_input.name.clone(), map_token_to_json(abi_input, token));
+        } else {
+            panic!("No ABI input found for tuple token at index: {}", index)
+        }
+    }
+
+    Value::Object(result)
+}
+
+/// Maps a token to JSON keeping tuples (and arrays of tuples) as objects with named fields
+fn map_token_to_json(abi_input: &ABIInput, token: &Token) -> Value {
+    match token {
+        Token::Tuple(tuple) => map_tuple_to_json(
+            abi_input.components.as_ref().expect("tuple should have a component ABI on"),
+            tuple,
+        ),
+        Token::FixedArray(tokens) | Token::Array(tokens) if abi_input.is_tuple() => {
+            let components =
+                abi_input.components.as_ref().expect("tuple should have a component ABI on");
+            Value::Array(
+                tokens
+                    .iter()
+                    .map(|token| match token {
+                        Token::Tuple(tuple) => map_tuple_to_json(components, tuple),
+                        _ => panic!("Expected tuple token in array for abi input: {:?}", abi_input),
+                    })
+                    .collect(),
+            )
+        }
+        _ => {
+            map_ethereum_wrapper_to_json_value(&map_log_token_to_ethereum_wrapper(abi_input, token))
+        }
+    }
+}
+
 fn process_tuple(abi_inputs: &[ABIInput], tokens: &[Token]) -> Vec<EthereumSqlTypeWrapper> {
     let mut wrappers = vec![];
 
@@ -484,6 +770,25 @@ fn convert_int(value: &Int, target_type: &EthereumSqlTypeWrapper) -> EthereumSql
         EthereumSqlTypeWrapper::U8(_) | EthereumSqlTypeWrapper::VecU8(_) => {
             EthereumSqlTypeWrapper::U8(value.low_u32() as u8)
         }
+        // signed values are abi encoded as sign extended two's complement words
+        EthereumSqlTypeWrapper::I256(_) | EthereumSqlTypeWrapper::VecI256(_) => {
+            EthereumSqlTypeWrapper::I256(I256::from_raw(*value))
+        }
+        EthereumSqlTypeWrapper::I128(_) | EthereumSqlTypeWrapper::VecI128(_) => {
+            EthereumSqlTypeWrapper::I128(I256::from_raw(*value).low_i128())
+        }
+        EthereumSqlTypeWrapper::I64(_) | EthereumSqlTypeWrapper::VecI64(_) => {
+            EthereumSqlTypeWrapper::I64(I256::from_raw(*value).low_i64())
+        }
+        EthereumSqlTypeWrapper::I32(_) | EthereumSqlTypeWrapper::VecI32(_) => {
+            EthereumSqlTypeWrapper::I32(I256::from_raw(*value).low_i32())
+        }
+        EthereumSqlTypeWrapper::I16(_) | EthereumSqlTypeWrapper::VecI16(_) => {
+            EthereumSqlTypeWrapper::I16(I256::from_raw(*value).low_i16())
+        }
+        EthereumSqlTypeWrapper::I8(_) | EthereumSqlTypeWrapper::VecI8(_) => {
+            EthereumSqlTypeWrapper::I8(I256::from_raw(*value).low_i8())
+        }
         _ => {
             let error_message = format!("Unsupported target type - {:?}", target_type);
             error!("{}", error_message);
@@ -643,6 +948,76 @@ fn map_log_token_to_ethereum_wrapper(
                                         })
                                         .collect(),
                                 ),
+                                EthereumSqlTypeWrapper::I256(_) |
+                                EthereumSqlTypeWrapper::VecI256(_) => {
+                                    EthereumSqlTypeWrapper::VecI256(
+                                        vec_wrapper
+                                            .into_iter()
+                                            .map(|w| match w {
+                                                EthereumSqlTypeWrapper::I256(v) => v,
+                                                _ => unreachable!(),
+                                            })
+                                            .collect(),
+                                    )
+                                }
+                                EthereumSqlTypeWrapper::I128(_) |
+                                EthereumSqlTypeWrapper::VecI128(_) => {
+                                    EthereumSqlTypeWrapper::VecI128(
+                                        vec_wrapper
+                                            .into_iter()
+                                            .map(|w| match w {
+                                                EthereumSqlTypeWrapper::I128(v) => v,
+                                                _ => unreachable!(),
+                                            })
+                                            .collect(),
+                                    )
+                                }
+                                EthereumSqlTypeWrapper::I64(_) |
+                                EthereumSqlTypeWrapper::VecI64(_) => {
+                                    EthereumSqlTypeWrapper::VecI64(
+                                        vec_wrapper
+                                            .into_iter()
+                                            .map(|w| match w {
+                                                EthereumSqlTypeWrapper::I64(v) => v,
+                                                _ => unreachable!(),
+                                            })
+                                            .collect(),
+                                    )
+                                }
+                                EthereumSqlTypeWrapper::I32(_) |
+                                EthereumSqlTypeWrapper::VecI32(_) => {
+                                    EthereumSqlTypeWrapper::VecI32(
+                                        vec_wrapper
+                                            .into_iter()
+                                            .map(|w| match w {
+                                                EthereumSqlTypeWrapper::I32(v) => v,
+                                                _ => unreachable!(),
+                                            })
+                                            .collect(),
+                                    )
+                                }
+                                EthereumSqlTypeWrapper::I16(_) |
+                                EthereumSqlTypeWrapper::VecI16(_) => {
+                                    EthereumSqlTypeWrapper::VecI16(
+                                        vec_wrapper
+                                            .into_iter()
+                                            .map(|w| match w {
+                                                EthereumSqlTypeWrapper::I16(v) => v,
+                                                _ => unreachable!(),
+                                            })
+                                            .collect(),
+                                    )
+                                }
+                                EthereumSqlTypeWrapper::I8(_) |
+                                EthereumSqlTypeWrapper::VecI8(_) => EthereumSqlTypeWrapper::VecI8(
+                                    vec_wrapper
+                                        .into_iter()
+                                        .map(|w| match w {
+                                            EthereumSqlTypeWrapper::I8(v) => v,
+                                            _ => unreachable!(),
+                                        })
+                                        .collect(),
+                                ),
                                 _ => panic!("Unknown int type for abi input: {:?}", abi_input),
                             }
                         }
@@ -670,8 +1045,7 @@ fn map_log_token_to_ethereum_wrapper(
                             unreachable!("Nested arrays are not supported by the EVM")
                         }
                         Token::Tuple(_) => {
-                            // TODO - this is not supported yet
-                            panic!("Array tuple not supported yet - please raise issue in github with ABI to recreate and we will fix")
+                            panic!("Array tuples can only be stored with `tuples_as_jsonb` enabled for the event in the contract storage config")
                         }
                     }
                 }
@@ -720,6 +1094,105 @@ fn serialize_vec_decimal<T: ToString>(
     Ok(IsNull::No)
 }
 
+/// Writes a whole number in the postgres NUMERIC binary format, `Decimal` can not be used here as
+/// it only holds 96 bits which is not enough for int128 and int256 values.
+fn serialize_numeric(value: &I256, out: &mut BytesMut) {
+    let base = U256::from(10_000);
+
+    // NUMERIC stores base 10000 digits with the most significant first
+    let mut digits: Vec<i16> = vec![];
+    let mut remaining = value.unsigned_abs();
+    while !remaining.is_zero() {
+        let (quotient, digit) = remaining.div_mod(base);
+        digits.push(digit.as_u32() as i16);
+        remaining = quotient;
+    }
+    digits.reverse();
+
+    let sign: u16 = if value.is_negative() { 0x4000 } else { 0x0000 };
+
+    out.extend_from_slice(&(digits.len() as i16).to_be_bytes()); // Number of digits
+    out.extend_from_slice(&(digits.len().saturating_sub(1) as i16).to_be_bytes()); // Weight
+    out.extend_from_slice(&sign.to_be_bytes()); // Sign
+    out.extend_from_slice(&0i16.to_be_bytes()); // Display scale
+    for digit in digits {
+        out.extend_from_slice(&digit.to_be_bytes());
+    }
+}
+
+/// Writes an amount with decimals in the postgres NUMERIC binary format, base 10000 digits are
+/// aligned on the decimal point so the whole and fractional digits are grouped separately
+fn serialize_scaled_numeric(amount: &ScaledAmount, out: &mut BytesMut) {
+    let (whole, fraction) = amount.split_digits();
+    let whole = whole.trim_start_matches('0');
+
+    let group = |digits: &str| -> Vec<i16> {
+        digits
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or("0").parse::<i16>().unwrap_or(0))
+            .collect()
+    };
+
+    let whole_digits = group(&format!("{:0>width$}", whole, width = whole.len().div_ceil(4) * 4));
+    let fraction_digits =
+        group(&format!("{:0<width$}", fraction, width = fraction.len().div_ceil(4) * 4));
+
+    let mut digits = whole_digits.clone();
+    digits.extend(&fraction_digits);
+    let mut weight = whole_digits.len() as i16 - 1;
+
+    // leading zero groups only exist when there is no whole part
+    while digits.first() == Some(&0) {
+        digits.remove(0);
+        weight -= 1;
+    }
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    if digits.is_empty() {
+        weight = 0;
+    }
+
+    let sign: u16 = if amount.negative && !digits.is_empty() { 0x4000 } else { 0x0000 };
+
+    out.extend_from_slice(&(digits.len() as i16).to_be_bytes()); // Number of digits
+    out.extend_from_slice(&weight.to_be_bytes()); // Weight
+    out.extend_from_slice(&sign.to_be_bytes()); // Sign
+    out.extend_from_slice(&(fraction.len() as i16).to_be_bytes()); // Display scale
+    for digit in digits {
+        out.extend_from_slice(&digit.to_be_bytes());
+    }
+}
+
+fn serialize_vec_numeric(
+    values: &[I256],
+    out: &mut BytesMut,
+) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+    if values.is_empty() {
+        return Ok(IsNull::Yes);
+    }
+
+    let mut buf = BytesMut::new();
+    buf.extend_from_slice(&(1i32.to_be_bytes())); // Number of dimensions
+    buf.extend_from_slice(&(0i32.to_be_bytes())); // Has nulls flag
+    buf.extend_from_slice(&PgType::NUMERIC.oid().to_be_bytes()); // Element type OID for numeric
+
+    // Upper and lower bounds for dimensions
+    buf.extend_from_slice(&(values.len() as i32).to_be_bytes()); // Length of the array
+    buf.extend_from_slice(&(1i32.to_be_bytes())); // Index lower bound
+
+    for value in values {
+        let mut elem_buf = BytesMut::new();
+        serialize_numeric(value, &mut elem_buf);
+        buf.extend_from_slice(&(elem_buf.len() as i32).to_be_bytes()); // Length of the element
+        buf.extend_from_slice(&elem_buf); // The element itself
+    }
+
+    out.extend_from_slice(&buf);
+    Ok(IsNull::No)
+}
+
 fn count_components(components: &[ABIInput]) -> usize {
     components
         .iter()
@@ -735,6 +1208,158 @@ fn count_components(components: &[ABIInput]) -> usize {
         .sum()
 }
 
+pub(crate) fn map_ethereum_wrapper_to_json_value(wrapper: &EthereumSqlTypeWrapper) -> Value {
+    match wrapper {
+        EthereumSqlTypeWrapper::U64(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU64(u64s) => json!(u64s),
+        EthereumSqlTypeWrapper::U128(u) => json!(u.to_string()),
+        EthereumSqlTypeWrapper::VecU128(u128s) => {
+            json!(u128s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::U256(u) => json!(u.to_string()),
+        EthereumSqlTypeWrapper::VecU256(u256s) => {
+            json!(u256s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::U512(u) => json!(u.to_string()),
+        EthereumSqlTypeWrapper::VecU512(u512s) => {
+            json!(u512s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::H128(h) => json!(h),
+        EthereumSqlTypeWrapper::VecH128(h128s) => json!(h128s),
+        EthereumSqlTypeWrapper::H160(h) => json!(h),
+        EthereumSqlTypeWrapper::VecH160(h160s) => json!(h160s),
+        EthereumSqlTypeWrapper::H256(h) => json!(h),
+        EthereumSqlTypeWrapper::VecH256(h256s) => json!(h256s),
+        EthereumSqlTypeWrapper::H512(h) => json!(h),
+        EthereumSqlTypeWrapper::VecH512(h512s) => json!(h512s),
+        EthereumSqlTypeWrapper::Address(address) => json!(address),
+        EthereumSqlTypeWrapper::VecAddress(addresses) => json!(addresses),
+        EthereumSqlTypeWrapper::Bool(b) => json!(b),
+        EthereumSqlTypeWrapper::VecBool(bools) => json!(bools),
+        EthereumSqlTypeWrapper::U32(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU32(u32s) => json!(u32s),
+        EthereumSqlTypeWrapper::U16(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU16(u16s) => json!(u16s),
+        EthereumSqlTypeWrapper::U8(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU8(u8s) => json!(u8s),
+        EthereumSqlTypeWrapper::I8(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI8(i8s) => json!(i8s),
+        EthereumSqlTypeWrapper::I16(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI16(i16s) => json!(i16s),
+        EthereumSqlTypeWrapper::I32(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI32(i32s) => json!(i32s),
+        EthereumSqlTypeWrapper::I64(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI64(i64s) => json!(i64s),
+        EthereumSqlTypeWrapper::I128(i) => json!(i.to_string()),
+        EthereumSqlTypeWrapper::VecI128(i128s) => {
+            json!(i128s.iter().map(|i| i.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::I256(i) => json!(i.to_string()),
+        EthereumSqlTypeWrapper::VecI256(i256s) => {
+            json!(i256s.iter().map(|i| i.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::String(s) => json!(s),
+        EthereumSqlTypeWrapper::VecString(strings) => json!(strings),
+        EthereumSqlTypeWrapper::Bytes(bytes) => json!(hex::encode(bytes)),
+        EthereumSqlTypeWrapper::VecBytes(bytes) => {
+            json!(bytes.iter().map(hex::encode).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::JSONB(json) => json.clone(),
+        EthereumSqlTypeWrapper::Numeric(value) => {
+            value.map_or(Value::Null, |amount| json!(amount.to_decimal_string()))
+        }
+        EthereumSqlTypeWrapper::NullableBool(value) => json!(value),
+        EthereumSqlTypeWrapper::NullableString(value) => json!(value),
+        EthereumSqlTypeWrapper::RawTopics(topics) => json!(topics),
+    }
+}
+
+fn hex_string<T: std::fmt::Debug>(value: &T) -> Value {
+    json!(format!("{:?}", value))
+}
+
+/// Maps the wrapper to a json value which keeps the column type, numbers wider than 64 bits are
+/// strings so they are not rounded by json parsers and hashes, addresses and bytes are 0x hex
+pub fn map_ethereum_wrapper_to_typed_json_value(wrapper: &EthereumSqlTypeWrapper) -> Value {
+    match wrapper {
+        EthereumSqlTypeWrapper::U64(u) => json!(u.as_u64()),
+        EthereumSqlTypeWrapper::VecU64(u64s) => {
+            json!(u64s.iter().map(|u| u.as_u64()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::U128(u) => json!(u.to_string()),
+        EthereumSqlTypeWrapper::VecU128(u128s) => {
+            json!(u128s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::U256(u) => json!(u.to_string()),
+        EthereumSqlTypeWrapper::VecU256(u256s) => {
+            json!(u256s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::U512(u) => json!(u.to_string()),
+        EthereumSqlTypeWrapper::VecU512(u512s) => {
+            json!(u512s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::I128(i) => json!(i.to_string()),
+        EthereumSqlTypeWrapper::VecI128(i128s) => {
+            json!(i128s.iter().map(|i| i.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::I256(i) => json!(i.to_string()),
+        EthereumSqlTypeWrapper::VecI256(i256s) => {
+            json!(i256s.iter().map(|i| i.to_string()).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::H128(h) => hex_string(h),
+        EthereumSqlTypeWrapper::VecH128(h128s) => {
+            Value::Array(h128s.iter().map(hex_string).collect())
+        }
+        EthereumSqlTypeWrapper::H160(h) => hex_string(h),
+        EthereumSqlTypeWrapper::VecH160(h160s) => {
+            Value::Array(h160s.iter().map(hex_string).collect())
+        }
+        EthereumSqlTypeWrapper::H256(h) => hex_string(h),
+        EthereumSqlTypeWrapper::VecH256(h256s) => {
+            Value::Array(h256s.iter().map(hex_string).collect())
+        }
+        EthereumSqlTypeWrapper::H512(h) => hex_string(h),
+        EthereumSqlTypeWrapper::VecH512(h512s) => {
+            Value::Array(h512s.iter().map(hex_string).collect())
+        }
+        EthereumSqlTypeWrapper::Address(address) => hex_string(address),
+        EthereumSqlTypeWrapper::VecAddress(addresses) => {
+            Value::Array(addresses.iter().map(hex_string).collect())
+        }
+        EthereumSqlTypeWrapper::Bool(b) => json!(b),
+        EthereumSqlTypeWrapper::VecBool(bools) => json!(bools),
+        EthereumSqlTypeWrapper::U32(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU32(u32s) => json!(u32s),
+        EthereumSqlTypeWrapper::U16(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU16(u16s) => json!(u16s),
+        EthereumSqlTypeWrapper::U8(u) => json!(u),
+        EthereumSqlTypeWrapper::VecU8(u8s) => json!(u8s),
+        EthereumSqlTypeWrapper::I8(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI8(i8s) => json!(i8s),
+        EthereumSqlTypeWrapper::I16(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI16(i16s) => json!(i16s),
+        EthereumSqlTypeWrapper::I32(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI32(i32s) => json!(i32s),
+        EthereumSqlTypeWrapper::I64(i) => json!(i),
+        EthereumSqlTypeWrapper::VecI64(i64s) => json!(i64s),
+        EthereumSqlTypeWrapper::String(s) => json!(s),
+        EthereumSqlTypeWrapper::VecString(strings) => json!(strings),
+        EthereumSqlTypeWrapper::Bytes(bytes) => json!(format!("0x{}", hex::encode(bytes))),
+        EthereumSqlTypeWrapper::VecBytes(bytes) => {
+            json!(bytes.iter().map(|b| format!("0x{}", hex::encode(b))).collect::<Vec<_>>())
+        }
+        EthereumSqlTypeWrapper::JSONB(json) => json!(json.to_string()),
+        EthereumSqlTypeWrapper::Numeric(value) => {
+            value.map_or(Value::Null, |amount| json!(amount.to_decimal_string()))
+        }
+        EthereumSqlTypeWrapper::NullableBool(value) => json!(value),
+        EthereumSqlTypeWrapper::NullableString(value) => json!(value),
+        EthereumSqlTypeWrapper::RawTopics(topics) => {
+            Value::Array(topics.iter().map(hex_string).collect())
+        }
+    }
+}
+
 pub fn map_ethereum_wrapper_to_json(
     abi_inputs: &[ABIInput],
     wrappers: &[EthereumSqlTypeWrapper],
@@ -751,7 +1376,12 @@ pub fn map_ethereum_wrapper_to_json(
             continue;
         }
         if let Some(wrapper) = wrappers.get(current_wrapper_index) {
-            if abi_input.type_ == "tuple" {
+            if let EthereumSqlTypeWrapper::JSONB(json) = wrapper {
+                // tuples stored as jsonb already hold the named structure
+                result.insert(abi_input.name.clone(), json.clone());
+                wrappers_index_processed.push(current_wrapper_index);
+                current_wrapper_index += 1;
+            } else if abi_input.type_ == "tuple" {
                 let components =
                     abi_input.components.as_ref().expect("Tuple should have components defined");
                 let total_properties = count_components(components);
@@ -767,46 +1397,7 @@ pub fn map_ethereum_wrapper_to_json(
                 }
                 current_wrapper_index = total_properties;
             } else {
-                let value = match wrapper {
-                    EthereumSqlTypeWrapper::U64(u) => json!(u),
-                    EthereumSqlTypeWrapper::VecU64(u64s) => json!(u64s),
-                    EthereumSqlTypeWrapper::U128(u) => json!(u.to_string()),
-                    EthereumSqlTypeWrapper::VecU128(u128s) => {
-                        json!(u128s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
-                    }
-                    EthereumSqlTypeWrapper::U256(u) => json!(u.to_string()),
-                    EthereumSqlTypeWrapper::VecU256(u256s) => {
-                        json!(u256s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
-                    }
-                    EthereumSqlTypeWrapper::U512(u) => json!(u.to_string()),
-                    EthereumSqlTypeWrapper::VecU512(u512s) => {
-                        json!(u512s.iter().map(|u| u.to_string()).collect::<Vec<_>>())
-                    }
-                    EthereumSqlTypeWrapper::H128(h) => json!(h),
-                    EthereumSqlTypeWrapper::VecH128(h128s) => json!(h128s),
-                    EthereumSqlTypeWrapper::H160(h) => json!(h),
-                    EthereumSqlTypeWrapper::VecH160(h160s) => json!(h160s),
-                    EthereumSqlTypeWrapper::H256(h) => json!(h),
-                    EthereumSqlTypeWrapper::VecH256(h256s) => json!(h256s),
-                    EthereumSqlTypeWrapper::H512(h) => json!(h),
-                    EthereumSqlTypeWrapper::VecH512(h512s) => json!(h512s),
-                    EthereumSqlTypeWrapper::Address(address) => json!(address),
-                    EthereumSqlTypeWrapper::VecAddress(addresses) => json!(addresses),
-                    EthereumSqlTypeWrapper::Bool(b) => json!(b),
-                    EthereumSqlTypeWrapper::VecBool(bools) => json!(bools),
-                    EthereumSqlTypeWrapper::U32(u) => json!(u),
-                    EthereumSqlTypeWrapper::VecU32(u32s) => json!(u32s),
-                    EthereumSqlTypeWrapper::U16(u) => json!(u),
-                    EthereumSqlTypeWrapper::VecU16(u16s) => json!(u16s),
-                    EthereumSqlTypeWrapper::U8(u) => json!(u),
-                    EthereumSqlTypeWrapper::VecU8(u8s) => json!(u8s),
-                    EthereumSqlTypeWrapper::String(s) => json!(s),
-                    EthereumSqlTypeWrapper::VecString(strings) => json!(strings),
-                    EthereumSqlTypeWrapper::Bytes(bytes) => json!(hex::encode(bytes)),
-                    EthereumSqlTypeWrapper::VecBytes(bytes) => {
-                        json!(bytes.iter().map(hex::encode).collect::<Vec<_>>())
-                    }
-                };
+                let value = map_ethereum_wrapper_to_json_value(wrapper);
                 result.insert(abi_input.name.clone(), value);
                 wrappers_index_processed.push(current_wrapper_index);
                 current_wrapper_index += 1;
@@ -826,3 +1417,525 @@ pub fn map_ethereum_wrapper_to_json(
 
     Value::Object(result)
 }
+
+fn json_to_u256(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(value) => match value.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(value).ok(),
+        },
+        _ => None,
+    }
+}
+
+fn json_to_u512(value: &Value) -> Option<U512> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U512::from),
+        Value::String(value) => match value.strip_prefix("0x") {
+            Some(hex) => U512::from_str_radix(hex, 16).ok(),
+            None => U512::from_dec_str(value).ok(),
+        },
+        _ => None,
+    }
+}
+
+fn json_to_i256(value: &Value) -> Option<I256> {
+    match value {
+        Value::Number(number) => number.as_i64().map(I256::from),
+        Value::String(value) => I256::from_dec_str(value).ok(),
+        _ => None,
+    }
+}
+
+/// Integers up to 128 bits, each wrapper checks the value fits its own width
+fn json_to_i128(value: &Value) -> Option<i128> {
+    match value {
+        Value::Number(number) => {
+            number.as_i64().map(i128::from).or_else(|| number.as_u64().map(i128::from))
+        }
+        Value::String(value) => value.parse().ok(),
+        _ => None,
+    }
+}
+
+fn json_to_hex_bytes(value: &Value) -> Option<Vec<u8>> {
+    let value = value.as_str()?;
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).ok()
+}
+
+fn json_to_hash<T: From<[u8; N]>, const N: usize>(value: &Value) -> Option<T> {
+    json_to_hex_bytes(value)?.try_into().ok().map(T::from)
+}
+
+fn json_to_vec<T>(value: &Value, map: impl Fn(&Value) -> Option<T>) -> Option<Vec<T>> {
+    value.as_array()?.iter().map(map).collect()
+}
+
+/// Reads a json value back into a wrapper of the same type as `like`, `None` when the value does
+/// not fit that type. Numbers can be json numbers, decimal strings or 0x hex for unsigned ones.
+pub fn map_json_value_to_ethereum_wrapper(
+    like: &EthereumSqlTypeWrapper,
+    value: &Value,
+) -> Option<EthereumSqlTypeWrapper> {
+    let u64 = |value: &Value| {
+        json_to_u256(value).filter(|v| v.bits() <= 64).map(|v| U64::from(v.as_u64()))
+    };
+    let u128 = |value: &Value| {
+        json_to_u256(value).filter(|v| v.bits() <= 128).map(|v| U128::from(v.as_u128()))
+    };
+    let small = |value: &Value| json_to_i128(value);
+    let bytes = |value: &Value| json_to_hex_bytes(value).map(Bytes::from);
+
+    Some(match like {
+        EthereumSqlTypeWrapper::U64(_) => EthereumSqlTypeWrapper::U64(u64(value)?),
+        EthereumSqlTypeWrapper::VecU64(_) => {
+            EthereumSqlTypeWrapper::VecU64(json_to_vec(value, u64)?)
+        }
+        EthereumSqlTypeWrapper::U128(_) => EthereumSqlTypeWrapper::U128(u128(value)?),
+        EthereumSqlTypeWrapper::VecU128(_) => {
+            EthereumSqlTypeWrapper::VecU128(json_to_vec(value, u128)?)
+        }
+        EthereumSqlTypeWrapper::U256(_) => EthereumSqlTypeWrapper::U256(json_to_u256(value)?),
+        EthereumSqlTypeWrapper::VecU256(_) => {
+            EthereumSqlTypeWrapper::VecU256(json_to_vec(value, json_to_u256)?)
+        }
+        EthereumSqlTypeWrapper::U512(_) => EthereumSqlTypeWrapper::U512(json_to_u512(value)?),
+        EthereumSqlTypeWrapper::VecU512(_) => {
+            EthereumSqlTypeWrapper::VecU512(json_to_vec(value, json_to_u512)?)
+        }
+        EthereumSqlTypeWrapper::H128(_) => EthereumSqlTypeWrapper::H128(json_to_hash(value)?),
+        EthereumSqlTypeWrapper::VecH128(_) => {
+            EthereumSqlTypeWrapper::VecH128(json_to_vec(value, json_to_hash)?)
+        }
+        EthereumSqlTypeWrapper::H160(_) => EthereumSqlTypeWrapper::H160(json_to_hash(value)?),
+        EthereumSqlTypeWrapper::VecH160(_) => {
+            EthereumSqlTypeWrapper::VecH160(json_to_vec(value, json_to_hash)?)
+        }
+        EthereumSqlTypeWrapper::H256(_) => EthereumSqlTypeWrapper::H256(json_to_hash(value)?),
+        EthereumSqlTypeWrapper::VecH256(_) => {
+            EthereumSqlTypeWrapper::VecH256(json_to_vec(value, json_to_hash)?)
+        }
+        EthereumSqlTypeWrapper::H512(_) => EthereumSqlTypeWrapper::H512(json_to_hash(value)?),
+        EthereumSqlTypeWrapper::VecH512(_) => {
+            EthereumSqlTypeWrapper::VecH512(json_to_vec(value, json_to_hash)?)
+        }
+        EthereumSqlTypeWrapper::Address(_) => EthereumSqlTypeWrapper::Address(json_to_hash(value)?),
+        EthereumSqlTypeWrapper::VecAddress(_) => {
+            EthereumSqlTypeWrapper::VecAddress(json_to_vec(value, json_to_hash)?)
+        }
+        EthereumSqlTypeWrapper::Bool(_) => EthereumSqlTypeWrapper::Bool(value.as_bool()?),
+        EthereumSqlTypeWrapper::VecBool(_) => {
+            EthereumSqlTypeWrapper::VecBool(json_to_vec(value, Value::as_bool)?)
+        }
+        EthereumSqlTypeWrapper::U32(_) => {
+            EthereumSqlTypeWrapper::U32(small(value)?.try_into().ok()?)
+        }
+        EthereumSqlTypeWrapper::VecU32(_) => {
+            EthereumSqlTypeWrapper::VecU32(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::U16(_) => {
+            EthereumSqlTypeWrapper::U16(small(value)?.try_into().ok()?)
+        }
+        EthereumSqlTypeWrapper::VecU16(_) => {
+            EthereumSqlTypeWrapper::VecU16(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::U8(_) => EthereumSqlTypeWrapper::U8(small(value)?.try_into().ok()?),
+        EthereumSqlTypeWrapper::VecU8(_) => {
+            EthereumSqlTypeWrapper::VecU8(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::I8(_) => EthereumSqlTypeWrapper::I8(small(value)?.try_into().ok()?),
+        EthereumSqlTypeWrapper::VecI8(_) => {
+            EthereumSqlTypeWrapper::VecI8(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::I16(_) => {
+            EthereumSqlTypeWrapper::I16(small(value)?.try_into().ok()?)
+        }
+        EthereumSqlTypeWrapper::VecI16(_) => {
+            EthereumSqlTypeWrapper::VecI16(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::I32(_) => {
+            EthereumSqlTypeWrapper::I32(small(value)?.try_into().ok()?)
+        }
+        EthereumSqlTypeWrapper::VecI32(_) => {
+            EthereumSqlTypeWrapper::VecI32(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::I64(_) => {
+            EthereumSqlTypeWrapper::I64(small(value)?.try_into().ok()?)
+        }
+        EthereumSqlTypeWrapper::VecI64(_) => {
+            EthereumSqlTypeWrapper::VecI64(json_to_vec(value, |v| small(v)?.try_into().ok())?)
+        }
+        EthereumSqlTypeWrapper::I128(_) => EthereumSqlTypeWrapper::I128(small(value)?),
+        EthereumSqlTypeWrapper::VecI128(_) => {
+            EthereumSqlTypeWrapper::VecI128(json_to_vec(value, small)?)
+        }
+        EthereumSqlTypeWrapper::I256(_) => EthereumSqlTypeWrapper::I256(json_to_i256(value)?),
+        EthereumSqlTypeWrapper::VecI256(_) => {
+            EthereumSqlTypeWrapper::VecI256(json_to_vec(value, json_to_i256)?)
+        }
+        EthereumSqlTypeWrapper::String(_) => {
+            EthereumSqlTypeWrapper::String(value.as_str()?.to_string())
+        }
+        EthereumSqlTypeWrapper::VecString(_) => {
+            EthereumSqlTypeWrapper::VecString(json_to_vec(value, |v| v.as_str().map(String::from))?)
+        }
+        EthereumSqlTypeWrapper::Bytes(_) => EthereumSqlTypeWrapper::Bytes(bytes(value)?),
+        EthereumSqlTypeWrapper::VecBytes(_) => {
+            EthereumSqlTypeWrapper::VecBytes(json_to_vec(value, bytes)?)
+        }
+        EthereumSqlTypeWrapper::JSONB(_) => EthereumSqlTypeWrapper::JSONB(value.clone()),
+        EthereumSqlTypeWrapper::Numeric(_) => EthereumSqlTypeWrapper::Numeric(match value {
+            Value::Null => None,
+            Value::Number(number) => Some(ScaledAmount::from_decimal_str(&number.to_string())?),
+            Value::String(value) => Some(ScaledAmount::from_decimal_str(value)?),
+            _ => return None,
+        }),
+        EthereumSqlTypeWrapper::NullableBool(_) => {
+            EthereumSqlTypeWrapper::NullableBool(match value {
+                Value::Null => None,
+                value => Some(value.as_bool()?),
+            })
+        }
+        EthereumSqlTypeWrapper::NullableString(_) => {
+            EthereumSqlTypeWrapper::NullableString(match value {
+                Value::Null => None,
+                value => Some(value.as_str()?.to_string()),
+            })
+        }
+        EthereumSqlTypeWrapper::RawTopics(_) => return None,
+    })
+}
+
+/// Reads the event inputs back from an event json like `map_ethereum_wrapper_to_json` builds, each
+/// wrapper keeps the type of the decoded one so the row still fits the event table. Every input
+/// must be there and tuples can not hold unknown fields, the caller checks the top level keys.
+pub fn map_json_to_ethereum_wrappers(
+    abi_inputs: &[ABIInput],
+    decoded: &[EthereumSqlTypeWrapper],
+    json: &serde_json::Map<String, Value>,
+) -> Result<Vec<EthereumSqlTypeWrapper>, String> {
+    fn read(
+        abi_inputs: &[ABIInput],
+        decoded: &[EthereumSqlTypeWrapper],
+        json: &serde_json::Map<String, Value>,
+        path: &str,
+        wrappers: &mut Vec<EthereumSqlTypeWrapper>,
+    ) -> Result<(), String> {
+        for abi_input in abi_inputs {
+            let name = format!("{}{}", path, abi_input.name);
+            let value =
+                json.get(&abi_input.name).ok_or_else(|| format!("`{}` is missing", name))?;
+            let wrapper = decoded
+                .get(wrappers.len())
+                .ok_or_else(|| format!("`{}` has no decoded value", name))?;
+
+            match (wrapper, &abi_input.components) {
+                // tuples stored as jsonb keep whatever structure they are given
+                (EthereumSqlTypeWrapper::JSONB(_), _) => {
+                    wrappers.push(EthereumSqlTypeWrapper::JSONB(value.clone()))
+                }
+                (_, Some(components)) if abi_input.type_ == "tuple" => {
+                    let tuple =
+                        value.as_object().ok_or_else(|| format!("`{}` must be an object", name))?;
+                    if let Some(key) =
+                        tuple.keys().find(|key| !components.iter().any(|c| &c.name == *key))
+                    {
+                        return Err(format!("`{}.{}` is not a field of the tuple", name, key));
+                    }
+                    read(components, decoded, tuple, &format!("{}.", name), wrappers)?;
+                }
+                _ => wrappers.push(map_json_value_to_ethereum_wrapper(wrapper, value).ok_or_else(
+                    || format!("`{}` can not be stored as {}", name, abi_input.type_),
+                )?),
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut wrappers = Vec::with_capacity(decoded.len());
+    read(abi_inputs, decoded, json, "", &mut wrappers)?;
+    Ok(wrappers)
+}
+
+/// Reads the event inputs back from an event json into log params so an event replaced by a script
+/// or transform is seen by the entities and rollups like a decoded one
+pub fn map_json_to_log_params(
+    abi_inputs: &[ABIInput],
+    json: &serde_json::Map<String, Value>,
+) -> Result<Vec<LogParam>, String> {
+    abi_inputs
+        .iter()
+        .map(|abi_input| {
+            let value = json
+                .get(&abi_input.name)
+                .ok_or_else(|| format!("`{}` is missing", abi_input.name))?;
+            Ok(LogParam {
+                name: abi_input.name.clone(),
+                value: map_json_value_to_token(
+                    abi_input,
+                    &abi_input.type_,
+                    value,
+                    &abi_input.name,
+                )?,
+            })
+        })
+        .collect()
+}
+
+/// Arrays are read element by element with the type inside their brackets, tuples by the names of
+/// their components
+fn map_json_value_to_token(
+    abi_input: &ABIInput,
+    type_: &str,
+    value: &Value,
+    name: &str,
+) -> Result<Token, String> {
+    let invalid = || format!("`{}` can not be read as {}", name, type_);
+
+    if let Some(array_type) = type_.strip_suffix(']') {
+        let (element_type, size) = array_type.rsplit_once('[').ok_or_else(invalid)?;
+        let tokens = value
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|value| map_json_value_to_token(abi_input, element_type, value, name))
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(if size.is_empty() { Token::Array(tokens) } else { Token::FixedArray(tokens) });
+    }
+
+    let token = match type_ {
+        "tuple" => {
+            let components = abi_input.components.as_ref().ok_or_else(invalid)?;
+            let tuple = value.as_object().ok_or_else(invalid)?;
+            let tokens = components
+                .iter()
+                .map(|component| {
+                    let name = format!("{}.{}", name, component.name);
+                    let value = tuple
+                        .get(&component.name)
+                        .ok_or_else(|| format!("`{}` is missing", name))?;
+                    map_json_value_to_token(component, &component.type_, value, &name)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(Token::Tuple(tokens))
+        }
+        "address" => json_to_hash(value).map(Token::Address),
+        "bool" => value.as_bool().map(Token::Bool),
+        "string" => value.as_str().map(|value| Token::String(value.to_string())),
+        "bytes" => json_to_hex_bytes(value).map(Token::Bytes),
+        _ if type_.starts_with("bytes") => json_to_hex_bytes(value).map(Token::FixedBytes),
+        _ if type_.starts_with("uint") => json_to_u256(value).map(Token::Uint),
+        _ if type_.starts_with("int") => {
+            json_to_i256(value).map(|value| Token::Int(value.into_raw()))
+        }
+        _ => None,
+    };
+
+    token.ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abi_input(type_: &str) -> ABIInput {
+        ABIInput {
+            indexed: None,
+            name: "value".to_string(),
+            type_: type_.to_string(),
+            components: None,
+        }
+    }
+
+    #[test]
+    fn test_signed_ints_keep_their_sign() {
+        let minus_one = Token::Int(U256::MAX);
+
+        match map_log_token_to_ethereum_wrapper(&abi_input("int24"), &minus_one) {
+            EthereumSqlTypeWrapper::I32(value) => assert_eq!(value, -1),
+            other => panic!("expected I32 got {:?}", other),
+        }
+
+        match map_log_token_to_ethereum_wrapper(&abi_input("int256"), &minus_one) {
+            EthereumSqlTypeWrapper::I256(value) => assert_eq!(value, I256::minus_one()),
+            other => panic!("expected I256 got {:?}", other),
+        }
+
+        let ticks = Token::Array(vec![Token::Int(U256::MAX), Token::Int(U256::from(887272))]);
+        match map_log_token_to_ethereum_wrapper(&abi_input("int24[]"), &ticks) {
+            EthereumSqlTypeWrapper::VecI32(values) => assert_eq!(values, vec![-1, 887272]),
+            other => panic!("expected VecI32 got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tuples_as_jsonb() {
+        let order = ABIInput {
+            indexed: None,
+            name: "orders".to_string(),
+            type_: "tuple[]".to_string(),
+            components: Some(vec![abi_input("int24"), {
+                let mut maker = abi_input("address");
+                maker.name = "maker".to_string();
+                maker
+            }]),
+        };
+        let params = vec![LogParam {
+            name: "orders".to_string(),
+            value: Token::Array(vec![Token::Tuple(vec![
+                Token::Int(U256::MAX),
+                Token::Address(Address::zero()),
+            ])]),
+        }];
+
+        let wrappers = map_log_params_to_ethereum_wrapper(&[order], &params, true);
+        match wrappers.as_slice() {
+            [EthereumSqlTypeWrapper::JSONB(json)] => assert_eq!(
+                json,
+                &json!([{ "value": -1, "maker": "0x0000000000000000000000000000000000000000" }])
+            ),
+            other => panic!("expected a single JSONB wrapper got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_serialize_numeric() {
+        let mut out = BytesMut::new();
+        serialize_numeric(&I256::from(-1234567890i64), &mut out);
+        assert_eq!(
+            out.to_vec(),
+            vec![0, 3, 0, 2, 0x40, 0, 0, 0, 0, 12, 0x0d, 0x80, 0x1e, 0xd2],
+            "-12|3456|7890 in base 10000"
+        );
+
+        let mut out = BytesMut::new();
+        serialize_numeric(&I256::zero(), &mut out);
+        assert_eq!(out.to_vec(), vec![0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_scaled_amount() {
+        let amount = |token: Token, decimals: u8| {
+            ScaledAmount::from_token(&token, decimals).expect("integer token").to_decimal_string()
+        };
+
+        assert_eq!(amount(Token::Uint(U256::from(1_500_000)), 6), "1.5");
+        assert_eq!(amount(Token::Uint(U256::from(5)), 18), "0.000000000000000005");
+        assert_eq!(amount(Token::Uint(U256::from(42)), 0), "42");
+        assert_eq!(amount(Token::Int(U256::MAX), 2), "-0.01");
+        assert!(ScaledAmount::from_token(&Token::Bool(true), 18).is_none());
+
+        let parsed =
+            |value: &str| ScaledAmount::from_decimal_str(value).map(|a| a.to_decimal_string());
+        assert_eq!(parsed("1.500000"), Some("1.5".to_string()));
+        assert_eq!(parsed("-0.01"), Some("-0.01".to_string()));
+        assert_eq!(parsed("42"), Some("42".to_string()));
+        assert_eq!(parsed("1e18"), None);
+        assert_eq!(parsed(""), None);
+
+        let mut out = BytesMut::new();
+        let amount = ScaledAmount::from_token(&Token::Int(U256::MAX - 123_449), 4).unwrap();
+        serialize_scaled_numeric(&amount, &mut out);
+        assert_eq!(
+            out.to_vec(),
+            vec![0, 2, 0, 0, 0x40, 0, 0, 3, 0, 12, 0x0d, 0x7a],
+            "-12.345 is 12|3450 with weight 0 and scale 3"
+        );
+
+        let mut out = BytesMut::new();
+        let amount = ScaledAmount::from_token(&Token::Uint(U256::from(1)), 5).unwrap();
+        serialize_scaled_numeric(&amount, &mut out);
+        assert_eq!(
+            out.to_vec(),
+            vec![0, 1, 0xff, 0xfe, 0, 0, 0, 5, 0x03, 0xe8],
+            "0.00001 is 1000 with weight -2"
+        );
+    }
+
+    #[test]
+    fn test_map_json_to_ethereum_wrappers() {
+        let inputs = vec![
+            ABIInput { name: "from".to_string(), ..abi_input("address") },
+            ABIInput {
+                name: "order".to_string(),
+                components: Some(vec![
+                    ABIInput { name: "amount".to_string(), ..abi_input("uint128") },
+                    ABIInput { name: "data".to_string(), ..abi_input("bytes") },
+                ]),
+                ..abi_input("tuple")
+            },
+        ];
+        let decoded = vec![
+            EthereumSqlTypeWrapper::Address(Address::zero()),
+            EthereumSqlTypeWrapper::U128(U128::zero()),
+            EthereumSqlTypeWrapper::Bytes(Bytes::new()),
+        ];
+        let json = |value: Value| value.as_object().unwrap().clone();
+
+        let wrappers = map_json_to_ethereum_wrappers(
+            &inputs,
+            &decoded,
+            &json(serde_json::json!({
+                "from": "0x0000000000000000000000000000000000000001",
+                "order": { "amount": "0x10", "data": "0xab" }
+            })),
+        )
+        .unwrap();
+        assert!(
+            matches!(wrappers[0], EthereumSqlTypeWrapper::Address(a) if a == Address::from_low_u64_be(1))
+        );
+        assert!(matches!(wrappers[1], EthereumSqlTypeWrapper::U128(u) if u == U128::from(16)));
+        assert!(
+            matches!(&wrappers[2], EthereumSqlTypeWrapper::Bytes(b) if b.to_vec() == vec![0xab])
+        );
+
+        let order = serde_json::json!({ "amount": 1, "data": "" });
+        for (event, error) in [
+            (serde_json::json!({ "order": order }), "`from` is missing"),
+            (serde_json::json!({ "from": "0x01", "order": order }), "`from` can not be stored"),
+            (
+                serde_json::json!({ "from": Address::zero(), "order": { "amount": -1, "data": "" } }),
+                "`order.amount` can not be stored",
+            ),
+            (
+                serde_json::json!({ "from": Address::zero(), "order": { "amount": 1, "data": "", "usd": 1 } }),
+                "`order.usd` is not a field",
+            ),
+        ] {
+            let e = map_json_to_ethereum_wrappers(&inputs, &decoded, &json(event)).unwrap_err();
+            assert!(e.starts_with(error), "{}", e);
+        }
+
+        let log_params = map_json_to_log_params(
+            &[ABIInput { name: "delta".to_string(), ..abi_input("int256") }, inputs[1].clone()],
+            &json(serde_json::json!({
+                "delta": "-2",
+                "order": { "amount": "16", "data": "0xab" }
+            })),
+        )
+        .unwrap();
+        assert_eq!(log_params[0].value, Token::Int(I256::from(-2).into_raw()));
+        assert_eq!(
+            log_params[1].value,
+            Token::Tuple(vec![Token::Uint(U256::from(16)), Token::Bytes(vec![0xab])])
+        );
+        assert!(map_json_to_log_params(&inputs, &json(serde_json::json!({ "from": "0x01" })))
+            .unwrap_err()
+            .starts_with("`from` can not be read as address"));
+
+        assert!(map_json_value_to_ethereum_wrapper(
+            &EthereumSqlTypeWrapper::U64(U64::zero()),
+            &serde_json::json!("18446744073709551616")
+        )
+        .is_none());
+        assert!(matches!(
+            map_json_value_to_ethereum_wrapper(
+                &EthereumSqlTypeWrapper::I8(0),
+                &serde_json::json!(-5)
+            ),
+            Some(EthereumSqlTypeWrapper::I8(-5))
+        ));
+    }
+}
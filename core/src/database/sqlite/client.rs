@@ -0,0 +1,240 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rusqlite::{
+    params_from_iter,
+    types::{FromSql, Value},
+    Connection, OptionalExtension,
+};
+use tokio::task;
+
+use crate::database::postgres::{
+    generate::generate_event_table_columns_names_sql,
+    sql_type_wrapper::{map_ethereum_wrapper_to_json_value, EthereumSqlTypeWrapper},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum SqliteConnectionError {
+    #[error("Could not create the directory for the sqlite database {0}: {1}")]
+    CouldNotCreateDirectory(String, std::io::Error),
+
+    #[error("Could not open the sqlite database {0}: {1}")]
+    CouldNotOpenDatabase(String, rusqlite::Error),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SqliteError {
+    #[error("SqliteError {0}")]
+    SqliteError(#[from] rusqlite::Error),
+
+    #[error("Sqlite task failed: {0}")]
+    TaskError(#[from] task::JoinError),
+}
+
+/// Maps the wrapper to the value sqlite stores, numbers which do not fit in a signed 64 bit
+/// integer are stored as text and arrays and tuples are stored as json text
+pub fn ethereum_sql_type_wrapper_to_sqlite_value(wrapper: &EthereumSqlTypeWrapper) -> Value {
+    match wrapper {
+        EthereumSqlTypeWrapper::U64(u) => match i64::try_from(u.as_u64()) {
+            Ok(value) => Value::Integer(value),
+            Err(_) => Value::Text(u.to_string()),
+        },
+        EthereumSqlTypeWrapper::U32(u) => Value::Integer(*u as i64),
+        EthereumSqlTypeWrapper::U16(u) => Value::Integer(*u as i64),
+        EthereumSqlTypeWrapper::U8(u) => Value::Integer(*u as i64),
+        EthereumSqlTypeWrapper::I8(i) => Value::Integer(*i as i64),
+        EthereumSqlTypeWrapper::I16(i) => Value::Integer(*i as i64),
+        EthereumSqlTypeWrapper::I32(i) => Value::Integer(*i as i64),
+        EthereumSqlTypeWrapper::I64(i) => Value::Integer(*i),
+        EthereumSqlTypeWrapper::Bool(b) => Value::Integer(*b as i64),
+        EthereumSqlTypeWrapper::U128(u) => Value::Text(u.to_string()),
+        EthereumSqlTypeWrapper::U256(u) => Value::Text(u.to_string()),
+        EthereumSqlTypeWrapper::U512(u) => Value::Text(u.to_string()),
+        EthereumSqlTypeWrapper::I128(i) => Value::Text(i.to_string()),
+        EthereumSqlTypeWrapper::I256(i) => Value::Text(i.to_string()),
+        EthereumSqlTypeWrapper::H128(h) => Value::Text(format!("{:?}", h)),
+        EthereumSqlTypeWrapper::H160(h) => Value::Text(format!("{:?}", h)),
+        EthereumSqlTypeWrapper::H256(h) => Value::Text(format!("{:?}", h)),
+        EthereumSqlTypeWrapper::H512(h) => Value::Text(format!("{:?}", h)),
+        EthereumSqlTypeWrapper::Address(address) => Value::Text(format!("{:?}", address)),
+        EthereumSqlTypeWrapper::String(s) => Value::Text(s.clone()),
+        EthereumSqlTypeWrapper::Bytes(bytes) => Value::Text(format!("0x{}", hex::encode(bytes))),
+        EthereumSqlTypeWrapper::Numeric(value) => {
+            value.map_or(Value::Null, |amount| Value::Text(amount.to_decimal_string()))
+        }
+        EthereumSqlTypeWrapper::NullableBool(value) => {
+            value.map_or(Value::Null, |b| Value::Integer(b as i64))
+        }
+        EthereumSqlTypeWrapper::NullableString(value) => {
+            value.clone().map_or(Value::Null, Value::Text)
+        }
+        _ => Value::Text(map_ethereum_wrapper_to_json_value(wrapper).to_string()),
+    }
+}
+
+pub struct SqliteClient {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteClient {
+    pub fn new(path: &Path) -> Result<Self, SqliteConnectionError> {
+        let display_path = path.display().to_string();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                SqliteConnectionError::CouldNotCreateDirectory(display_path.clone(), e)
+            })?;
+        }
+
+        let open = || -> Result<Connection, rusqlite::Error> {
+            let connection = Connection::open(path)?;
+            // WAL allows the graphql and indexing side to read while events are written
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+            connection.pragma_update(None, "synchronous", "NORMAL")?;
+            connection.busy_timeout(Duration::from_secs(5))?;
+            Ok(connection)
+        };
+
+        let connection =
+            open().map_err(|e| SqliteConnectionError::CouldNotOpenDatabase(display_path, e))?;
+
+        Ok(SqliteClient { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    /// rusqlite is blocking so all the work is moved off the async runtime
+    async fn run<F, T>(&self, f: F) -> Result<T, SqliteError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, rusqlite::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        let result = task::spawn_blocking(move || {
+            let mut connection = connection.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut connection)
+        })
+        .await??;
+
+        Ok(result)
+    }
+
+    pub async fn batch_execute(&self, sql: &str) -> Result<(), SqliteError> {
+        let sql = sql.to_string();
+        self.run(move |connection| connection.execute_batch(&sql)).await
+    }
+
+    pub async fn execute(&self, sql: &str, params: Vec<Value>) -> Result<usize, SqliteError> {
+        let sql = sql.to_string();
+        self.run(move |connection| connection.execute(&sql, params_from_iter(params))).await
+    }
+
+    pub async fn query_one_or_none<T>(
+        &self,
+        sql: &str,
+        params: Vec<Value>,
+    ) -> Result<Option<T>, SqliteError>
+    where
+        T: FromSql + Send + 'static,
+    {
+        let sql = sql.to_string();
+        self.run(move |connection| {
+            connection.query_row(&sql, params_from_iter(params), |row| row.get(0)).optional()
+        })
+        .await
+    }
+
+    /// Inserts all the rows within a single transaction reusing the prepared statement
+    pub async fn bulk_insert(
+        &self,
+        table_name: &str,
+        column_names: &[String],
+        bulk_data: &[Vec<EthereumSqlTypeWrapper>],
+    ) -> Result<usize, SqliteError> {
+        let sql = format!(
+            "INSERT INTO \"{}\" ({}) VALUES ({})",
+            table_name,
+            generate_event_table_columns_names_sql(column_names),
+            (1..=column_names.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ")
+        );
+
+        let rows: Vec<Vec<Value>> = bulk_data
+            .iter()
+            .map(|row| row.iter().map(ethereum_sql_type_wrapper_to_sqlite_value).collect())
+            .collect();
+
+        self.run(move |connection| {
+            let transaction = connection.transaction()?;
+            let mut inserted = 0;
+            {
+                let mut statement = transaction.prepare_cached(&sql)?;
+                for row in rows {
+                    inserted += statement.execute(params_from_iter(row))?;
+                }
+            }
+            transaction.commit()?;
+            Ok(inserted)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, U256, U64};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bulk_insert() {
+        let directory = tempfile::tempdir().unwrap();
+        let client = SqliteClient::new(&directory.path().join("nested/rindexer.db")).unwrap();
+        client
+            .batch_execute(
+                r#"CREATE TABLE "transfer" ("from" TEXT NOT NULL, "value" TEXT NOT NULL, "block_number" INTEGER NOT NULL);"#,
+            )
+            .await
+            .unwrap();
+
+        let inserted = client
+            .bulk_insert(
+                "transfer",
+                &["from".to_string(), "value".to_string(), "block_number".to_string()],
+                &[
+                    vec![
+                        EthereumSqlTypeWrapper::Address(Address::zero()),
+                        EthereumSqlTypeWrapper::U256(U256::MAX),
+                        EthereumSqlTypeWrapper::U64(U64::from(10)),
+                    ],
+                    vec![
+                        EthereumSqlTypeWrapper::Address(Address::repeat_byte(1)),
+                        EthereumSqlTypeWrapper::U256(U256::from(1)),
+                        EthereumSqlTypeWrapper::U64(U64::from(11)),
+                    ],
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted, 2);
+
+        let value: Option<String> = client
+            .query_one_or_none(
+                r#"SELECT "value" FROM "transfer" WHERE "block_number" = ?1"#,
+                vec![Value::Integer(10)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(value, Some(U256::MAX.to_string()));
+
+        let missing: Option<String> = client
+            .query_one_or_none(
+                r#"SELECT "value" FROM "transfer" WHERE "block_number" = ?1"#,
+                vec![Value::Integer(12)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(missing, None);
+    }
+}
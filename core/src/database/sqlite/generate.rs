@@ -0,0 +1,519 @@
+use std::path::Path;
+
+use tracing::{error, info};
+
+use crate::{
+    abi::{
+        get_abi_item_with_db_map, ABIInput, ABIItem, EventInfo, GenerateAbiPropertiesType,
+        GetAbiItemWithDbMapError, ParamTypeError, ReadAbiError,
+    },
+    database::postgres::generate::EventTableColumn,
+    helpers::camel_to_snake,
+    indexer::Indexer,
+    manifest::{
+        contract::Contract,
+        storage::{ComputedColumnType, ForeignKeys, PostgresIndexes, StorageEvent},
+    },
+    types::code::Code,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum GenerateTablesForIndexerSqliteError {
+    #[error("{0}")]
+    ReadAbiError(#[from] ReadAbiError),
+
+    #[error("{0}")]
+    ParamTypeError(#[from] ParamTypeError),
+
+    #[error("{0}")]
+    GetAbiParameterError(#[from] GetAbiItemWithDbMapError),
+
+    #[error("Contract {0} not found in `contracts` make sure it is defined")]
+    ContractMissing(String),
+
+    #[error("Type mismatch: {0}")]
+    TypeMismatch(String),
+}
+
+/// SQLite has no schemas so the indexer and contract are part of the table name, takes the event
+/// table name from `Contract::event_table_name` so any override is kept
+pub fn generate_sqlite_event_table_name(
+    indexer_name: &str,
+    contract_name: &str,
+    event_table_name: &str,
+) -> String {
+    format!(
+        "{}_{}_{}",
+        camel_to_snake(indexer_name),
+        camel_to_snake(contract_name),
+        event_table_name
+    )
+}
+
+pub fn generate_sqlite_internal_event_table_name(
+    indexer_name: &str,
+    contract_name: &str,
+    event_name: &str,
+) -> String {
+    format!(
+        "rindexer_internal_{}",
+        generate_sqlite_event_table_name(indexer_name, contract_name, &camel_to_snake(event_name))
+    )
+}
+
+/// Anything which can not fit in a signed 64 bit integer is stored as TEXT, an INTEGER column
+/// would turn those values into lossy REALs
+pub fn solidity_type_to_sqlite_type(abi_type: &str) -> &'static str {
+    if abi_type.ends_with(']') {
+        // arrays are stored as json
+        return "TEXT";
+    }
+
+    match abi_type {
+        "bool" => "INTEGER",
+        t if t.starts_with("uint") => match t[4..].parse::<usize>() {
+            Ok(size) if size <= 56 => "INTEGER",
+            _ => "TEXT",
+        },
+        t if t.starts_with("int") => match t[3..].parse::<usize>() {
+            Ok(size) if size <= 64 => "INTEGER",
+            _ => "TEXT",
+        },
+        _ => "TEXT",
+    }
+}
+
+/// All the columns of an event table in the order they are created
+pub fn generate_sqlite_event_table_columns(
+    event_info: &EventInfo,
+    storage_event: &StorageEvent,
+) -> Vec<EventTableColumn> {
+    let mut columns = vec![
+        EventTableColumn {
+            name: "rindexer_id".to_string(),
+            data_type: "INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL".to_string(),
+        },
+        EventTableColumn {
+            name: "contract_address".to_string(),
+            data_type: "TEXT NOT NULL".to_string(),
+        },
+    ];
+
+    columns.extend(
+        ABIInput::generate_abi_name_properties(
+            &event_info.inputs,
+            &GenerateAbiPropertiesType::PostgresColumnsNamesOnly,
+            None,
+            storage_event.is_tuples_as_jsonb(),
+        )
+        .into_iter()
+        .map(|property| EventTableColumn {
+            name: storage_event.column_name(&property.value),
+            data_type: solidity_type_to_sqlite_type(&property.abi_type).to_string(),
+        }),
+    );
+
+    // formatted values are text so no precision is lost
+    columns.extend(
+        storage_event
+            .formatted_column_names(&event_info.inputs)
+            .into_iter()
+            .map(|name| EventTableColumn { name, data_type: "TEXT".to_string() }),
+    );
+
+    // numbers are text like the formatted values
+    columns.extend(storage_event.computed_columns().iter().map(|computed_column| {
+        let data_type = match computed_column.type_ {
+            ComputedColumnType::Bool => "INTEGER",
+            ComputedColumnType::Number | ComputedColumnType::String => "TEXT",
+        };
+        EventTableColumn { name: computed_column.name.clone(), data_type: data_type.to_string() }
+    }));
+
+    // the topics are a json array of hex strings
+    columns.extend(
+        storage_event
+            .raw_log_column_names()
+            .into_iter()
+            .map(|name| EventTableColumn { name, data_type: "TEXT".to_string() }),
+    );
+
+    for (name, data_type) in [
+        ("tx_hash", "TEXT NOT NULL"),
+        ("block_number", "INTEGER NOT NULL"),
+        ("block_hash", "TEXT NOT NULL"),
+        ("network", "TEXT NOT NULL"),
+        ("tx_index", "INTEGER NOT NULL"),
+        ("log_index", "TEXT NOT NULL"),
+    ] {
+        columns.push(EventTableColumn { name: name.to_string(), data_type: data_type.to_string() });
+    }
+
+    columns
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqliteForeignKey {
+    pub db_table_name: String,
+
+    pub db_table_column: String,
+
+    pub linked_db_table_name: String,
+
+    pub linked_db_table_column: String,
+}
+
+impl SqliteForeignKey {
+    fn constraint_sql(&self) -> String {
+        format!(
+            "FOREIGN KEY (\"{}\") REFERENCES \"{}\" (\"{}\")",
+            self.db_table_column, self.linked_db_table_name, self.linked_db_table_column
+        )
+    }
+
+    /// A foreign key must point to a unique column and the referencing column is indexed so
+    /// lookups across the relationship stay fast
+    fn index_sql(&self) -> String {
+        format!(
+            r#"
+            CREATE UNIQUE INDEX IF NOT EXISTS "unique_{linked_db_table_name}_{linked_db_table_column}" ON "{linked_db_table_name}" ("{linked_db_table_column}");
+            CREATE INDEX IF NOT EXISTS "idx_{db_table_name}_{db_table_column}" ON "{db_table_name}" ("{db_table_column}");
+            "#,
+            db_table_name = self.db_table_name,
+            db_table_column = self.db_table_column,
+            linked_db_table_name = self.linked_db_table_name,
+            linked_db_table_column = self.linked_db_table_column
+        )
+    }
+}
+
+fn find_contract<'a>(
+    contracts: &'a [Contract],
+    contract_name: &str,
+) -> Result<&'a Contract, GenerateTablesForIndexerSqliteError> {
+    contracts.iter().find(|c| c.name == contract_name).ok_or_else(|| {
+        GenerateTablesForIndexerSqliteError::ContractMissing(contract_name.to_string())
+    })
+}
+
+pub fn generate_sqlite_foreign_keys(
+    project_path: &Path,
+    indexer_name: &str,
+    contracts: &[Contract],
+    foreign_keys: &[ForeignKeys],
+) -> Result<Vec<SqliteForeignKey>, GenerateTablesForIndexerSqliteError> {
+    let mut sqlite_foreign_keys = vec![];
+
+    for foreign_key in foreign_keys {
+        let contract = find_contract(contracts, &foreign_key.contract_name)?;
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        let abi_parameter = get_abi_item_with_db_map(
+            &abi_items,
+            &contract.storage_event(&foreign_key.event_name),
+            &foreign_key.event_input_name.split('.').collect::<Vec<&str>>(),
+        )?;
+
+        for linked_key in &foreign_key.foreign_keys {
+            let linked_contract = find_contract(contracts, &linked_key.contract_name)?;
+            let linked_abi_items = ABIItem::read_abi_items(project_path, linked_contract)?;
+            let linked_abi_parameter = get_abi_item_with_db_map(
+                &linked_abi_items,
+                &linked_contract.storage_event(&linked_key.event_name),
+                &linked_key.event_input_name.split('.').collect::<Vec<&str>>(),
+            )?;
+
+            if abi_parameter.abi_item.type_ != linked_abi_parameter.abi_item.type_ {
+                return Err(GenerateTablesForIndexerSqliteError::TypeMismatch(format!(
+                    "Type mismatch between {}.{} ({}) and {}.{} ({})",
+                    &foreign_key.contract_name,
+                    &foreign_key.event_input_name,
+                    &abi_parameter.abi_item.type_,
+                    &linked_key.contract_name,
+                    &linked_key.event_input_name,
+                    &linked_abi_parameter.abi_item.type_
+                )));
+            }
+
+            sqlite_foreign_keys.push(SqliteForeignKey {
+                db_table_name: generate_sqlite_event_table_name(
+                    indexer_name,
+                    &contract.name,
+                    &contract.event_table_name(&foreign_key.event_name),
+                ),
+                db_table_column: abi_parameter.db_column_name.clone(),
+                linked_db_table_name: generate_sqlite_event_table_name(
+                    indexer_name,
+                    &linked_contract.name,
+                    &linked_contract.event_table_name(&linked_key.event_name),
+                ),
+                linked_db_table_column: linked_abi_parameter.db_column_name,
+            });
+        }
+    }
+
+    Ok(sqlite_foreign_keys)
+}
+
+fn generate_event_table_sql(
+    event_info: &EventInfo,
+    contract: &Contract,
+    table_name: &str,
+    foreign_keys: &[SqliteForeignKey],
+) -> String {
+    info!("Creating table if not exists: {}", table_name);
+    let mut definitions: Vec<String> =
+        generate_sqlite_event_table_columns(event_info, &contract.storage_event(&event_info.name))
+            .iter()
+            .map(|c| c.definition_sql())
+            .collect();
+
+    // sqlite can only add foreign keys when a table is created
+    definitions.extend(
+        foreign_keys
+            .iter()
+            .filter(|foreign_key| foreign_key.db_table_name == table_name)
+            .map(|foreign_key| foreign_key.constraint_sql()),
+    );
+
+    format!("CREATE TABLE IF NOT EXISTS \"{}\" ({});", table_name, definitions.join(", "))
+}
+
+fn generate_internal_event_table_sql(table_name: &str, networks: &[&str]) -> String {
+    let create_table_query = format!(
+        r#"CREATE TABLE IF NOT EXISTS "{}" ("network" TEXT PRIMARY KEY, "last_synced_block" INTEGER NOT NULL);"#,
+        table_name
+    );
+
+    let insert_queries = networks
+        .iter()
+        .map(|network| {
+            format!(
+                r#"INSERT INTO "{}" ("network", "last_synced_block") VALUES ('{}', 0) ON CONFLICT ("network") DO NOTHING;"#,
+                table_name, network
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("{}\n{}", create_table_query, insert_queries)
+}
+
+/// The event tables with the internal table holding the last synced block of their event
+pub fn generate_sqlite_event_table_names(
+    project_path: &Path,
+    indexer: &Indexer,
+) -> Result<Vec<(String, String)>, GenerateTablesForIndexerSqliteError> {
+    let mut table_names = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        for event_info in ABIItem::extract_event_names_and_signatures_from_abi(abi_items)? {
+            table_names.push((
+                generate_sqlite_event_table_name(
+                    &indexer.name,
+                    &contract_name,
+                    &contract.event_table_name(&event_info.name),
+                ),
+                generate_sqlite_internal_event_table_name(
+                    &indexer.name,
+                    &contract_name,
+                    &event_info.name,
+                ),
+            ));
+        }
+    }
+
+    Ok(table_names)
+}
+
+pub fn generate_tables_for_indexer_sqlite_sql(
+    project_path: &Path,
+    indexer: &Indexer,
+    foreign_keys: &[SqliteForeignKey],
+) -> Result<Code, GenerateTablesForIndexerSqliteError> {
+    let mut sql = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
+        let networks: Vec<&str> = contract.details.iter().map(|d| d.network.as_str()).collect();
+
+        for event_info in &event_names {
+            let table_name = generate_sqlite_event_table_name(
+                &indexer.name,
+                &contract_name,
+                &contract.event_table_name(&event_info.name),
+            );
+            sql.push(generate_event_table_sql(event_info, contract, &table_name, foreign_keys));
+            sql.push(generate_internal_event_table_sql(
+                &generate_sqlite_internal_event_table_name(
+                    &indexer.name,
+                    &contract_name,
+                    &event_info.name,
+                ),
+                &networks,
+            ));
+        }
+    }
+
+    sql.extend(foreign_keys.iter().map(|foreign_key| foreign_key.index_sql()));
+
+    Ok(Code::new(sql.join("\n")))
+}
+
+fn index_sql(db_table_name: &str, db_table_columns: &[String]) -> String {
+    format!(
+        r#"CREATE INDEX IF NOT EXISTS "idx_{}_{}" ON "{}" ({});"#,
+        db_table_name,
+        db_table_columns.join("_"),
+        db_table_name,
+        db_table_columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ")
+    )
+}
+
+pub fn generate_indexes_sqlite_sql(
+    project_path: &Path,
+    indexer_name: &str,
+    indexes: &PostgresIndexes,
+    contracts: &[Contract],
+) -> Result<Code, GenerateTablesForIndexerSqliteError> {
+    let mut sql = vec![];
+
+    if let Some(global_injected_parameters) = &indexes.global_injected_parameters {
+        for contract in contracts {
+            for abi_item in ABIItem::read_abi_items(project_path, contract)? {
+                let db_table_name = generate_sqlite_event_table_name(
+                    indexer_name,
+                    &contract.name,
+                    &contract.event_table_name(&abi_item.name),
+                );
+                for parameter in global_injected_parameters {
+                    sql.push(index_sql(&db_table_name, std::slice::from_ref(parameter)));
+                }
+            }
+        }
+    }
+
+    for contract_event_indexes in indexes.contracts.iter().flatten() {
+        let contract = find_contract(contracts, &contract_event_indexes.name)?;
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+
+        if let Some(injected_parameters) = &contract_event_indexes.injected_parameters {
+            for abi_item in &abi_items {
+                let db_table_name = generate_sqlite_event_table_name(
+                    indexer_name,
+                    &contract.name,
+                    &contract.event_table_name(&abi_item.name),
+                );
+                for parameter in injected_parameters {
+                    sql.push(index_sql(&db_table_name, std::slice::from_ref(parameter)));
+                }
+            }
+        }
+
+        for event_indexes in &contract_event_indexes.events {
+            let storage_event = contract.storage_event(&event_indexes.name);
+            let db_table_name = generate_sqlite_event_table_name(
+                indexer_name,
+                &contract.name,
+                &storage_event.event_table_name(),
+            );
+
+            for parameter in event_indexes.injected_parameters.iter().flatten() {
+                sql.push(index_sql(&db_table_name, std::slice::from_ref(parameter)));
+            }
+
+            for index in &event_indexes.indexes {
+                let mut db_table_columns = vec![];
+                for parameter in &index.event_input_names {
+                    let abi_parameter = get_abi_item_with_db_map(
+                        &abi_items,
+                        &storage_event,
+                        &parameter.split('.').collect::<Vec<&str>>(),
+                    )?;
+                    db_table_columns.push(abi_parameter.db_column_name);
+                }
+                sql.push(index_sql(&db_table_name, &db_table_columns));
+            }
+        }
+    }
+
+    Ok(Code::new(sql.join("\n")))
+}
+
+pub fn drop_tables_for_indexer_sqlite_sql(project_path: &Path, indexer: &Indexer) -> Code {
+    let mut sql = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        match ABIItem::read_abi_items(project_path, contract) {
+            Ok(abi_items) => {
+                for abi_item in abi_items.iter() {
+                    sql.push(format!(
+                        "DROP TABLE IF EXISTS \"{}\";",
+                        generate_sqlite_event_table_name(
+                            &indexer.name,
+                            &contract_name,
+                            &contract.event_table_name(&abi_item.name)
+                        )
+                    ));
+                    sql.push(format!(
+                        "DROP TABLE IF EXISTS \"{}\";",
+                        generate_sqlite_internal_event_table_name(
+                            &indexer.name,
+                            &contract_name,
+                            &abi_item.name
+                        )
+                    ));
+                }
+            }
+            Err(_) => {
+                error!(
+                    "Could not read ABI items for contract moving on clearing the other data up: {}",
+                    contract.name
+                );
+            }
+        }
+    }
+
+    Code::new(sql.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solidity_type_to_sqlite_type() {
+        assert_eq!(solidity_type_to_sqlite_type("bool"), "INTEGER");
+        assert_eq!(solidity_type_to_sqlite_type("uint32"), "INTEGER");
+        assert_eq!(solidity_type_to_sqlite_type("uint64"), "TEXT");
+        assert_eq!(solidity_type_to_sqlite_type("int64"), "INTEGER");
+        assert_eq!(solidity_type_to_sqlite_type("int128"), "TEXT");
+        assert_eq!(solidity_type_to_sqlite_type("uint256"), "TEXT");
+        assert_eq!(solidity_type_to_sqlite_type("address"), "TEXT");
+        assert_eq!(solidity_type_to_sqlite_type("uint8[]"), "TEXT");
+        assert_eq!(solidity_type_to_sqlite_type("uint8[2]"), "TEXT");
+    }
+
+    #[test]
+    fn test_relationship_and_index_sql() {
+        let foreign_key = SqliteForeignKey {
+            db_table_name: "indexer_erc20_approval".to_string(),
+            db_table_column: "owner".to_string(),
+            linked_db_table_name: "indexer_erc20_transfer".to_string(),
+            linked_db_table_column: "from".to_string(),
+        };
+
+        assert_eq!(
+            foreign_key.constraint_sql(),
+            r#"FOREIGN KEY ("owner") REFERENCES "indexer_erc20_transfer" ("from")"#
+        );
+        assert_eq!(
+            index_sql("indexer_erc20_transfer", &["from".to_string(), "to".to_string()]),
+            r#"CREATE INDEX IF NOT EXISTS "idx_indexer_erc20_transfer_from_to" ON "indexer_erc20_transfer" ("from", "to");"#
+        );
+    }
+}
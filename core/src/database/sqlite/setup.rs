@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info, warn};
+
+use crate::{
+    database::sqlite::{
+        client::{SqliteClient, SqliteConnectionError, SqliteError},
+        generate::{
+            drop_tables_for_indexer_sqlite_sql, generate_indexes_sqlite_sql,
+            generate_sqlite_event_table_names, generate_sqlite_foreign_keys,
+            generate_tables_for_indexer_sqlite_sql, GenerateTablesForIndexerSqliteError,
+        },
+    },
+    manifest::{core::Manifest, storage::SqliteDetails},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetupSqliteError {
+    #[error("{0}")]
+    SqliteConnection(#[from] SqliteConnectionError),
+
+    #[error("{0}")]
+    SqliteError(#[from] SqliteError),
+
+    #[error("Error creating tables for indexer: {0}")]
+    GeneratingTables(#[from] GenerateTablesForIndexerSqliteError),
+}
+
+/// Relative paths are resolved from the project path
+pub fn sqlite_database_path(project_path: &Path, details: &SqliteDetails) -> PathBuf {
+    project_path.join(&details.path)
+}
+
+/// An event table which no longer exists while its event has synced blocks was renamed or dropped,
+/// the event is indexed again from its start block so the table is not missing its history
+async fn reset_orphaned_last_synced(
+    client: &SqliteClient,
+    table_names: &[(String, String)],
+) -> Result<(), SetupSqliteError> {
+    let table_exists = |table_name: &str| {
+        client.query_one_or_none::<i64>(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            vec![table_name.to_string().into()],
+        )
+    };
+
+    for (table_name, internal_table_name) in table_names {
+        if table_exists(table_name).await? != Some(0) ||
+            table_exists(internal_table_name).await? == Some(0)
+        {
+            continue;
+        }
+
+        let synced = client
+            .query_one_or_none::<i64>(
+                &format!(
+                    r#"SELECT COUNT(*) FROM "{}" WHERE last_synced_block > 0"#,
+                    internal_table_name
+                ),
+                vec![],
+            )
+            .await?;
+        if synced.unwrap_or(0) > 0 {
+            warn!(
+                "Table {} does not exist but its event has already been indexed, the old table was renamed or dropped - the event is indexed again from its start block",
+                table_name
+            );
+            client
+                .batch_execute(&format!(
+                    r#"UPDATE "{}" SET last_synced_block = 0;"#,
+                    internal_table_name
+                ))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn setup_sqlite(
+    project_path: &Path,
+    manifest: &Manifest,
+    details: &SqliteDetails,
+) -> Result<SqliteClient, SetupSqliteError> {
+    info!("Setting up sqlite");
+    let client = SqliteClient::new(&sqlite_database_path(project_path, details))?;
+    let indexer = manifest.to_indexer();
+
+    if manifest.storage.sqlite_drop_each_run() {
+        info!(
+            "`drop_each_run` enabled so dropping all sqlite data for {} before starting",
+            &manifest.name
+        );
+        let sql = drop_tables_for_indexer_sqlite_sql(project_path, &indexer);
+        client.batch_execute(sql.as_str()).await?;
+        info!("Dropped all sqlite data for {}", manifest.name);
+    }
+
+    let foreign_keys = match &details.relationships {
+        Some(relationships) => generate_sqlite_foreign_keys(
+            project_path,
+            &manifest.name,
+            &manifest.contracts,
+            relationships,
+        )?,
+        None => vec![],
+    };
+
+    reset_orphaned_last_synced(
+        &client,
+        &generate_sqlite_event_table_names(project_path, &indexer)?,
+    )
+    .await?;
+
+    info!("Creating sqlite tables for {}", manifest.name);
+    let sql = generate_tables_for_indexer_sqlite_sql(project_path, &indexer, &foreign_keys)?;
+    debug!("{}", sql);
+    client.batch_execute(sql.as_str()).await?;
+
+    if let Some(indexes) = &details.indexes {
+        let sql = generate_indexes_sqlite_sql(
+            project_path,
+            &manifest.name,
+            indexes,
+            &manifest.contracts,
+        )?;
+        debug!("{}", sql);
+        client.batch_execute(sql.as_str()).await?;
+    }
+    info!("Created sqlite tables for {}", manifest.name);
+
+    Ok(client)
+}
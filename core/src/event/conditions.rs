@@ -1,7 +1,37 @@
-use ethers::prelude::U64;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use lazy_static::lazy_static;
 use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::event::expression::{parse_expression, Expression, ExpressionError};
+
+lazy_static! {
+    // conditions are evaluated for every event so only parse (and compile regexes) once
+    static ref PARSED_CONDITIONS: RwLock<HashMap<(String, String), Arc<ParsedCondition>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// A condition with the exact values it matched before the expression language, an event matches
+/// when its value is one of them or the expression holds
+pub struct ParsedCondition {
+    expression: Option<Expression>,
+    legacy_values: Option<Vec<String>>,
+}
 
-fn get_nested_value(data: &Value, path: &str) -> Option<Value> {
+/// Conditions without a comparison used to match the value as an exact string with `||` between
+/// the values, so values with spaces or `-` which are no valid expression keep matching
+fn legacy_values(condition: &str) -> Option<Vec<String>> {
+    if condition.contains(['<', '>', '=', '&']) {
+        return None;
+    }
+    Some(condition.split("||").map(String::from).collect())
+}
+
+fn get_nested_value<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
     let keys: Vec<&str> = path.split('.').collect();
     let mut current = data;
     for key in keys {
@@ -10,66 +40,66 @@ fn get_nested_value(data: &Value, path: &str) -> Option<Value> {
             None => return None,
         }
     }
-    Some(current.clone())
+    Some(current)
 }
 
-#[allow(clippy::manual_strip)]
-fn evaluate_condition(value: &Value, condition: &str) -> bool {
-    if condition.contains("||") ||
-        condition.contains("&&") ||
-        condition.contains('>') ||
-        condition.contains('<') ||
-        condition.contains('=')
+fn condition_to_string(key: &str, condition: &Value) -> Result<String, ConditionError> {
+    match condition {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(ConditionError::UnsupportedConditionType(key.to_string())),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConditionError {
+    #[error("condition for `{0}` must be a string")]
+    UnsupportedConditionType(String),
+
+    #[error("condition for `{0}` is invalid: {1}")]
+    InvalidExpression(String, ExpressionError),
+}
+
+/// Parses a single `field: expression` condition, the field is used as the left hand side of any
+/// comparison which does not define one.
+pub fn parse_condition(
+    key: &str,
+    condition: &Value,
+) -> Result<Arc<ParsedCondition>, ConditionError> {
+    let condition = condition_to_string(key, condition)?;
+    let cache_key = (key.to_string(), condition);
+
+    if let Some(expression) =
+        PARSED_CONDITIONS.read().expect("Failed to read parsed conditions").get(&cache_key)
     {
-        let parts: Vec<&str> = condition.split("||").collect();
-        for part in parts {
-            let subparts: Vec<&str> = part.split("&&").collect();
-            let mut and_result = true;
-            for subpart in subparts {
-                let (op, comp) = if subpart.starts_with(">=") {
-                    (">=", &subpart[2..])
-                } else if subpart.starts_with("<=") {
-                    ("<=", &subpart[2..])
-                } else if subpart.starts_with(">") {
-                    (">", &subpart[1..])
-                } else if subpart.starts_with("<") {
-                    ("<", &subpart[1..])
-                } else if subpart.starts_with("=") {
-                    ("=", &subpart[1..])
-                } else {
-                    ("", subpart)
-                };
-
-                and_result &= match op {
-                    ">=" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() >=
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    "<=" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() <=
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    ">" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() >
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    "<" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() <
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    "=" => value == &Value::String(comp.to_string()),
-                    "" => value == &Value::String(subpart.to_string()),
-                    _ => false,
-                };
-            }
-            if and_result {
-                return true;
-            }
+        return Ok(Arc::clone(expression));
+    }
+
+    let legacy_values = legacy_values(&cache_key.1);
+    let expression = match parse_expression(&cache_key.1, Some(key)) {
+        Ok(expression) => Some(expression),
+        Err(_) if legacy_values.is_some() => None,
+        Err(e) => return Err(ConditionError::InvalidExpression(key.to_string(), e)),
+    };
+    let parsed = Arc::new(ParsedCondition { expression, legacy_values });
+
+    PARSED_CONDITIONS
+        .write()
+        .expect("Failed to write parsed conditions")
+        .insert(cache_key, Arc::clone(&parsed));
+
+    Ok(parsed)
+}
+
+pub fn validate_conditions(conditions: &[Map<String, Value>]) -> Result<(), ConditionError> {
+    for condition in conditions {
+        for (key, value) in condition {
+            parse_condition(key, value)?;
         }
-        false
-    } else {
-        value == &Value::String(condition.to_string())
     }
+
+    Ok(())
 }
 
 pub fn filter_event_data_by_conditions(
@@ -78,14 +108,65 @@ pub fn filter_event_data_by_conditions(
 ) -> bool {
     for condition in conditions {
         for (key, value) in condition {
-            if let Some(event_value) = get_nested_value(event_data, key) {
-                if !evaluate_condition(&event_value, value.as_str().unwrap_or("")) {
+            let Some(event_value) = get_nested_value(event_data, key) else {
+                return false;
+            };
+
+            let parsed = match parse_condition(key, value) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    // manifest validation catches these so this should never happen
+                    debug!("Condition could not be parsed: {}", e);
                     return false;
                 }
-            } else {
+            };
+
+            let legacy_match = match (&parsed.legacy_values, event_value) {
+                (Some(values), Value::String(event_value)) => values.contains(event_value),
+                _ => false,
+            };
+            if legacy_match {
+                continue;
+            }
+
+            let Some(expression) = &parsed.expression else {
                 return false;
+            };
+            match expression.evaluate_bool(event_data) {
+                Ok(true) => {}
+                Ok(false) => return false,
+                Err(e) => {
+                    debug!("Condition for `{}` did not evaluate: {}", key, e);
+                    return false;
+                }
             }
         }
     }
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn matches(event: Value, key: &str, condition: &str) -> bool {
+        let conditions = vec![json!({ key: condition }).as_object().unwrap().clone()];
+        filter_event_data_by_conditions(&event, &conditions)
+    }
+
+    #[test]
+    fn test_legacy_bare_values_still_match() {
+        assert!(matches(json!({ "name": "hello world" }), "name", "hello world"));
+        assert!(matches(json!({ "name": "rocket-pool" }), "name", "rocket-pool"));
+        assert!(matches(json!({ "name": "b" }), "name", "a||b"));
+        assert!(!matches(json!({ "name": "rocket" }), "name", "rocket-pool"));
+        assert!(matches(json!({ "value": "0x0a" }), "value", "10"));
+        assert!(
+            validate_conditions(&[json!({ "name": "a b" }).as_object().unwrap().clone()]).is_ok()
+        );
+        assert!(validate_conditions(&[json!({ "name": ">= a b" }).as_object().unwrap().clone()])
+            .is_err());
+    }
+}
@@ -10,8 +10,8 @@ use crate::{
         BuildRindexerFilterError, RindexerEventFilter,
     },
     indexer::IndexingEventsProgressState,
-    manifest::storage::CsvDetails,
-    PostgresClient,
+    manifest::storage::{CsvDetails, NdjsonDetails, ParquetDetails, PostgresPartitioning},
+    ClickhouseClient, PostgresClient, SqliteClient,
 };
 
 pub struct EventProcessingConfig {
@@ -22,6 +22,8 @@ pub struct EventProcessingConfig {
     pub info_log_name: String,
     pub topic_id: H256,
     pub event_name: String,
+    /// The event table name without the schema, the event name unless overridden in the storage
+    pub event_table_name: String,
     pub network_contract: Arc<NetworkContract>,
     pub start_block: U64,
     pub end_block: U64,
@@ -29,7 +31,12 @@ pub struct EventProcessingConfig {
     pub registry: Arc<EventCallbackRegistry>,
     pub progress: Arc<Mutex<IndexingEventsProgressState>>,
     pub database: Option<Arc<PostgresClient>>,
+    pub sqlite: Option<Arc<SqliteClient>>,
+    pub clickhouse: Option<Arc<ClickhouseClient>>,
+    pub postgres_partitioning: Option<PostgresPartitioning>,
     pub csv_details: Option<CsvDetails>,
+    pub parquet_details: Option<ParquetDetails>,
+    pub ndjson_details: Option<NdjsonDetails>,
     pub stream_last_synced_block_file_path: Option<String>,
     pub index_event_in_order: bool,
     pub live_indexing: bool,
@@ -0,0 +1,873 @@
+use std::{cmp::Ordering, fmt};
+
+use ethers::prelude::U512;
+use regex::Regex;
+use serde_json::Value;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    #[error("unexpected character `{0}` at position {1}")]
+    UnexpectedCharacter(char, usize),
+
+    #[error("unterminated string starting at position {0}")]
+    UnterminatedString(usize),
+
+    #[error("unexpected token `{0}` at position {1}")]
+    UnexpectedToken(String, usize),
+
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+
+    #[error("invalid number `{0}`")]
+    InvalidNumber(String),
+
+    #[error("invalid regex `{0}`: {1}")]
+    InvalidRegex(String, String),
+
+    #[error("`{0}` expects a string pattern")]
+    ExpectedRegexPattern(String),
+
+    #[error("field `{0}` not found in event data")]
+    FieldNotFound(String),
+
+    #[error("can not use {0} in {1}")]
+    TypeMismatch(String, String),
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("number overflow")]
+    Overflow,
+}
+
+/// Digits of `U512::MAX`
+const MAX_U512_DIGITS: usize = 155;
+
+/// Sign and magnitude integer which can hold every int256 and uint256 value, so comparisons and
+/// arithmetic over event fields never truncate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpressionNumber {
+    negative: bool,
+    magnitude: U512,
+}
+
+impl ExpressionNumber {
+    pub fn new(negative: bool, magnitude: U512) -> Self {
+        Self { negative: negative && !magnitude.is_zero(), magnitude }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn magnitude(&self) -> U512 {
+        self.magnitude
+    }
+
+    /// Parses decimal (`-12`), hex (`0x0c`) and scientific (`1e18`, `1.5e18`) integers.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (negative, unsigned) = match value.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+
+        if let Some(hex) = unsigned.strip_prefix("0x").or_else(|| unsigned.strip_prefix("0X")) {
+            if hex.is_empty() || hex.len() > 128 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            return U512::from_str_radix(hex, 16).ok().map(|m| Self::new(negative, m));
+        }
+
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<usize>().ok()?),
+            None => (unsigned, 0),
+        };
+
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer.is_empty() ||
+            !integer.chars().all(|c| c.is_ascii_digit()) ||
+            !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        // fractions are only allowed when the exponent shifts them into a whole number
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > exponent {
+            return None;
+        }
+
+        // U512 holds at most 155 digits, anything longer is rejected before the zeros are built
+        let integer = integer.trim_start_matches('0');
+        if integer.is_empty() && fraction.is_empty() {
+            return Some(Self::new(negative, U512::zero()));
+        }
+        if integer.len().saturating_add(exponent) > MAX_U512_DIGITS {
+            return None;
+        }
+
+        let digits = format!("{}{}{}", integer, fraction, "0".repeat(exponent - fraction.len()));
+        U512::from_dec_str(&digits).ok().map(|m| Self::new(negative, m))
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        if self.negative == other.negative {
+            return self.magnitude.checked_add(other.magnitude).map(|m| Self::new(self.negative, m));
+        }
+
+        match self.magnitude.cmp(&other.magnitude) {
+            Ordering::Less => Some(Self::new(other.negative, other.magnitude - self.magnitude)),
+            _ => Some(Self::new(self.negative, self.magnitude - other.magnitude)),
+        }
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(Self::new(!other.negative, other.magnitude))
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        self.magnitude
+            .checked_mul(other.magnitude)
+            .map(|m| Self::new(self.negative != other.negative, m))
+    }
+
+    fn checked_div(self, other: Self) -> Option<Self> {
+        if other.magnitude.is_zero() {
+            return None;
+        }
+        Some(Self::new(self.negative != other.negative, self.magnitude / other.magnitude))
+    }
+
+    fn checked_rem(self, other: Self) -> Option<Self> {
+        if other.magnitude.is_zero() {
+            return None;
+        }
+        Some(Self::new(self.negative, self.magnitude % other.magnitude))
+    }
+}
+
+impl Ord for ExpressionNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for ExpressionNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for ExpressionNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-{}", self.magnitude)
+        } else {
+            write!(f, "{}", self.magnitude)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionValue {
+    Number(ExpressionNumber),
+    String(String),
+    Bool(bool),
+    List(Vec<ExpressionValue>),
+    Null,
+}
+
+impl ExpressionValue {
+    fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null | Value::Object(_) => ExpressionValue::Null,
+            Value::Bool(b) => ExpressionValue::Bool(*b),
+            Value::Number(n) => match ExpressionNumber::parse(&n.to_string()) {
+                Some(number) => ExpressionValue::Number(number),
+                None => ExpressionValue::String(n.to_string()),
+            },
+            Value::String(s) => ExpressionValue::String(s.clone()),
+            Value::Array(values) => {
+                ExpressionValue::List(values.iter().map(ExpressionValue::from_json).collect())
+            }
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            ExpressionValue::Number(_) => "number",
+            ExpressionValue::String(_) => "string",
+            ExpressionValue::Bool(_) => "bool",
+            ExpressionValue::List(_) => "list",
+            ExpressionValue::Null => "null",
+        }
+    }
+
+    /// Numbers from the event data are mostly decimal strings (uint256 does not fit json numbers)
+    /// so strings are coerced when used as numbers.
+    pub fn as_number(&self) -> Option<ExpressionNumber> {
+        match self {
+            ExpressionValue::Number(n) => Some(*n),
+            ExpressionValue::String(s) => ExpressionNumber::parse(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            ExpressionValue::Number(n) => Some(n.to_string()),
+            ExpressionValue::String(s) => Some(s.clone()),
+            ExpressionValue::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn loose_eq(&self, other: &ExpressionValue) -> bool {
+        match (self, other) {
+            (ExpressionValue::Number(_), _) | (_, ExpressionValue::Number(_)) => {
+                match (self.as_number(), other.as_number()) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => false,
+                }
+            }
+            (ExpressionValue::String(a), ExpressionValue::String(b)) => {
+                // addresses and hashes are compared by value so `0x0` equals the zero address
+                // and checksums do not matter
+                if a.starts_with("0x") && b.starts_with("0x") {
+                    match (ExpressionNumber::parse(a), ExpressionNumber::parse(b)) {
+                        (Some(a), Some(b)) => a == b,
+                        _ => a.eq_ignore_ascii_case(b),
+                    }
+                } else {
+                    a == b
+                }
+            }
+            (ExpressionValue::Bool(b), ExpressionValue::String(s)) |
+            (ExpressionValue::String(s), ExpressionValue::Bool(b)) => b.to_string() == *s,
+            (ExpressionValue::List(a), ExpressionValue::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.loose_eq(b))
+            }
+            _ => self == other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComparisonOperator {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Literal(ExpressionValue),
+    Field(Vec<String>),
+    List(Vec<Expression>),
+    Negate(Box<Expression>),
+    Not(Box<Expression>),
+    Arithmetic(ArithmeticOperator, Box<Expression>, Box<Expression>),
+    Comparison(ComparisonOperator, Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Box<Expression>),
+    Matches(Box<Expression>, Regex),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+fn get_field<'a>(data: &'a Value, path: &[String]) -> Option<&'a Value> {
+    let mut current = data;
+    for key in path {
+        current = match current {
+            Value::Array(values) => values.get(key.parse::<usize>().ok()?)?,
+            _ => current.get(key)?,
+        };
+    }
+    Some(current)
+}
+
+impl Expression {
+    fn is_boolean(&self) -> bool {
+        matches!(
+            self,
+            Expression::Not(_) |
+                Expression::Comparison(..) |
+                Expression::In(..) |
+                Expression::Matches(..) |
+                Expression::And(..) |
+                Expression::Or(..)
+        )
+    }
+
+    pub fn evaluate(&self, data: &Value) -> Result<ExpressionValue, ExpressionError> {
+        match self {
+            Expression::Literal(value) => Ok(value.clone()),
+            Expression::Field(path) => get_field(data, path)
+                .map(ExpressionValue::from_json)
+                .ok_or_else(|| ExpressionError::FieldNotFound(path.join("."))),
+            Expression::List(items) => Ok(ExpressionValue::List(
+                items.iter().map(|item| item.evaluate(data)).collect::<Result<_, _>>()?,
+            )),
+            Expression::Negate(inner) => {
+                let value = inner.evaluate(data)?;
+                let number = value.as_number().ok_or_else(|| {
+                    ExpressionError::TypeMismatch(value.type_name().to_string(), "`-`".to_string())
+                })?;
+                Ok(ExpressionValue::Number(ExpressionNumber::new(
+                    !number.is_negative(),
+                    number.magnitude(),
+                )))
+            }
+            Expression::Not(inner) => Ok(ExpressionValue::Bool(!inner.evaluate_bool(data)?)),
+            Expression::Arithmetic(operator, left, right) => {
+                let left = left.evaluate(data)?;
+                let right = right.evaluate(data)?;
+                let (a, b) = match (left.as_number(), right.as_number()) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => {
+                        return Err(ExpressionError::TypeMismatch(
+                            format!("{} and {}", left.type_name(), right.type_name()),
+                            "arithmetic".to_string(),
+                        ))
+                    }
+                };
+
+                let result = match operator {
+                    ArithmeticOperator::Add => a.checked_add(b),
+                    ArithmeticOperator::Subtract => a.checked_sub(b),
+                    ArithmeticOperator::Multiply => a.checked_mul(b),
+                    ArithmeticOperator::Divide => {
+                        a.checked_div(b).ok_or(ExpressionError::DivisionByZero).map(Some)?
+                    }
+                    ArithmeticOperator::Remainder => {
+                        a.checked_rem(b).ok_or(ExpressionError::DivisionByZero).map(Some)?
+                    }
+                };
+
+                result.map(ExpressionValue::Number).ok_or(ExpressionError::Overflow)
+            }
+            Expression::Comparison(operator, left, right) => {
+                let left = left.evaluate(data)?;
+                let right = right.evaluate(data)?;
+                let result = match operator {
+                    ComparisonOperator::Equal => left.loose_eq(&right),
+                    ComparisonOperator::NotEqual => !left.loose_eq(&right),
+                    _ => {
+                        let ordering = match (left.as_number(), right.as_number()) {
+                            (Some(a), Some(b)) => a.cmp(&b),
+                            _ => {
+                                return Err(ExpressionError::TypeMismatch(
+                                    format!("{} and {}", left.type_name(), right.type_name()),
+                                    "an ordering comparison".to_string(),
+                                ))
+                            }
+                        };
+                        match operator {
+                            ComparisonOperator::GreaterThan => ordering == Ordering::Greater,
+                            ComparisonOperator::GreaterThanOrEqual => ordering != Ordering::Less,
+                            ComparisonOperator::LessThan => ordering == Ordering::Less,
+                            _ => ordering != Ordering::Greater,
+                        }
+                    }
+                };
+                Ok(ExpressionValue::Bool(result))
+            }
+            Expression::In(value, list) => {
+                let value = value.evaluate(data)?;
+                match list.evaluate(data)? {
+                    ExpressionValue::List(items) => {
+                        Ok(ExpressionValue::Bool(items.iter().any(|item| item.loose_eq(&value))))
+                    }
+                    other => Err(ExpressionError::TypeMismatch(
+                        other.type_name().to_string(),
+                        "`in`".to_string(),
+                    )),
+                }
+            }
+            Expression::Matches(value, regex) => {
+                let value = value.evaluate(data)?;
+                let text = value.as_text().ok_or_else(|| {
+                    ExpressionError::TypeMismatch(
+                        value.type_name().to_string(),
+                        "a regex match".to_string(),
+                    )
+                })?;
+                Ok(ExpressionValue::Bool(regex.is_match(&text)))
+            }
+            Expression::And(left, right) => {
+                Ok(ExpressionValue::Bool(left.evaluate_bool(data)? && right.evaluate_bool(data)?))
+            }
+            Expression::Or(left, right) => {
+                Ok(ExpressionValue::Bool(left.evaluate_bool(data)? || right.evaluate_bool(data)?))
+            }
+        }
+    }
+
+    pub fn evaluate_bool(&self, data: &Value) -> Result<bool, ExpressionError> {
+        match self.evaluate(data)? {
+            ExpressionValue::Bool(b) => Ok(b),
+            other => Err(ExpressionError::TypeMismatch(
+                other.type_name().to_string(),
+                "a condition".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    String(String),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::String(s) => write!(f, "'{}'", s),
+            Token::Identifier(i) => write!(f, "{}", i),
+            Token::Symbol(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+// longest first so `>=` wins over `>`
+const SYMBOLS: [&str; 21] = [
+    "||", "&&", "==", "!=", ">=", "<=", "=~", "!", "=", ">", "<", "+", "-", "*", "/", "%", "(",
+    ")", "[", "]", ",",
+];
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        if c == '"' || c == '\'' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(ExpressionError::UnterminatedString(start)),
+                    Some('\\') => {
+                        if let Some(escaped) = chars.get(i + 1) {
+                            value.push(*escaped);
+                        }
+                        i += 2;
+                    }
+                    Some(q) if *q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(other) => {
+                        value.push(*other);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push((Token::String(value), start));
+        } else if c.is_ascii_digit() {
+            // hex values which are not valid numbers (like addresses with typos) are kept whole
+            // so they can still be compared as strings
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            let raw: String = chars[start..i].iter().collect();
+            if raw.starts_with("0x") || raw.starts_with("0X") {
+                tokens.push((Token::String(raw), start));
+            } else {
+                tokens.push((Token::Number(raw), start));
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() &&
+                (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            tokens.push((Token::Identifier(chars[start..i].iter().collect()), start));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            match SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+                Some(symbol) => {
+                    tokens.push((Token::Symbol(symbol), start));
+                    i += symbol.len();
+                }
+                None => return Err(ExpressionError::UnexpectedCharacter(c, start)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    subject: Option<Vec<String>>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, ExpressionError> {
+        let token = self.tokens.get(self.position).map(|(token, _)| token.clone());
+        self.position += 1;
+        token.ok_or(ExpressionError::UnexpectedEnd)
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        match self.tokens.get(self.position) {
+            Some((token, position)) => {
+                ExpressionError::UnexpectedToken(token.to_string(), *position)
+            }
+            None => ExpressionError::UnexpectedEnd,
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Identifier(i)) if i == keyword) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ExpressionError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn parse(mut self) -> Result<Expression, ExpressionError> {
+        let expression = self.or()?;
+        if self.position < self.tokens.len() {
+            return Err(self.unexpected());
+        }
+        Ok(expression)
+    }
+
+    fn or(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.and()?;
+        while self.eat_symbol("||") {
+            left = Expression::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.condition()?;
+        while self.eat_symbol("&&") {
+            left = Expression::And(Box::new(left), Box::new(self.condition()?));
+        }
+        Ok(left)
+    }
+
+    /// A boolean position, when the condition is keyed by a field a bare value here is shorthand
+    /// for `field = value` so `"from": "0x01 || 0x02"` keeps working.
+    fn condition(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat_symbol("!") {
+            return Ok(Expression::Not(Box::new(self.condition()?)));
+        }
+
+        let bare_word = matches!(
+            self.peek(),
+            Some(Token::Identifier(i)) if !i.contains('.') && !["true", "false", "null"].contains(&i.as_str())
+        );
+        let expression = self.comparison()?;
+
+        match &self.subject {
+            Some(subject) if !expression.is_boolean() => {
+                let value = match expression {
+                    Expression::Field(path) if bare_word => {
+                        Expression::Literal(ExpressionValue::String(path.join(".")))
+                    }
+                    other => other,
+                };
+                Ok(Expression::Comparison(
+                    ComparisonOperator::Equal,
+                    Box::new(Expression::Field(subject.clone())),
+                    Box::new(value),
+                ))
+            }
+            _ => Ok(expression),
+        }
+    }
+
+    fn comparison_operator(&self) -> Option<ComparisonOperator> {
+        match self.peek() {
+            Some(Token::Symbol("=")) | Some(Token::Symbol("==")) => Some(ComparisonOperator::Equal),
+            Some(Token::Symbol("!=")) => Some(ComparisonOperator::NotEqual),
+            Some(Token::Symbol(">")) => Some(ComparisonOperator::GreaterThan),
+            Some(Token::Symbol(">=")) => Some(ComparisonOperator::GreaterThanOrEqual),
+            Some(Token::Symbol("<")) => Some(ComparisonOperator::LessThan),
+            Some(Token::Symbol("<=")) => Some(ComparisonOperator::LessThanOrEqual),
+            _ => None,
+        }
+    }
+
+    fn is_match_operator(&self) -> bool {
+        matches!(self.peek(), Some(Token::Symbol("=~"))) ||
+            matches!(self.peek(), Some(Token::Identifier(i)) if i == "matches" || i == "in")
+    }
+
+    fn comparison(&mut self) -> Result<Expression, ExpressionError> {
+        // `>= 10` with no left hand side compares against the field the condition is keyed by
+        let left = match &self.subject {
+            Some(subject) if self.comparison_operator().is_some() || self.is_match_operator() => {
+                Expression::Field(subject.clone())
+            }
+            _ => self.additive()?,
+        };
+
+        if let Some(operator) = self.comparison_operator() {
+            self.position += 1;
+            let right = self.additive()?;
+            return Ok(Expression::Comparison(operator, Box::new(left), Box::new(right)));
+        }
+
+        if self.eat_keyword("in") {
+            let list = self.additive()?;
+            return Ok(Expression::In(Box::new(left), Box::new(list)));
+        }
+
+        if self.eat_symbol("=~") || self.eat_keyword("matches") {
+            return match self.next()? {
+                Token::String(pattern) => {
+                    let regex = Regex::new(&pattern)
+                        .map_err(|e| ExpressionError::InvalidRegex(pattern, e.to_string()))?;
+                    Ok(Expression::Matches(Box::new(left), regex))
+                }
+                other => Err(ExpressionError::ExpectedRegexPattern(other.to_string())),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let operator = if self.eat_symbol("+") {
+                ArithmeticOperator::Add
+            } else if self.eat_symbol("-") {
+                ArithmeticOperator::Subtract
+            } else {
+                return Ok(left);
+            };
+            left =
+                Expression::Arithmetic(operator, Box::new(left), Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.unary()?;
+        loop {
+            let operator = if self.eat_symbol("*") {
+                ArithmeticOperator::Multiply
+            } else if self.eat_symbol("/") {
+                ArithmeticOperator::Divide
+            } else if self.eat_symbol("%") {
+                ArithmeticOperator::Remainder
+            } else {
+                return Ok(left);
+            };
+            left = Expression::Arithmetic(operator, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat_symbol("-") {
+            return Ok(Expression::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat_symbol("!") {
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat_symbol("(") {
+            let expression = self.or()?;
+            self.expect_symbol(")")?;
+            return Ok(expression);
+        }
+
+        if self.eat_symbol("[") {
+            let mut items = Vec::new();
+            if !self.eat_symbol("]") {
+                loop {
+                    items.push(self.additive()?);
+                    if self.eat_symbol("]") {
+                        break;
+                    }
+                    self.expect_symbol(",")?;
+                }
+            }
+            return Ok(Expression::List(items));
+        }
+
+        let position = self.position;
+        match self.next()? {
+            Token::Number(raw) => ExpressionNumber::parse(&raw)
+                .map(|n| Expression::Literal(ExpressionValue::Number(n)))
+                .ok_or(ExpressionError::InvalidNumber(raw)),
+            Token::String(s) => Ok(Expression::Literal(ExpressionValue::String(s))),
+            Token::Identifier(identifier) => match identifier.as_str() {
+                "true" => Ok(Expression::Literal(ExpressionValue::Bool(true))),
+                "false" => Ok(Expression::Literal(ExpressionValue::Bool(false))),
+                "null" => Ok(Expression::Literal(ExpressionValue::Null)),
+                _ => Ok(Expression::Field(identifier.split('.').map(String::from).collect())),
+            },
+            Token::Symbol(_) => {
+                self.position = position;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+/// Parses an expression, `subject` is the field the expression is keyed by (if any) which enables
+/// the shorthand forms `>= 10` and `0x01 || 0x02`.
+pub fn parse_expression(input: &str, subject: Option<&str>) -> Result<Expression, ExpressionError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(ExpressionError::UnexpectedEnd);
+    }
+
+    Parser {
+        tokens,
+        position: 0,
+        subject: subject.map(|s| s.split('.').map(String::from).collect()),
+    }
+    .parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn data() -> Value {
+        json!({
+            "from": "0x0338ce5020c447f7e668dc2ef778025ce3982662",
+            "to": "0xae78736cd615f374d3085123a210448e74fc6393",
+            "value": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+            "amount0": "-2500",
+            "tick": -887220,
+            "symbol": "r>ETH",
+            "ids": ["1", "2", "3"],
+            "quoteParams": { "profileId": "1" }
+        })
+    }
+
+    fn evaluate(input: &str, subject: Option<&str>) -> bool {
+        parse_expression(input, subject).unwrap().evaluate_bool(&data()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(ExpressionNumber::parse("1e18").unwrap().to_string(), "1000000000000000000");
+        assert_eq!(ExpressionNumber::parse("1.5e3").unwrap().to_string(), "1500");
+        assert_eq!(ExpressionNumber::parse("-0x10").unwrap().to_string(), "-16");
+        assert!(ExpressionNumber::parse("1.5").is_none());
+        assert!(ExpressionNumber::parse("abc").is_none());
+        assert_eq!(ExpressionNumber::parse("0e99999999999").unwrap().to_string(), "0");
+        assert_eq!(ExpressionNumber::parse("001e2").unwrap().to_string(), "100");
+        assert!(ExpressionNumber::parse("1e99999999999").is_none());
+        assert!(ExpressionNumber::parse("1e155").is_none());
+        assert!(ExpressionNumber::parse("1e154").is_some());
+    }
+
+    #[test]
+    fn test_full_width_and_signed_comparisons() {
+        assert!(evaluate("value > 1e18", None));
+        assert!(evaluate(
+            ">= 115792089237316195423570985008687907853269984665640564039457584007913129639935",
+            Some("value")
+        ));
+        assert!(evaluate("amount0 < -2000 && tick <= -887220", None));
+        assert!(evaluate("value - 1 < value", None));
+        assert!(evaluate("(amount0 * 2) / 5 = -1000", None));
+    }
+
+    #[test]
+    fn test_shorthand_conditions() {
+        assert!(evaluate(">=1 && <=2", Some("quoteParams.profileId")));
+        assert!(evaluate("=1", Some("quoteParams.profileId")));
+        assert!(evaluate(
+            "0x0338CE5020C447F7E668DC2EF778025CE3982662 || 0x0000000000000000000000000000000000000000",
+            Some("from")
+        ));
+        assert!(!evaluate("0x0000000000000000000000000000000000000000", Some("from")));
+        assert!(evaluate("from != 0x0 && 0x0 == 0x0000000000000000000000000000000000000000", None));
+        assert!(evaluate("'r>ETH'", Some("symbol")));
+    }
+
+    #[test]
+    fn test_logic_lists_and_regex() {
+        assert!(evaluate("!(from = to) && '2' in ids", None));
+        assert!(evaluate("in ['0xAE78736CD615F374D3085123A210448E74FC6393']", Some("to")));
+        assert!(evaluate("symbol =~ '^r>' && !(symbol matches 'eth$')", None));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(parse_expression("value >", None).is_err());
+        assert!(parse_expression("(value > 1", None).is_err());
+        assert!(parse_expression("symbol =~ '['", None).is_err());
+        assert!(parse_expression("value # 1", None).is_err());
+        assert!(parse_expression("'unterminated", None).is_err());
+        assert!(parse_expression("", None).is_err());
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let expression = parse_expression("missing > 1", None).unwrap();
+        assert!(expression.evaluate_bool(&data()).is_err());
+
+        let expression = parse_expression("symbol > 1", None).unwrap();
+        assert!(expression.evaluate_bool(&data()).is_err());
+
+        let expression = parse_expression("value / 0 > 1", None).unwrap();
+        assert_eq!(expression.evaluate_bool(&data()), Err(ExpressionError::DivisionByZero));
+    }
+}
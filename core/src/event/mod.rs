@@ -10,4 +10,7 @@ mod message;
 pub use message::EventMessage;
 
 mod conditions;
-pub use conditions::filter_event_data_by_conditions;
+pub use conditions::{filter_event_data_by_conditions, validate_conditions, ConditionError};
+
+mod expression;
+pub use expression::{parse_expression, Expression, ExpressionError, ExpressionValue};
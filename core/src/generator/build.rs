@@ -230,9 +230,15 @@ pub fn generate_rindexer_handlers(
             handlers.push_str(
                 r#"
         use std::path::PathBuf;
-        use rindexer::event::callback_registry::EventCallbackRegistry;
+        use rindexer::{
+            event::callback_registry::EventCallbackRegistry, manifest::yaml::read_manifest,
+            set_postgres_connection_details,
+        };
         
         pub async fn register_all_handlers(manifest_path: &PathBuf) -> EventCallbackRegistry {
+             // the handlers connect to postgres when they are created
+             let manifest = read_manifest(manifest_path).expect("Failed to read rindexer.yaml");
+             set_postgres_connection_details(manifest.storage.postgres_connection());
              let mut registry = EventCallbackRegistry::new();
         "#,
             );
@@ -389,6 +395,7 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
 
                 let mut enable_graphql = false;
                 let mut enable_indexer = false;
+                let mut allow_destructive_migrations = false;
                 
                 let mut port: Option<u16> = None;
 
@@ -396,6 +403,7 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
                     match arg.as_str() {
                         "--graphql" => enable_graphql = true,
                         "--indexer" => enable_indexer = true,
+                        "--allow-destructive" => allow_destructive_migrations = true,
                         _ if arg.starts_with("--port=") || arg.starts_with("--p") => {
                             if let Some(value) = arg.split('=').nth(1) {
                                 let overridden_port = value.parse::<u16>();
@@ -425,6 +433,7 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
                             indexing_details: if enable_indexer {
                                 Some(IndexingDetails {
                                     registry: register_all_handlers(&manifest_path).await,
+                                    allow_destructive_migrations,
                                 })
                             } else {
                                 None
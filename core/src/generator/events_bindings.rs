@@ -188,8 +188,11 @@ fn generate_csv_instance(
     }
 
     let csv_path = event_info.create_csv_file_for_event(project_path, contract, csv_path)?;
-    let headers: Vec<String> =
-        event_info.csv_headers_for_event().iter().map(|h| format!("\"{}\"", h)).collect();
+    let headers: Vec<String> = event_info
+        .csv_headers_for_event(&contract.storage_event(&event_info.name))
+        .iter()
+        .map(|h| format!("\"{}\"", h))
+        .collect();
 
     Ok(Code::new(format!(
         r#"
@@ -727,6 +730,7 @@ pub fn generate_event_handlers(
             &event.inputs,
             &GenerateAbiPropertiesType::Object,
             None,
+            false,
         );
 
         let mut csv_write = String::new();
@@ -878,15 +882,21 @@ pub fn generate_event_handlers(
                             }}
                     }}
                 "#,
-                table_name =
-                    generate_event_table_full_name(indexer_name, &contract.name, &event.name),
+                table_name = generate_event_table_full_name(
+                    indexer_name,
+                    &contract.name,
+                    &contract.event_table_name(&event.name),
+                ),
                 handler_name = event.name,
                 event_type_name = event_type_name,
-                columns_names = generate_column_names_only_with_base_properties(&event.inputs)
-                    .iter()
-                    .map(|item| format!("\"{}\".to_string()", item))
-                    .collect::<Vec<String>>()
-                    .join(", "),
+                columns_names = generate_column_names_only_with_base_properties(
+                    &event.inputs,
+                    &contract.storage_event(&event.name),
+                )
+                .iter()
+                .map(|item| format!("\"{}\".to_string()", item))
+                .collect::<Vec<String>>()
+                .join(", "),
                 data = data,
                 csv_write = csv_write,
                 csv_bulk_data = if storage.csv_enabled() {
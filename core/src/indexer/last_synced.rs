@@ -1,6 +1,7 @@
 use std::{path::Path, sync::Arc};
 
 use ethers::prelude::U64;
+use rusqlite::types::Value;
 use rust_decimal::Decimal;
 use tokio::{
     fs,
@@ -10,10 +11,17 @@ use tokio::{
 use tracing::error;
 
 use crate::{
+    database::{
+        clickhouse::generate::generate_clickhouse_internal_event_table_name,
+        sqlite::generate::generate_sqlite_internal_event_table_name,
+    },
     event::config::EventProcessingConfig,
     helpers::{camel_to_snake, get_full_path},
-    manifest::{storage::CsvDetails, stream::StreamsConfig},
-    EthereumSqlTypeWrapper, PostgresClient,
+    manifest::{
+        storage::{CsvDetails, NdjsonDetails, ParquetDetails},
+        stream::StreamsConfig,
+    },
+    ClickhouseClient, EthereumSqlTypeWrapper, PostgresClient, SqliteClient,
 };
 
 async fn get_last_synced_block_number_file(
@@ -49,7 +57,7 @@ async fn get_last_synced_block_number_file(
     Ok(None)
 }
 
-fn build_last_synced_block_number_file(
+pub(crate) fn build_last_synced_block_number_file(
     full_path: &Path,
     contract_name: &str,
     network: &str,
@@ -68,7 +76,11 @@ fn build_last_synced_block_number_file(
 pub struct SyncConfig<'a> {
     pub project_path: &'a Path,
     pub database: &'a Option<Arc<PostgresClient>>,
+    pub sqlite: &'a Option<Arc<SqliteClient>>,
+    pub clickhouse: &'a Option<Arc<ClickhouseClient>>,
     pub csv_details: &'a Option<CsvDetails>,
+    pub parquet_details: &'a Option<ParquetDetails>,
+    pub ndjson_details: &'a Option<NdjsonDetails>,
     pub stream_details: &'a Option<&'a StreamsConfig>,
     pub contract_csv_enabled: bool,
     pub indexer_name: &'a str,
@@ -78,8 +90,11 @@ pub struct SyncConfig<'a> {
 }
 
 pub async fn get_last_synced_block_number(config: SyncConfig<'_>) -> Option<U64> {
+    let no_database =
+        config.database.is_none() && config.sqlite.is_none() && config.clickhouse.is_none();
+
     // Check CSV file for last seen block as no database enabled
-    if config.database.is_none() && config.contract_csv_enabled {
+    if no_database && config.contract_csv_enabled {
         if let Some(csv_details) = config.csv_details {
             return if let Ok(result) = get_last_synced_block_number_file(
                 &get_full_path(config.project_path, &csv_details.path).unwrap_or_else(|_| {
@@ -105,8 +120,49 @@ pub async fn get_last_synced_block_number(config: SyncConfig<'_>) -> Option<U64>
         }
     }
 
-    // Then check streams if no csv or database to find out last synced block
-    if config.database.is_none() && !config.contract_csv_enabled && config.stream_details.is_some()
+    let file_storage_path = config
+        .parquet_details
+        .as_ref()
+        .filter(|details| details.enabled)
+        .map(|details| &details.path)
+        .or_else(|| {
+            config
+                .ndjson_details
+                .as_ref()
+                .filter(|details| details.enabled)
+                .map(|details| &details.path)
+        });
+
+    // Then check parquet or ndjson files if no csv or database
+    if no_database && !config.contract_csv_enabled {
+        if let Some(file_storage_path) = file_storage_path {
+            return match get_last_synced_block_number_file(
+                &get_full_path(config.project_path, file_storage_path).unwrap_or_else(|_| {
+                    panic!("failed to get full path {}", config.project_path.display())
+                }),
+                config.contract_name,
+                config.network,
+                config.event_name,
+            )
+            .await
+            {
+                Ok(result) => result.filter(|value| !value.is_zero()),
+                Err(e) => {
+                    error!(
+                        "Error fetching last synced block from file - path - {} error - {:?}",
+                        file_storage_path, e
+                    );
+                    None
+                }
+            };
+        }
+    }
+
+    // Then check streams if no csv, parquet, ndjson or database to find out last synced block
+    if no_database &&
+        !config.contract_csv_enabled &&
+        file_storage_path.is_none() &&
+        config.stream_details.is_some()
     {
         let stream_details = config.stream_details.as_ref().unwrap();
 
@@ -165,6 +221,49 @@ pub async fn get_last_synced_block_number(config: SyncConfig<'_>) -> Option<U64>
                 None
             }
         }
+    } else if let Some(sqlite) = config.sqlite {
+        let query = format!(
+            r#"SELECT last_synced_block FROM "{}" WHERE network = ?1"#,
+            generate_sqlite_internal_event_table_name(
+                config.indexer_name,
+                config.contract_name,
+                config.event_name
+            )
+        );
+
+        match sqlite
+            .query_one_or_none::<i64>(&query, vec![Value::Text(config.network.to_string())])
+            .await
+        {
+            Ok(Some(result)) if result > 0 => Some(U64::from(result as u64)),
+            Ok(_) => None,
+            Err(e) => {
+                error!("Error fetching last synced block from sqlite: {:?}", e);
+                None
+            }
+        }
+    } else if let Some(clickhouse) = config.clickhouse {
+        let query = format!(
+            "SELECT toString(max(last_synced_block)) AS last_synced_block FROM {} WHERE network = {{network:String}}",
+            generate_clickhouse_internal_event_table_name(
+                config.indexer_name,
+                config.contract_name,
+                config.event_name
+            )
+        );
+
+        match clickhouse.query(&query, &[("network", config.network)]).await {
+            Ok(rows) => rows
+                .first()
+                .and_then(|row| row.get("last_synced_block"))
+                .and_then(|value| value.as_str())
+                .and_then(|value| U64::from_dec_str(value).ok())
+                .filter(|value| !value.is_zero()),
+            Err(e) => {
+                error!("Error fetching last synced block from clickhouse: {:?}", e);
+                None
+            }
+        }
     } else {
         None
     }
@@ -246,6 +345,47 @@ pub fn update_progress_and_last_synced(config: Arc<EventProcessingConfig>, to_bl
             if let Err(e) = result {
                 error!("Error updating last synced block: {:?}", e);
             }
+        } else if let Some(sqlite) = &config.sqlite {
+            let result = sqlite
+                .execute(
+                    &format!(
+                        r#"UPDATE "{}" SET last_synced_block = ?1 WHERE network = ?2 AND ?1 > last_synced_block"#,
+                        generate_sqlite_internal_event_table_name(
+                            &config.indexer_name,
+                            &config.contract_name,
+                            &config.event_name
+                        )
+                    ),
+                    vec![
+                        Value::Integer(to_block.as_u64() as i64),
+                        Value::Text(config.network_contract.network.clone()),
+                    ],
+                )
+                .await;
+
+            if let Err(e) = result {
+                error!("Error updating last synced block in sqlite: {:?}", e);
+            }
+        } else if let Some(clickhouse) = &config.clickhouse {
+            // rows are only ever inserted and the highest block is read back
+            let result = clickhouse
+                .bulk_insert(
+                    &generate_clickhouse_internal_event_table_name(
+                        &config.indexer_name,
+                        &config.contract_name,
+                        &config.event_name,
+                    ),
+                    &["network".to_string(), "last_synced_block".to_string()],
+                    &[vec![
+                        EthereumSqlTypeWrapper::String(config.network_contract.network.clone()),
+                        EthereumSqlTypeWrapper::U64(to_block),
+                    ]],
+                )
+                .await;
+
+            if let Err(e) = result {
+                error!("Error updating last synced block in clickhouse: {:?}", e);
+            }
         } else if let Some(csv_details) = &config.csv_details {
             if let Err(e) = update_last_synced_block_number_for_file(
                 &config,
@@ -261,6 +401,38 @@ pub fn update_progress_and_last_synced(config: Arc<EventProcessingConfig>, to_bl
                     csv_details.path, e
                 );
             }
+        } else if let Some(parquet_details) = &config.parquet_details {
+            // rows in a parquet file which is never closed are recovered by the appender rewinding
+            // this block on the next start
+            if let Err(e) = update_last_synced_block_number_for_file(
+                &config,
+                &get_full_path(&config.project_path, &parquet_details.path).unwrap_or_else(|_| {
+                    panic!("failed to get full path {}", config.project_path.display())
+                }),
+                to_block,
+            )
+            .await
+            {
+                error!(
+                    "Error updating last synced block to parquet - path - {} error - {:?}",
+                    parquet_details.path, e
+                );
+            }
+        } else if let Some(ndjson_details) = &config.ndjson_details {
+            if let Err(e) = update_last_synced_block_number_for_file(
+                &config,
+                &get_full_path(&config.project_path, &ndjson_details.path).unwrap_or_else(|_| {
+                    panic!("failed to get full path {}", config.project_path.display())
+                }),
+                to_block,
+            )
+            .await
+            {
+                error!(
+                    "Error updating last synced block to ndjson - path - {} error - {:?}",
+                    ndjson_details.path, e
+                );
+            }
         } else if let Some(stream_last_synced_block_file_path) =
             &config.stream_last_synced_block_file_path
         {
@@ -3,7 +3,7 @@ use std::str::FromStr;
 use ethers::{
     abi::{Event, Log as ParsedLog, LogParam, RawLog, Token},
     addressbook::Address,
-    prelude::{Block, Bloom, FilteredParams, ValueOrArray, H256, U256},
+    prelude::{Block, Bloom, FilteredParams, ValueOrArray, H256, I256, U256},
     types::{BigEndianHash, Log},
     utils::keccak256,
 };
@@ -32,7 +32,8 @@ fn map_token_to_raw_values(token: &Token) -> Vec<String> {
     match token {
         Token::Address(addr) => vec![format!("{:?}", addr)],
         Token::FixedBytes(bytes) | Token::Bytes(bytes) => vec![format!("{:?}", bytes)],
-        Token::Int(int) => vec![int.to_string()],
+        // signed values are two's complement so need converting to keep the sign
+        Token::Int(int) => vec![I256::from_raw(*int).to_string()],
         Token::Uint(uint) => vec![uint.to_string()],
         Token::Bool(b) => vec![b.to_string()],
         Token::String(s) => vec![s.clone()],
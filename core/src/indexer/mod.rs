@@ -4,22 +4,27 @@ mod progress;
 pub use progress::{IndexingEventProgressStatus, IndexingEventsProgressState};
 use serde::{Deserialize, Serialize};
 
-mod log_helpers;
+pub(crate) mod log_helpers;
 pub use log_helpers::parse_topic;
 mod dependency;
 pub use dependency::ContractEventDependenciesMapFromRelationshipsError;
 mod fetch_logs;
-mod last_synced;
+pub(crate) mod last_synced;
 pub mod no_code;
 mod reorg;
+pub(crate) mod script;
+pub(crate) mod transform;
 pub mod start;
 pub use dependency::{ContractEventDependencies, EventDependencies, EventsDependencyTree};
 
-use crate::manifest::contract::Contract;
+use crate::manifest::{contract::Contract, entity::Entity};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Indexer {
     pub name: String,
 
     pub contracts: Vec<Contract>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<Entity>>,
 }
@@ -1,23 +1,52 @@
-use std::{fs, io, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+    sync::Arc,
+};
 
 use colored::Colorize;
-use ethers::abi::{Abi, Contract as EthersContract, Event};
+use csv::QuoteStyle;
+use ethers::{
+    abi::{Abi, Contract as EthersContract, Event, LogParam, Token},
+    providers::ProviderError,
+    types::{Address, H256, U256, U64},
+};
 use serde_json::Value;
-use tokio_postgres::types::Type as PgType;
+use tokio_postgres::types::{ToSql, Type as PgType};
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    abi::{ABIItem, CreateCsvFileForEvent, EventInfo, ParamTypeError, ReadAbiError},
+    abi::{ABIInput, ABIItem, CreateCsvFileForEvent, EventInfo, ParamTypeError, ReadAbiError},
     chat::ChatClients,
-    database::postgres::{
-        client::PostgresClient,
-        generate::{
-            generate_column_names_only_with_base_properties, generate_event_table_full_name,
+    database::{
+        clickhouse::{
+            client::ClickhouseClient,
+            generate::generate_clickhouse_event_table_name,
+            setup::{setup_clickhouse, SetupClickhouseError},
         },
-        setup::{setup_postgres, SetupPostgresError},
-        sql_type_wrapper::{
-            map_ethereum_wrapper_to_json, map_log_params_to_ethereum_wrapper,
-            EthereumSqlTypeWrapper,
+        postgres::{
+            applied_events::applied_events_statement,
+            client::{set_postgres_connection_details, PostgresClient},
+            entities::{generate_entity_rule_statements, EntityRuleStatement},
+            generate::{
+                generate_column_names_only_with_base_properties, generate_event_table_full_name,
+                generate_indexer_contract_schema_name,
+            },
+            rollups::{generate_rollup_statements, RollupStatement},
+            scripts::generate_script_row_insert,
+            setup::{setup_postgres, SetupPostgresError},
+            sql_type_wrapper::{
+                map_ethereum_wrapper_to_json, map_ethereum_wrapper_to_json_value,
+                map_ethereum_wrapper_to_typed_json_value, map_json_to_ethereum_wrappers,
+                map_json_to_log_params, map_json_value_to_ethereum_wrapper,
+                map_log_params_to_ethereum_wrapper, EthereumSqlTypeWrapper, ScaledAmount,
+            },
+        },
+        sqlite::{
+            client::SqliteClient,
+            generate::generate_sqlite_event_table_name,
+            setup::{setup_sqlite, SetupSqliteError},
         },
     },
     event::{
@@ -26,19 +55,34 @@ use crate::{
             EventCallbackType, TxInformation,
         },
         contract_setup::{ContractInformation, CreateContractInformationError},
-        EventMessage,
+        filter_event_data_by_conditions, parse_expression, EventMessage, Expression,
+        ExpressionValue,
     },
     generate_random_id,
     helpers::get_full_path,
-    indexer::log_helpers::{map_log_params_to_raw_values, parse_log},
+    indexer::{
+        log_helpers::{map_log_params_to_raw_values, parse_log},
+        script::{ScriptError, ScriptHandler},
+        transform::TransformHook,
+    },
     manifest::{
         core::Manifest,
+        storage::{
+            ComputedColumn, ComputedColumnType, CsvCompression, CsvQuoteStyle, StorageEvent,
+        },
         yaml::{read_manifest, ReadManifestError},
     },
-    provider::{CreateNetworkProvider, RetryClientError},
+    provider::{CreateNetworkProvider, JsonRpcCachedProvider, RetryClientError},
     setup_info_logger,
+    simple_file_formatters::{
+        compression::FileCompression,
+        csv::CsvFileSettings,
+        ndjson::{NdjsonAppenderError, NdjsonFileSettings},
+        parquet::{generate_parquet_event_schema, ParquetAppenderError, ParquetFileSettings},
+    },
     streams::StreamsClients,
-    AsyncCsvAppender, FutureExt, IndexingDetails, StartDetails, StartNoCodeDetails,
+    AsyncCsvAppender, AsyncNdjsonAppender, AsyncParquetAppender, FutureExt, IndexingDetails,
+    StartDetails, StartNoCodeDetails,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -52,6 +96,12 @@ pub enum SetupNoCodeError {
     #[error("Could not setup postgres: {0}")]
     SetupPostgresError(#[from] SetupPostgresError),
 
+    #[error("Could not setup sqlite: {0}")]
+    SetupSqliteError(#[from] SetupSqliteError),
+
+    #[error("Could not setup clickhouse: {0}")]
+    SetupClickhouseError(#[from] SetupClickhouseError),
+
     #[error("{0}")]
     RetryClientError(#[from] RetryClientError),
 
@@ -72,13 +122,32 @@ pub async fn setup_no_code(
     match project_path {
         Some(project_path) => {
             let mut manifest = read_manifest(details.manifest_path)?;
+            set_postgres_connection_details(manifest.storage.postgres_connection());
             setup_info_logger();
 
             info!("Starting rindexer no code");
 
             let mut postgres: Option<Arc<PostgresClient>> = None;
             if manifest.storage.postgres_enabled() {
-                postgres = Some(Arc::new(setup_postgres(project_path, &manifest).await?));
+                postgres = Some(Arc::new(
+                    setup_postgres(
+                        project_path,
+                        &manifest,
+                        details.indexing_details.allow_destructive_migrations,
+                    )
+                    .await?,
+                ));
+            }
+
+            let mut sqlite: Option<Arc<SqliteClient>> = None;
+            if let Some(sqlite_details) = manifest.storage.sqlite.as_ref().filter(|s| s.enabled) {
+                sqlite =
+                    Some(Arc::new(setup_sqlite(project_path, &manifest, sqlite_details).await?));
+            }
+
+            let mut clickhouse: Option<Arc<ClickhouseClient>> = None;
+            if manifest.storage.clickhouse_enabled() {
+                clickhouse = Some(Arc::new(setup_clickhouse(project_path, &manifest).await?));
             }
 
             if !details.indexing_details.enabled {
@@ -99,8 +168,15 @@ pub async fn setup_no_code(
                     .join(", ")
             );
 
-            let events =
-                process_events(project_path, &mut manifest, postgres, &network_providers).await?;
+            let events = process_events(
+                project_path,
+                &mut manifest,
+                postgres,
+                sqlite,
+                clickhouse,
+                &network_providers,
+            )
+            .await?;
 
             let registry = EventCallbackRegistry { events };
             info!(
@@ -115,7 +191,12 @@ pub async fn setup_no_code(
 
             Ok(StartDetails {
                 manifest_path: details.manifest_path,
-                indexing_details: Some(IndexingDetails { registry }),
+                indexing_details: Some(IndexingDetails {
+                    registry,
+                    allow_destructive_migrations: details
+                        .indexing_details
+                        .allow_destructive_migrations,
+                }),
                 graphql_details: details.graphql_details,
             })
         }
@@ -131,13 +212,335 @@ struct NoCodeCallbackParams {
     event: Event,
     index_event_in_order: bool,
     csv: Option<Arc<AsyncCsvAppender>>,
+    parquet: Option<Arc<AsyncParquetAppender>>,
+    ndjson: Option<Arc<AsyncNdjsonAppender>>,
     postgres: Option<Arc<PostgresClient>>,
     postgres_event_table_name: String,
-    postgres_column_names: Vec<String>,
+    sqlite: Option<Arc<SqliteClient>>,
+    sqlite_event_table_name: String,
+    clickhouse: Option<Arc<ClickhouseClient>>,
+    clickhouse_event_table_name: String,
+    column_names: Vec<String>,
+    storage_event: StorageEvent,
+    providers: HashMap<String, Arc<JsonRpcCachedProvider>>,
+    computed_columns: Vec<(ComputedColumn, Expression)>,
+    entity_rules: Vec<EntityRuleStatement>,
+    rollups: Vec<RollupStatement>,
+    script: Option<Arc<ScriptHandler>>,
+    transform: Option<Arc<TransformHook>>,
+    /// Schema of the contract where rollup and script tables live
+    contract_schema_name: String,
     streams_clients: Arc<Option<StreamsClients>>,
     chat_clients: Arc<Option<ChatClients>>,
 }
 
+/// The formatted values of the event in the order of their columns, a value is null when the
+/// decimals of its token could not be read so one bad token does not stop indexing
+async fn format_event_inputs(
+    params: &NoCodeCallbackParams,
+    log_params: &[LogParam],
+    contract_address: Address,
+    network: &str,
+) -> Vec<(String, EthereumSqlTypeWrapper)> {
+    let mut formatted = vec![];
+
+    // the log params are in input order, unnamed inputs only have a name in the event info
+    let param_value = |name: &str| {
+        params
+            .event_info
+            .inputs
+            .iter()
+            .position(|input| input.name == name)
+            .and_then(|index| log_params.get(index))
+            .map(|param| &param.value)
+    };
+
+    for (input, input_format) in params.storage_event.formatted_inputs(&params.event_info.inputs) {
+        let token = param_value(&input.name);
+
+        let decimals = match (input_format.decimals, input_format.decimals_from.as_deref()) {
+            (Some(decimals), _) => Some(decimals),
+            (None, Some(decimals_from)) => {
+                let token_address = if decimals_from == "contract_address" {
+                    Some(contract_address)
+                } else {
+                    match param_value(decimals_from) {
+                        Some(Token::Address(address)) => Some(*address),
+                        _ => None,
+                    }
+                };
+
+                match (token_address, params.providers.get(network)) {
+                    (Some(token_address), Some(provider)) => {
+                        match provider.get_token_decimals(token_address).await {
+                            Ok(decimals) => Some(decimals),
+                            Err(e) => {
+                                warn!(
+                                    "{}::{} - Could not read decimals of {:?} to format {}: {}",
+                                    params.contract_name,
+                                    params.event_info.name,
+                                    token_address,
+                                    input.name,
+                                    e
+                                );
+                                None
+                            }
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            (None, None) => None,
+        };
+
+        let amount = token
+            .zip(decimals)
+            .and_then(|(token, decimals)| ScaledAmount::from_token(token, decimals));
+        formatted
+            .push((format!("{}_formatted", input.name), EthereumSqlTypeWrapper::Numeric(amount)));
+    }
+
+    formatted
+}
+
+/// The computed columns of the event with their parsed expressions
+pub(crate) fn parse_computed_columns(
+    storage_event: &StorageEvent,
+) -> Vec<(ComputedColumn, Expression)> {
+    // expressions are validated with the manifest
+    storage_event
+        .computed_columns()
+        .iter()
+        .filter_map(|column| {
+            parse_expression(&column.expression, None)
+                .ok()
+                .map(|expression| (column.clone(), expression))
+        })
+        .collect()
+}
+
+/// Evaluates the computed columns in order against the event json, each result is added to the
+/// json so later expressions, conditions, streams, chat and ndjson can use it
+pub(crate) fn evaluate_computed_columns(
+    contract_name: &str,
+    event_name: &str,
+    computed_columns: &[(ComputedColumn, Expression)],
+    event_result: &mut Value,
+) -> Vec<EthereumSqlTypeWrapper> {
+    let mut computed_values = vec![];
+
+    for (column, expression) in computed_columns {
+        let value = match expression.evaluate(event_result) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                debug!(
+                    "{}::{} - Computed column {} did not evaluate: {}",
+                    contract_name, event_name, column.name, e
+                );
+                None
+            }
+        };
+
+        let wrapper = match column.type_ {
+            ComputedColumnType::Number => EthereumSqlTypeWrapper::Numeric(
+                value.and_then(|value| value.as_number()).and_then(|number| {
+                    U256::try_from(number.magnitude()).ok().map(|magnitude| ScaledAmount {
+                        value: magnitude,
+                        negative: number.is_negative(),
+                        decimals: column.decimals.unwrap_or(0),
+                    })
+                }),
+            ),
+            ComputedColumnType::Bool => EthereumSqlTypeWrapper::NullableBool(match value {
+                Some(ExpressionValue::Bool(value)) => Some(value),
+                _ => None,
+            }),
+            ComputedColumnType::String => {
+                EthereumSqlTypeWrapper::NullableString(value.and_then(|value| value.as_text()))
+            }
+        };
+
+        if let Value::Object(event_result) = event_result {
+            event_result.insert(column.name.clone(), map_ethereum_wrapper_to_json_value(&wrapper));
+        }
+        computed_values.push(wrapper);
+    }
+
+    computed_values
+}
+
+/// The columns of a decoded event, written as one row to every storage backend
+struct EventColumns {
+    address: Address,
+    transaction_hash: H256,
+    block_number: U64,
+    block_hash: H256,
+    network: String,
+    contract_address: EthereumSqlTypeWrapper,
+    event_parameters: Vec<EthereumSqlTypeWrapper>,
+    formatted_values: Vec<(String, EthereumSqlTypeWrapper)>,
+    computed_values: Vec<EthereumSqlTypeWrapper>,
+    raw_log_values: Vec<EthereumSqlTypeWrapper>,
+    end_global_parameters: Vec<EthereumSqlTypeWrapper>,
+    log_index: U256,
+    /// A script or transform replaced the event so its values no longer match the log
+    replaced: bool,
+}
+
+/// Maps an event replaced by a script or transform back onto the columns of the event table. The
+/// event must keep every input, formatted and computed key with a value of the column type and can
+/// not add keys without a column, the transaction information can not be changed.
+fn map_event_json_to_columns(
+    inputs: &[ABIInput],
+    computed_columns: &[(ComputedColumn, Expression)],
+    event_result: &Value,
+    columns: &mut EventColumns,
+) -> Result<(), String> {
+    let event = event_result.as_object().ok_or("the event must be an object")?;
+
+    let is_column = |key: &String| {
+        key == "transaction_information" ||
+            inputs.iter().any(|input| &input.name == key) ||
+            columns.formatted_values.iter().any(|(name, _)| name == key) ||
+            computed_columns.iter().any(|(column, _)| &column.name == key)
+    };
+    if let Some(key) = event.keys().find(|key| !is_column(key)) {
+        return Err(format!("`{}` has no column, declare a computed column to store it", key));
+    }
+
+    let read = |name: &str, wrapper: &EthereumSqlTypeWrapper| {
+        let value = event.get(name).ok_or_else(|| format!("`{}` is missing", name))?;
+        map_json_value_to_ethereum_wrapper(wrapper, value)
+            .ok_or_else(|| format!("`{}` can not be stored in its column", name))
+    };
+
+    let event_parameters = map_json_to_ethereum_wrappers(inputs, &columns.event_parameters, event)?;
+    let formatted_values = columns
+        .formatted_values
+        .iter()
+        .map(|(name, wrapper)| Ok((name.clone(), read(name, wrapper)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let computed_values = computed_columns
+        .iter()
+        .zip(&columns.computed_values)
+        .map(|((column, _), wrapper)| read(&column.name, wrapper))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    columns.event_parameters = event_parameters;
+    columns.formatted_values = formatted_values;
+    columns.computed_values = computed_values;
+    columns.replaced = true;
+
+    Ok(())
+}
+
+/// The csv value of an event input which was replaced by a script or transform, written like the
+/// raw log values
+fn map_wrapper_to_csv_value(wrapper: &EthereumSqlTypeWrapper) -> String {
+    fn csv_value(value: &Value) -> String {
+        match value {
+            Value::String(value) => value.clone(),
+            Value::Array(values) => {
+                format!("[{}]", values.iter().map(csv_value).collect::<Vec<_>>().join(", "))
+            }
+            value => value.to_string(),
+        }
+    }
+
+    match wrapper {
+        EthereumSqlTypeWrapper::JSONB(json) => json.to_string(),
+        wrapper => csv_value(&map_ethereum_wrapper_to_typed_json_value(wrapper)),
+    }
+}
+
+/// Pushes the database and csv rows of an event unless the storage conditions filter it out,
+/// returns whether the event is stored
+fn push_event_rows(
+    storage_event: &StorageEvent,
+    csv: bool,
+    log_params: &[LogParam],
+    event_result: Option<&Value>,
+    columns: EventColumns,
+    bulk_data: &mut Vec<Vec<EthereumSqlTypeWrapper>>,
+    csv_bulk_data: &mut Vec<Vec<String>>,
+) -> bool {
+    // storage conditions only decide what is written to the storage, streams and chat apply
+    // their own conditions
+    if let (Some(conditions), Some(event_result)) = (&storage_event.conditions, event_result) {
+        if !filter_event_data_by_conditions(event_result, conditions) {
+            return false;
+        }
+    }
+
+    if csv {
+        let mut csv_data: Vec<String> = vec![format!("{:?}", columns.address)];
+
+        let raw_values = if columns.replaced {
+            columns.event_parameters.iter().map(map_wrapper_to_csv_value).collect()
+        } else if storage_event.is_tuples_as_jsonb() {
+            // tuples are a single json column so each log param maps to one wrapper
+            log_params
+                .iter()
+                .zip(&columns.event_parameters)
+                .flat_map(|(param, wrapper)| match wrapper {
+                    EthereumSqlTypeWrapper::JSONB(json) => vec![json.to_string()],
+                    _ => map_log_params_to_raw_values(std::slice::from_ref(param)),
+                })
+                .collect()
+        } else {
+            map_log_params_to_raw_values(log_params)
+        };
+
+        for param in raw_values {
+            csv_data.push(param);
+        }
+
+        for (_, wrapper) in &columns.formatted_values {
+            csv_data.push(match wrapper {
+                EthereumSqlTypeWrapper::Numeric(Some(amount)) => amount.to_decimal_string(),
+                _ => String::new(),
+            });
+        }
+
+        for wrapper in &columns.computed_values {
+            csv_data.push(match wrapper {
+                EthereumSqlTypeWrapper::Numeric(Some(amount)) => amount.to_decimal_string(),
+                EthereumSqlTypeWrapper::NullableBool(Some(value)) => value.to_string(),
+                EthereumSqlTypeWrapper::NullableString(Some(value)) => value.clone(),
+                _ => String::new(),
+            });
+        }
+
+        for wrapper in &columns.raw_log_values {
+            csv_data.push(match wrapper {
+                EthereumSqlTypeWrapper::RawTopics(topics) => {
+                    topics.iter().map(|topic| format!("{:?}", topic)).collect::<Vec<_>>().join(",")
+                }
+                EthereumSqlTypeWrapper::Bytes(data) => format!("0x{}", hex::encode(data)),
+                _ => String::new(),
+            });
+        }
+
+        csv_data.push(format!("{:?}", columns.transaction_hash));
+        csv_data.push(format!("{:?}", columns.block_number));
+        csv_data.push(format!("{:?}", columns.block_hash));
+        csv_data.push(columns.network);
+
+        csv_bulk_data.push(csv_data);
+    }
+
+    let mut all_params: Vec<EthereumSqlTypeWrapper> = vec![columns.contract_address];
+    all_params.extend(columns.event_parameters);
+    all_params.extend(columns.formatted_values.into_iter().map(|(_, wrapper)| wrapper));
+    all_params.extend(columns.computed_values);
+    all_params.extend(columns.raw_log_values);
+    all_params.extend(columns.end_global_parameters);
+
+    bulk_data.push(all_params);
+
+    true
+}
+
 fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
     Arc::new(move |results| {
         let params = Arc::clone(&params);
@@ -166,10 +569,22 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
 
             let network = results.first().unwrap().tx_information.network.clone();
 
+            if let Some(script) = &params.script {
+                script.reload_if_changed();
+            }
+
             let mut indexed_count = 0;
-            let mut postgres_bulk_data: Vec<Vec<EthereumSqlTypeWrapper>> = Vec::new();
-            let mut postgres_bulk_column_types: Vec<PgType> = Vec::new();
+            let mut filtered_out_count = 0;
+            let mut bulk_data: Vec<Vec<EthereumSqlTypeWrapper>> = Vec::new();
             let mut csv_bulk_data: Vec<Vec<String>> = Vec::new();
+            let mut ndjson_bulk_data: Vec<Value> = Vec::new();
+            let mut statements: Vec<(String, Vec<Box<dyn ToSql + Send + Sync>>)> =
+                Vec::new();
+            let mut rollup_rows: Vec<Vec<(U64, U256, Vec<String>)>> =
+                vec![vec![]; params.rollups.len()];
+            // the events applied to each entity and rollup table, recorded with them so a retry
+            // of the batch does not apply them again
+            let mut applied_events: BTreeMap<String, Vec<(U64, U256)>> = BTreeMap::new();
 
             // stream and chat info
             let mut event_message_data: Vec<Value> = Vec::new();
@@ -189,7 +604,11 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                     let log_index = result.tx_information.log_index;
 
                     let event_parameters: Vec<EthereumSqlTypeWrapper> =
-                        map_log_params_to_ethereum_wrapper(&params.event_info.inputs, &log.params);
+                        map_log_params_to_ethereum_wrapper(
+                            &params.event_info.inputs,
+                            &log.params,
+                            params.storage_event.is_tuples_as_jsonb(),
+                        );
 
                     let contract_address = EthereumSqlTypeWrapper::Address(address);
                     let end_global_parameters = vec![
@@ -201,6 +620,15 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                         EthereumSqlTypeWrapper::U256(log_index),
                     ];
 
+                    let raw_log_values = if params.storage_event.store_raw_log {
+                        vec![
+                            EthereumSqlTypeWrapper::RawTopics(result.log.topics.clone()),
+                            EthereumSqlTypeWrapper::Bytes(result.log.data.clone()),
+                        ]
+                    } else {
+                        vec![]
+                    };
+
                     Some((
                         log.params,
                         address,
@@ -212,11 +640,21 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                         network,
                         contract_address,
                         event_parameters,
+                        raw_log_values,
                         end_global_parameters,
                     ))
                 })
                 .collect();
 
+            let publish_event = params.streams_clients.is_some() || params.chat_clients.is_some();
+            let store_columns = params.postgres.is_some() ||
+                params.sqlite.is_some() ||
+                params.clickhouse.is_some() ||
+                params.csv.is_some() ||
+                params.parquet.is_some();
+
+            // the events are prepared first so the transform endpoint gets the whole batch
+            let mut prepared_results = Vec::with_capacity(owned_results.len());
             for (
                 log_params,
                 address,
@@ -228,11 +666,21 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                 network,
                 contract_address,
                 event_parameters,
+                raw_log_values,
                 end_global_parameters,
             ) in owned_results
             {
-                if params.streams_clients.is_some() || params.chat_clients.is_some() {
-                    let event_result = map_ethereum_wrapper_to_json(
+                let formatted_values =
+                    format_event_inputs(&params, &log_params, address, &network).await;
+
+                let event_result = if publish_event ||
+                    params.storage_event.conditions.is_some() ||
+                    params.ndjson.is_some() ||
+                    !params.computed_columns.is_empty() ||
+                    params.script.is_some() ||
+                    params.transform.is_some()
+                {
+                    let mut event_result = map_ethereum_wrapper_to_json(
                         &params.event_info.inputs,
                         &event_parameters,
                         &TxInformation {
@@ -246,52 +694,388 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                         },
                         false,
                     );
-                    event_message_data.push(event_result);
-                }
+                    if let Value::Object(event_result) = &mut event_result {
+                        for (name, wrapper) in &formatted_values {
+                            event_result
+                                .insert(name.clone(), map_ethereum_wrapper_to_json_value(wrapper));
+                        }
+                    }
+                    Some(event_result)
+                } else {
+                    None
+                };
+
+                let (computed_values, event_result) = match event_result {
+                    Some(mut event_result) => {
+                        let computed_values = evaluate_computed_columns(
+                            &params.contract_name,
+                            &params.event_info.name,
+                            &params.computed_columns,
+                            &mut event_result,
+                        );
+                        (computed_values, Some(event_result))
+                    }
+                    None => (vec![], None),
+                };
+
+                // the script sees the computed columns and can drop the event before the storage
+                // conditions are checked
+                let mut columns = EventColumns {
+                    address,
+                    transaction_hash,
+                    block_number,
+                    block_hash,
+                    network,
+                    contract_address,
+                    event_parameters,
+                    formatted_values,
+                    computed_values,
+                    raw_log_values,
+                    end_global_parameters,
+                    log_index,
+                    replaced: false,
+                };
+                let network = columns.network.clone();
+
+                let event_result = match (&params.script, event_result) {
+                    (Some(script), Some(event_result)) => {
+                        // scripts are synchronous so they run off the async runtime
+                        let handled = {
+                            let script = Arc::clone(script);
+                            let event_result = event_result.clone();
+                            tokio::task::spawn_blocking(move || script.handle(&event_result)).await
+                        };
+                        let output = match handled {
+                            Ok(Ok(output)) => output,
+                            Ok(Err(e)) => {
+                                error!(
+                                    "{}::{} - Error running script: {}",
+                                    params.contract_name, params.event_info.name, e
+                                );
+                                return Err(e.to_string());
+                            }
+                            Err(e) => {
+                                error!(
+                                    "{}::{} - Script did not finish: {}",
+                                    params.contract_name, params.event_info.name, e
+                                );
+                                return Err(e.to_string());
+                            }
+                        };
+
+                        // emitted rows are written whatever the script returns for the event
+                        for (table_name, row) in &output.rows {
+                            let table = params
+                                .storage_event
+                                .script
+                                .as_ref()
+                                .and_then(|script| script.find_table(table_name));
+                            let statement = match table {
+                                Some(table) => generate_script_row_insert(
+                                    &params.contract_schema_name,
+                                    table,
+                                    row,
+                                    &network,
+                                    block_number,
+                                    transaction_hash,
+                                ),
+                                None => Err(format!(
+                                    "table {} is not defined for the script",
+                                    table_name
+                                )),
+                            };
+                            match statement {
+                                Ok(statement) => statements.push(statement),
+                                Err(e) => warn!(
+                                    "{}::{} - script row can not be stored, {} in tx {:?}",
+                                    params.contract_name,
+                                    params.event_info.name,
+                                    e,
+                                    transaction_hash
+                                ),
+                            }
+                        }
+
+                        if publish_event {
+                            event_message_data.extend(output.published);
+                        }
+
+                        match output.event {
+                            Some(replaced) => {
+                                if store_columns && replaced != event_result {
+                                    if let Err(e) = map_event_json_to_columns(
+                                        &params.event_info.inputs,
+                                        &params.computed_columns,
+                                        &replaced,
+                                        &mut columns,
+                                    ) {
+                                        error!(
+                                            "{}::{} - Script replaced the event in tx {:?} with one which can not be stored: {}",
+                                            params.contract_name,
+                                            params.event_info.name,
+                                            transaction_hash,
+                                            e
+                                        );
+                                        return Err(e);
+                                    }
+                                }
+                                Some(replaced)
+                            }
+                            None => {
+                                filtered_out_count += 1;
+                                continue;
+                            }
+                        }
+                    }
+                    (_, event_result) => event_result,
+                };
+
+                prepared_results.push((log_params, columns, event_result));
+            }
 
-                let mut all_params: Vec<EthereumSqlTypeWrapper> = vec![contract_address];
-                all_params.extend(event_parameters);
-                all_params.extend(end_global_parameters);
+            if let Some(transform) = &params.transform {
+                let events: Vec<Value> = prepared_results
+                    .iter()
+                    .filter_map(|(_, _, event_result)| event_result.clone())
+                    .collect();
 
-                // Set column types dynamically based on first result
-                if postgres_bulk_column_types.is_empty() {
-                    postgres_bulk_column_types =
-                        all_params.iter().map(|param| param.to_type()).collect();
+                if !events.is_empty() {
+                    match transform
+                        .transform(&params.contract_name, &params.event_info.name, &network, events)
+                        .await
+                    {
+                        Ok(transformed) => {
+                            let mut kept = Vec::with_capacity(prepared_results.len());
+                            for ((log_params, mut columns, event_result), transformed) in
+                                prepared_results.into_iter().zip(transformed)
+                            {
+                                let Some(transformed) = transformed else {
+                                    filtered_out_count += 1;
+                                    continue;
+                                };
+
+                                if store_columns && Some(&transformed) != event_result.as_ref() {
+                                    if let Err(e) = map_event_json_to_columns(
+                                        &params.event_info.inputs,
+                                        &params.computed_columns,
+                                        &transformed,
+                                        &mut columns,
+                                    ) {
+                                        error!(
+                                            "{}::{} - Transform answered with an event in tx {:?} which can not be stored: {}",
+                                            params.contract_name,
+                                            params.event_info.name,
+                                            columns.transaction_hash,
+                                            e
+                                        );
+                                        return Err(e);
+                                    }
+                                }
+
+                                kept.push((log_params, columns, Some(transformed)));
+                            }
+                            prepared_results = kept;
+                        }
+                        Err(e) => {
+                            // nothing is stored untransformed, the batch is retried with a backoff
+                            // and the event does not progress until the transform succeeds
+                            error!(
+                                "{}::{} - Transform failed, the batch is not indexed: {}",
+                                params.contract_name, params.event_info.name, e
+                            );
+                            return Err(e.to_string());
+                        }
+                    }
                 }
+            }
+
+            for (log_params, columns, event_result) in prepared_results {
+                let (address, transaction_hash, block_number, log_index, replaced) = (
+                    columns.address,
+                    columns.transaction_hash,
+                    columns.block_number,
+                    columns.log_index,
+                    columns.replaced,
+                );
 
-                postgres_bulk_data.push(all_params);
+                let store_event = push_event_rows(
+                    &params.storage_event,
+                    params.csv.is_some(),
+                    &log_params,
+                    event_result.as_ref(),
+                    columns,
+                    &mut bulk_data,
+                    &mut csv_bulk_data,
+                );
 
-                if params.csv.is_some() {
-                    let mut csv_data: Vec<String> = vec![format!("{:?}", address)];
+                // entities and rollups read the values of an event a script or transform replaced
+                let log_params = match &event_result {
+                    Some(Value::Object(event)) if replaced &&
+                        store_event &&
+                        (!params.entity_rules.is_empty() || !params.rollups.is_empty()) => {
+                        match map_json_to_log_params(&params.event_info.inputs, event) {
+                            Ok(log_params) => log_params,
+                            Err(e) => {
+                                error!(
+                                    "{}::{} - The replaced event in tx {:?} can not be read by the entities and rollups: {}",
+                                    params.contract_name, params.event_info.name, transaction_hash, e
+                                );
+                                return Err(e);
+                            }
+                        }
+                    }
+                    _ => log_params,
+                };
 
-                    let raw_values = map_log_params_to_raw_values(&log_params);
+                if store_event && params.ndjson.is_some() {
+                    if let Some(event_result) = &event_result {
+                        ndjson_bulk_data.push(event_result.clone());
+                    }
+                }
 
-                    for param in raw_values {
-                        csv_data.push(param);
+                if publish_event {
+                    if let Some(event_result) = event_result {
+                        event_message_data.push(event_result);
                     }
+                }
 
-                    csv_data.push(format!("{:?}", transaction_hash));
-                    csv_data.push(format!("{:?}", block_number));
-                    csv_data.push(format!("{:?}", block_hash));
-                    csv_data.push(network);
+                if !store_event {
+                    filtered_out_count += 1;
+                    continue;
+                }
+
+                for entity_rule in &params.entity_rules {
+                    match entity_rule.params(
+                        &params.event_info.inputs,
+                        &log_params,
+                        address,
+                        &network,
+                        block_number,
+                        log_index,
+                    ) {
+                        Some(rule_params) => {
+                            statements.push((entity_rule.sql().to_string(), rule_params));
+                            applied_events
+                                .entry(entity_rule.table_name.clone())
+                                .or_default()
+                                .push((block_number, log_index));
+                        }
+                        None => warn!(
+                            "{}::{} - could not read the values of a rule of entity {} in tx {:?}",
+                            params.contract_name,
+                            params.event_info.name,
+                            entity_rule.entity_name,
+                            transaction_hash
+                        ),
+                    }
+                }
 
-                    csv_bulk_data.push(csv_data);
+                for (rollup, rows) in params.rollups.iter().zip(&mut rollup_rows) {
+                    match rollup.row(&params.event_info.inputs, &log_params, address) {
+                        Some(row) => rows.push((block_number, log_index, row)),
+                        None => warn!(
+                            "{}::{} - could not read the values of rollup {} in tx {:?}",
+                            params.contract_name,
+                            params.event_info.name,
+                            rollup.rollup_name,
+                            transaction_hash
+                        ),
+                    }
                 }
 
                 indexed_count += 1;
             }
 
+            // Set column types dynamically based on first result
+            let postgres_bulk_column_types: Vec<PgType> = bulk_data
+                .first()
+                .map(|row| row.iter().map(|param| param.to_type()).collect())
+                .unwrap_or_default();
+
+            if filtered_out_count > 0 {
+                debug!(
+                    "{}::{} - {} events were filtered out by the storage conditions, script or transform and will not be stored",
+                    params.contract_name, params.event_info.name, filtered_out_count
+                );
+            }
+
             if let Some(postgres) = &params.postgres {
-                let bulk_data_length = postgres_bulk_data.len();
-                if bulk_data_length > 0 {
+                let bulk_data_length = bulk_data.len();
+
+                if rollup_rows.iter().any(|rows| !rows.is_empty()) {
+                    let block_numbers: Vec<U64> =
+                        rollup_rows.iter().flatten().map(|(block_number, ..)| *block_number).collect();
+                    let block_timestamps = match params.providers.get(&network) {
+                        Some(provider) => provider.get_block_timestamps(&block_numbers).await,
+                        None => Err(ProviderError::CustomError(format!(
+                            "No provider for network {}",
+                            network
+                        ))),
+                    };
+                    let block_timestamps = match block_timestamps {
+                        Ok(block_timestamps) => block_timestamps,
+                        Err(e) => {
+                            error!(
+                                "{}::{} - Error reading block timestamps for rollups: {}",
+                                params.contract_name, params.event_info.name, e
+                            );
+                            return Err(e.to_string());
+                        }
+                    };
+
+                    for (rollup, rows) in params.rollups.iter().zip(rollup_rows) {
+                        if rows.is_empty() {
+                            continue;
+                        }
+                        applied_events.entry(rollup.table_name.clone()).or_default().extend(
+                            rows.iter().map(|(block_number, log_index, _)| (*block_number, *log_index)),
+                        );
+                        let rows: Vec<(U256, U64, U256, Vec<String>)> = rows
+                            .into_iter()
+                            .map(|(block_number, log_index, row)| {
+                                (block_timestamps[&block_number], block_number, log_index, row)
+                            })
+                            .collect();
+                        statements.push((rollup.sql().to_string(), rollup.params(&network, &rows)));
+                    }
+                }
+
+                // after the statements applying the events so they still see them as not applied
+                for (table_name, events) in &applied_events {
+                    statements.push(applied_events_statement(
+                        &params.indexer_name,
+                        table_name,
+                        &network,
+                        events,
+                    ));
+                }
+
+                if !statements.is_empty() {
+                    if let Err(e) = postgres
+                        .bulk_insert_with_statements(
+                            &params.postgres_event_table_name,
+                            &params.column_names,
+                            &postgres_bulk_column_types,
+                            &bulk_data,
+                            &statements,
+                        )
+                        .await
+                    {
+                        error!(
+                            "{}::{} - Error performing bulk insert with entities and rollups: {}",
+                            params.contract_name, params.event_info.name, e
+                        );
+                        return Err(e.to_string());
+                    }
+                } else if bulk_data_length > 0 {
                     // anything over 100 events is considered bulk and goes the COPY route
                     if bulk_data_length > 100 {
                         if let Err(e) = postgres
                             .bulk_insert_via_copy(
                                 &params.postgres_event_table_name,
-                                &params.postgres_column_names,
+                                &params.column_names,
                                 &postgres_bulk_column_types,
-                                &postgres_bulk_data,
+                                &bulk_data,
                             )
                             .await
                         {
@@ -304,8 +1088,8 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                     } else if let Err(e) = postgres
                         .bulk_insert(
                             &params.postgres_event_table_name,
-                            &params.postgres_column_names,
-                            &postgres_bulk_data,
+                            &params.column_names,
+                            &bulk_data,
                         )
                         .await
                     {
@@ -318,9 +1102,84 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                 }
             }
 
+            if let Some(sqlite) = &params.sqlite {
+                if !bulk_data.is_empty() {
+                    if let Err(e) = sqlite
+                        .bulk_insert(
+                            &params.sqlite_event_table_name,
+                            &params.column_names,
+                            &bulk_data,
+                        )
+                        .await
+                    {
+                        error!(
+                            "{}::{} - Error performing sqlite bulk insert: {}",
+                            params.contract_name, params.event_info.name, e
+                        );
+                        return Err(e.to_string());
+                    }
+                }
+            }
+
+            if let Some(clickhouse) = &params.clickhouse {
+                if !bulk_data.is_empty() {
+                    if let Err(e) = clickhouse
+                        .bulk_insert(
+                            &params.clickhouse_event_table_name,
+                            &params.column_names,
+                            &bulk_data,
+                        )
+                        .await
+                    {
+                        error!(
+                            "{}::{} - Error performing clickhouse bulk insert: {}",
+                            params.contract_name, params.event_info.name, e
+                        );
+                        return Err(e.to_string());
+                    }
+                }
+            }
+
             if let Some(csv) = &params.csv {
                 if !csv_bulk_data.is_empty() {
-                    if let Err(e) = csv.append_bulk(csv_bulk_data).await {
+                    if let Err(e) = csv
+                        .append_bulk_in_block_range(
+                            network.clone(),
+                            from_block,
+                            to_block,
+                            csv_bulk_data,
+                        )
+                        .await
+                    {
+                        return Err(e.to_string());
+                    }
+                }
+            }
+
+            if let Some(parquet) = &params.parquet {
+                if !bulk_data.is_empty() {
+                    if let Err(e) =
+                        parquet.append_bulk(network.clone(), from_block, to_block, bulk_data).await
+                    {
+                        error!(
+                            "{}::{} - Error writing parquet file: {}",
+                            params.contract_name, params.event_info.name, e
+                        );
+                        return Err(e.to_string());
+                    }
+                }
+            }
+
+            if let Some(ndjson) = &params.ndjson {
+                if !ndjson_bulk_data.is_empty() {
+                    if let Err(e) = ndjson
+                        .append_bulk(network.clone(), from_block, to_block, ndjson_bulk_data)
+                        .await
+                    {
+                        error!(
+                            "{}::{} - Error writing ndjson file: {}",
+                            params.contract_name, params.event_info.name, e
+                        );
                         return Err(e.to_string());
                     }
                 }
@@ -438,6 +1297,12 @@ pub enum ProcessIndexersError {
     #[error("Could not append headers to csv: {0}")]
     CsvHeadersAppendError(#[from] csv::Error),
 
+    #[error("Could not create parquet file: {0}")]
+    ParquetAppenderError(#[from] ParquetAppenderError),
+
+    #[error("Could not create ndjson file: {0}")]
+    NdjsonAppenderError(#[from] NdjsonAppenderError),
+
     #[error("{0}")]
     CreateContractInformationError(#[from] CreateContractInformationError),
 
@@ -449,12 +1314,17 @@ pub enum ProcessIndexersError {
 
     #[error("Event name not found in ABI for contract: {0} - event: {1}")]
     EventNameNotFoundInAbi(String, String),
+
+    #[error("{0}")]
+    ScriptError(#[from] ScriptError),
 }
 
 pub async fn process_events(
     project_path: &Path,
     manifest: &mut Manifest,
     postgres: Option<Arc<PostgresClient>>,
+    sqlite: Option<Arc<SqliteClient>>,
+    clickhouse: Option<Arc<ClickhouseClient>>,
     network_providers: &[CreateNetworkProvider],
 ) -> Result<Vec<EventCallbackRegistryInformation>, ProcessIndexersError> {
     let mut events: Vec<EventCallbackRegistryInformation> = vec![];
@@ -502,25 +1372,95 @@ pub async fn process_events(
             let mut csv: Option<Arc<AsyncCsvAppender>> = None;
             if contract.generate_csv.unwrap_or(true) && manifest.storage.csv_enabled() {
                 let csv_path = manifest.storage.csv.as_ref().map_or("./generated_csv", |c| &c.path);
-                let headers: Vec<String> = event_info.csv_headers_for_event();
+                let headers: Vec<String> =
+                    event_info.csv_headers_for_event(&contract.storage_event(&event_name));
 
                 let csv_path =
                     event_info.create_csv_file_for_event(project_path, contract, csv_path)?;
-                let csv_appender = AsyncCsvAppender::new(&csv_path);
-                if !Path::new(&csv_path).exists() {
-                    csv_appender.append_header(headers).await?;
-                }
+                let settings = manifest.storage.csv.as_ref().map(|details| CsvFileSettings {
+                    delimiter: details.delimiter.map_or(b',', |delimiter| delimiter as u8),
+                    quote_style: match details.quote_style {
+                        Some(CsvQuoteStyle::Always) => QuoteStyle::Always,
+                        Some(CsvQuoteStyle::NonNumeric) => QuoteStyle::NonNumeric,
+                        Some(CsvQuoteStyle::Never) => QuoteStyle::Never,
+                        Some(CsvQuoteStyle::Necessary) | None => QuoteStyle::Necessary,
+                    },
+                    // written to every new file so rotated files keep their headers
+                    header: Some(headers),
+                    max_blocks_per_file: details.max_blocks_per_file,
+                    max_file_size_bytes: details.max_file_size_mb.map(|mb| mb * 1024 * 1024),
+                    compression: details.compression.map(|compression| match compression {
+                        CsvCompression::Gzip => FileCompression::Gzip,
+                        CsvCompression::Zstd => FileCompression::Zstd,
+                    }),
+                    fsync: details.fsync.unwrap_or_default(),
+                });
+
+                csv = Some(Arc::new(AsyncCsvAppender::with_settings(
+                    &csv_path,
+                    settings.unwrap_or_default(),
+                )));
+            }
 
-                csv = Some(Arc::new(csv_appender));
+            let storage_event = contract.storage_event(&event_info.name);
+
+            let mut parquet: Option<Arc<AsyncParquetAppender>> = None;
+            if let Some(parquet_details) = manifest.storage.parquet.as_ref().filter(|p| p.enabled) {
+                let settings = ParquetFileSettings {
+                    max_blocks_per_file: parquet_details.max_blocks_per_file(),
+                    max_file_size_bytes: parquet_details.max_file_size_bytes(),
+                    end_blocks: contract
+                        .details
+                        .iter()
+                        .filter_map(|details| {
+                            details.end_block.map(|end_block| (details.network.clone(), end_block))
+                        })
+                        .collect::<HashMap<_, _>>(),
+                };
+
+                parquet = Some(Arc::new(AsyncParquetAppender::new(
+                    &project_path.join(&parquet_details.path),
+                    &contract.name,
+                    &event_info.name,
+                    generate_parquet_event_schema(&event_info, &storage_event),
+                    settings,
+                )?));
             }
 
-            let postgres_column_names =
-                generate_column_names_only_with_base_properties(&event_info.inputs);
+            let column_names =
+                generate_column_names_only_with_base_properties(&event_info.inputs, &storage_event);
+            let event_table_name = storage_event.event_table_name();
             let postgres_event_table_name =
-                generate_event_table_full_name(&manifest.name, &contract.name, &event_info.name);
+                generate_event_table_full_name(&manifest.name, &contract.name, &event_table_name);
+            let sqlite_event_table_name =
+                generate_sqlite_event_table_name(&manifest.name, &contract.name, &event_table_name);
+            let clickhouse_event_table_name = generate_clickhouse_event_table_name(
+                &manifest.name,
+                &contract.name,
+                &event_table_name,
+            );
+
+            let mut ndjson: Option<Arc<AsyncNdjsonAppender>> = None;
+            if let Some(ndjson_details) = manifest.storage.ndjson.as_ref().filter(|n| n.enabled) {
+                let settings = NdjsonFileSettings {
+                    max_blocks_per_file: ndjson_details.max_blocks_per_file,
+                    max_file_size_bytes: ndjson_details.max_file_size_mb.map(|mb| mb * 1024 * 1024),
+                    gzip: ndjson_details.gzip.unwrap_or_default(),
+                };
+
+                ndjson = Some(Arc::new(
+                    AsyncNdjsonAppender::new(
+                        &project_path.join(&ndjson_details.path),
+                        &contract.name,
+                        &event_info.name,
+                        settings,
+                    )
+                    .map_err(NdjsonAppenderError::FileIo)?,
+                ));
+            }
 
             let streams_client = if let Some(streams) = &contract.streams {
-                Some(StreamsClients::new(streams.clone()).await)
+                Some(StreamsClients::new(streams.clone(), project_path).await)
             } else {
                 None
             };
@@ -536,6 +1476,32 @@ pub async fn process_events(
                 .as_ref()
                 .map_or(false, |vec| vec.contains(&event_info.name));
 
+            let computed_columns = parse_computed_columns(&storage_event);
+            let entity_rules = generate_entity_rule_statements(
+                &manifest.name,
+                manifest.entities.as_deref().unwrap_or_default(),
+                &contract.before_modify_name_if_filter_readonly(),
+                &event_info.name,
+            );
+            let contract_schema_name =
+                generate_indexer_contract_schema_name(&manifest.name, &contract.name);
+            let rollups = generate_rollup_statements(
+                &manifest.name,
+                &contract_schema_name,
+                &event_info.inputs,
+                &storage_event,
+            );
+            let script = match &storage_event.script {
+                Some(script) => {
+                    Some(Arc::new(ScriptHandler::new(&project_path.join(&script.path))?))
+                }
+                None => None,
+            };
+            let transform = storage_event
+                .transform
+                .clone()
+                .map(|transform| Arc::new(TransformHook::new(transform)));
+
             let event = EventCallbackRegistryInformation {
                 id: generate_random_id(10),
                 indexer_name: manifest.name.clone(),
@@ -550,9 +1516,28 @@ pub async fn process_events(
                     event: event.clone(),
                     index_event_in_order,
                     csv,
+                    parquet,
+                    ndjson,
                     postgres: postgres.clone(),
                     postgres_event_table_name,
-                    postgres_column_names,
+                    sqlite: sqlite.clone(),
+                    sqlite_event_table_name,
+                    clickhouse: clickhouse.clone(),
+                    clickhouse_event_table_name,
+                    column_names,
+                    storage_event,
+                    providers: network_providers
+                        .iter()
+                        .map(|provider| {
+                            (provider.network_name.clone(), Arc::clone(&provider.client))
+                        })
+                        .collect(),
+                    computed_columns,
+                    entity_rules,
+                    rollups,
+                    script,
+                    transform,
+                    contract_schema_name,
                     streams_clients: Arc::new(streams_client),
                     chat_clients: Arc::new(chat_clients),
                 })),
@@ -564,3 +1549,166 @@ pub async fn process_events(
 
     Ok(events)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn transfer_columns(value: u64) -> (Vec<LogParam>, EventColumns) {
+        let log_params =
+            vec![LogParam { name: "value".to_string(), value: Token::Uint(value.into()) }];
+        let columns = EventColumns {
+            address: Address::zero(),
+            transaction_hash: H256::zero(),
+            block_number: U64::from(1),
+            block_hash: H256::zero(),
+            network: "ethereum".to_string(),
+            contract_address: EthereumSqlTypeWrapper::Address(Address::zero()),
+            event_parameters: vec![EthereumSqlTypeWrapper::U256(value.into())],
+            formatted_values: vec![],
+            computed_values: vec![],
+            raw_log_values: vec![],
+            end_global_parameters: vec![EthereumSqlTypeWrapper::U64(U64::from(1))],
+            log_index: U256::zero(),
+            replaced: false,
+        };
+        (log_params, columns)
+    }
+
+    #[test]
+    fn test_push_event_rows_skips_filtered_events() {
+        let storage_event: StorageEvent = serde_yaml::from_str(
+            r#"
+event_name: Transfer
+conditions:
+  - "value": ">=1000"
+"#,
+        )
+        .unwrap();
+        let mut bulk_data = vec![];
+        let mut csv_bulk_data = vec![];
+
+        let (log_params, columns) = transfer_columns(10);
+        assert!(!push_event_rows(
+            &storage_event,
+            true,
+            &log_params,
+            Some(&json!({ "value": "10" })),
+            columns,
+            &mut bulk_data,
+            &mut csv_bulk_data,
+        ));
+        assert!(bulk_data.is_empty());
+        assert!(csv_bulk_data.is_empty());
+
+        let (log_params, columns) = transfer_columns(5000);
+        assert!(push_event_rows(
+            &storage_event,
+            true,
+            &log_params,
+            Some(&json!({ "value": "5000" })),
+            columns,
+            &mut bulk_data,
+            &mut csv_bulk_data,
+        ));
+        assert_eq!(bulk_data.len(), 1);
+        assert_eq!(csv_bulk_data.len(), 1);
+        assert_eq!(csv_bulk_data[0][1], "5000");
+    }
+
+    #[test]
+    fn test_replaced_event_is_mapped_to_columns() {
+        let storage_event: StorageEvent = serde_yaml::from_str(
+            r#"
+event_name: Transfer
+computed_columns:
+  - name: is_whale
+    expression: "null"
+    type: bool
+"#,
+        )
+        .unwrap();
+        let computed_columns = parse_computed_columns(&storage_event);
+        let inputs = vec![ABIInput {
+            indexed: None,
+            name: "value".to_string(),
+            type_: "uint256".to_string(),
+            components: None,
+        }];
+
+        let (log_params, mut columns) = transfer_columns(10);
+        columns.computed_values = vec![EthereumSqlTypeWrapper::NullableBool(None)];
+        map_event_json_to_columns(
+            &inputs,
+            &computed_columns,
+            &json!({ "value": "5000", "is_whale": true, "transaction_information": {} }),
+            &mut columns,
+        )
+        .unwrap();
+
+        let mut bulk_data = vec![];
+        let mut csv_bulk_data = vec![];
+        assert!(push_event_rows(
+            &storage_event,
+            true,
+            &log_params,
+            None,
+            columns,
+            &mut bulk_data,
+            &mut csv_bulk_data,
+        ));
+        assert_eq!(csv_bulk_data[0][1], "5000");
+        assert_eq!(csv_bulk_data[0][2], "true");
+        assert!(
+            matches!(bulk_data[0][1], EthereumSqlTypeWrapper::U256(value) if value == 5000.into())
+        );
+
+        let (_, mut columns) = transfer_columns(10);
+        columns.computed_values = vec![EthereumSqlTypeWrapper::NullableBool(None)];
+        for (event, error) in [
+            (json!({ "value": "10", "is_whale": null, "usd": 1 }), "`usd` has no column"),
+            (json!({ "is_whale": null }), "`value` is missing"),
+            (json!({ "value": "-1", "is_whale": null }), "`value` can not be stored"),
+            (json!({ "value": "10", "is_whale": "yes" }), "`is_whale` can not be stored"),
+        ] {
+            let e = map_event_json_to_columns(&inputs, &computed_columns, &event, &mut columns)
+                .unwrap_err();
+            assert!(e.starts_with(error), "{}", e);
+        }
+        assert!(!columns.replaced);
+    }
+
+    #[test]
+    fn test_rollups_read_the_transformed_event() {
+        let storage_event: StorageEvent = serde_yaml::from_str(
+            r#"
+event_name: Transfer
+rollups:
+  - name: daily_volume
+    interval: day
+    aggregates:
+      - name: volume
+        function: sum
+        input: value
+"#,
+        )
+        .unwrap();
+        let inputs = vec![ABIInput {
+            indexed: None,
+            name: "value".to_string(),
+            type_: "uint256".to_string(),
+            components: None,
+        }];
+        let rollups =
+            generate_rollup_statements("MyIndexer", "my_indexer_token", &inputs, &storage_event);
+
+        let transformed = json!({ "value": "5000", "transaction_information": {} });
+        let log_params = map_json_to_log_params(&inputs, transformed.as_object().unwrap()).unwrap();
+        assert_eq!(
+            rollups[0].row(&inputs, &log_params, Address::zero()),
+            Some(vec!["5000".to_string()])
+        );
+    }
+}
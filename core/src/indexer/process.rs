@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_std::prelude::StreamExt;
 use ethers::{
@@ -6,13 +6,18 @@ use ethers::{
     types::{H256, U64},
 };
 use futures::future::join_all;
+use rand::Rng;
 use tokio::{
     sync::{Mutex, MutexGuard},
     task::{JoinError, JoinHandle},
+    time::sleep,
 };
 use tracing::{debug, error, info};
 
 use crate::{
+    database::postgres::{
+        generate::generate_event_table_full_name, partitions::ensure_block_partitions,
+    },
     event::{
         callback_registry::EventResult, config::EventProcessingConfig, BuildRindexerFilterError,
         RindexerEventFilter,
@@ -453,6 +458,41 @@ async fn handle_logs_result(
                 .collect::<Vec<_>>();
 
             if !fn_data.is_empty() {
+                // the partitions must exist before the handlers insert the events
+                if let (Some(database), Some(partitioning)) =
+                    (&config.database, &config.postgres_partitioning)
+                {
+                    let table_name = generate_event_table_full_name(
+                        &config.indexer_name,
+                        &config.contract_name,
+                        &config.event_table_name,
+                    );
+                    // retried like the handlers so a transient postgres error does not stop the
+                    // event
+                    let mut attempts = 0;
+                    let mut delay = Duration::from_millis(100);
+                    while let Err(e) = ensure_block_partitions(
+                        database,
+                        &config.indexer_name,
+                        &table_name,
+                        partitioning,
+                        result.from_block.as_u64(),
+                        result.to_block.as_u64(),
+                    )
+                    .await
+                    {
+                        attempts += 1;
+                        error!(
+                            "{} - Could not create the partitions of {}. Retrying... (attempt {}). Error: {}",
+                            config.info_log_name, table_name, attempts, e
+                        );
+
+                        delay = (delay * 2).min(Duration::from_secs(15));
+                        let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..1000));
+                        sleep(delay + jitter).await;
+                    }
+                }
+
                 if config.index_event_in_order {
                     config.trigger_event(fn_data).await;
                     update_progress_and_last_synced(config, result.to_block);
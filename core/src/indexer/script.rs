@@ -0,0 +1,282 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use rhai::{
+    module_resolvers::DummyModuleResolver,
+    serde::{from_dynamic, to_dynamic},
+    CallFnOptions, Dynamic, Engine, Scope, AST,
+};
+use serde_json::{Map, Value};
+use tracing::{debug, error, info};
+
+/// The function every script must define, called with the event and its transaction information
+const SCRIPT_HANDLE_FN: &str = "handle";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ScriptError {
+    #[error("Could not read script {0}: {1}")]
+    CouldNotRead(String, std::io::Error),
+
+    #[error("Could not compile script {0}: {1}")]
+    CouldNotCompile(String, String),
+
+    #[error("Script {0} must define fn handle(event, tx)")]
+    HandleNotDefined(String),
+
+    #[error("Script {0} failed: {1}")]
+    Failed(String, String),
+
+    #[error("Script {0} emitted an invalid row into {1}: {2}")]
+    InvalidEmit(String, String, String),
+}
+
+/// What a script did with a single event
+#[derive(Debug, Default)]
+pub struct ScriptOutput {
+    /// `None` when the script filtered the event out
+    pub event: Option<Value>,
+
+    /// Rows emitted with `emit(table, row)` in the order they were emitted
+    pub rows: Vec<(String, Map<String, Value>)>,
+
+    /// Extra messages published with `publish(message)`
+    pub published: Vec<Value>,
+}
+
+struct CompiledScript {
+    ast: AST,
+    modified: Option<SystemTime>,
+}
+
+/// Runs the rhai script of an event, the engine has no access to the network, the file system or
+/// other modules and a run is capped so a runaway script can not stall indexing. The script is
+/// compiled again when its file changes, if it no longer compiles the last good version is kept.
+pub struct ScriptHandler {
+    path: PathBuf,
+    engine: Engine,
+    script: Mutex<CompiledScript>,
+    emitted: Arc<Mutex<Vec<(String, Dynamic)>>>,
+    published: Arc<Mutex<Vec<Dynamic>>>,
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn compile(engine: &Engine, path: &Path) -> Result<AST, ScriptError> {
+    let display = path.display().to_string();
+    let source =
+        fs::read_to_string(path).map_err(|e| ScriptError::CouldNotRead(display.clone(), e))?;
+    let ast = engine
+        .compile(source)
+        .map_err(|e| ScriptError::CouldNotCompile(display.clone(), e.to_string()))?;
+
+    if !ast.iter_functions().any(|f| f.name == SCRIPT_HANDLE_FN && f.params.len() == 2) {
+        return Err(ScriptError::HandleNotDefined(display));
+    }
+
+    Ok(ast)
+}
+
+fn to_json(value: &Dynamic) -> Result<Value, String> {
+    from_dynamic::<Value>(value).map_err(|e| e.to_string())
+}
+
+impl ScriptHandler {
+    pub fn new(path: &Path) -> Result<Self, ScriptError> {
+        let emitted: Arc<Mutex<Vec<(String, Dynamic)>>> = Arc::new(Mutex::new(vec![]));
+        let published: Arc<Mutex<Vec<Dynamic>>> = Arc::new(Mutex::new(vec![]));
+
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(1_000_000)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1024 * 1024)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000)
+            .set_module_resolver(DummyModuleResolver::new())
+            .disable_symbol("eval");
+
+        let source = path.display().to_string();
+        let print_source = source.clone();
+        engine.on_print(move |message| info!("{} - {}", print_source, message));
+        engine
+            .on_debug(move |message, _, position| debug!("{} {} - {}", source, position, message));
+
+        let emit_buffer = Arc::clone(&emitted);
+        engine.register_fn("emit", move |table: &str, row: rhai::Map| {
+            emit_buffer.lock().unwrap().push((table.to_string(), Dynamic::from_map(row)));
+        });
+        let publish_buffer = Arc::clone(&published);
+        engine.register_fn("publish", move |message: Dynamic| {
+            publish_buffer.lock().unwrap().push(message);
+        });
+
+        let ast = compile(&engine, path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            script: Mutex::new(CompiledScript { ast, modified: modified_at(path) }),
+            emitted,
+            published,
+        })
+    }
+
+    /// Compiles the script again if its file changed since it was last compiled
+    pub fn reload_if_changed(&self) {
+        let modified = modified_at(&self.path);
+        let mut script = self.script.lock().unwrap();
+        if modified.is_none() || modified == script.modified {
+            return;
+        }
+        script.modified = modified;
+
+        match compile(&self.engine, &self.path) {
+            Ok(ast) => {
+                script.ast = ast;
+                info!("Reloaded script {}", self.path.display());
+            }
+            Err(e) => error!("{} - the previous version of the script is still used", e),
+        }
+    }
+
+    /// Calls `handle(event, tx)`, returning `false` drops the event, returning a map replaces the
+    /// event and anything else keeps it as it is
+    pub fn handle(&self, event: &Value) -> Result<ScriptOutput, ScriptError> {
+        let display = || self.path.display().to_string();
+        let failed = |e: String| ScriptError::Failed(display(), e);
+
+        let mut event = event.clone();
+        let tx = match &mut event {
+            Value::Object(event) => event.remove("transaction_information"),
+            _ => None,
+        }
+        .unwrap_or(Value::Null);
+
+        let script = self.script.lock().unwrap();
+        self.emitted.lock().unwrap().clear();
+        self.published.lock().unwrap().clear();
+
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &script.ast,
+            SCRIPT_HANDLE_FN,
+            (
+                to_dynamic(&event).map_err(|e| failed(e.to_string()))?,
+                to_dynamic(&tx).map_err(|e| failed(e.to_string()))?,
+            ),
+        );
+        let emitted = std::mem::take(&mut *self.emitted.lock().unwrap());
+        let published = std::mem::take(&mut *self.published.lock().unwrap());
+        drop(script);
+
+        let result = result.map_err(|e| failed(e.to_string()))?;
+
+        let mut output = ScriptOutput::default();
+        for (table, row) in emitted {
+            match to_json(&row) {
+                Ok(Value::Object(row)) => output.rows.push((table, row)),
+                Ok(_) => unreachable!("emit only accepts maps"),
+                Err(e) => return Err(ScriptError::InvalidEmit(display(), table, e)),
+            }
+        }
+        for message in published {
+            output.published.push(to_json(&message).map_err(failed)?);
+        }
+
+        output.event = if result.as_bool() == Ok(false) {
+            None
+        } else if result.is_map() {
+            let mut replaced = to_json(&result).map_err(failed)?;
+            if let Value::Object(replaced) = &mut replaced {
+                replaced.insert("transaction_information".to_string(), tx);
+            }
+            Some(replaced)
+        } else {
+            if let Value::Object(event) = &mut event {
+                event.insert("transaction_information".to_string(), tx);
+            }
+            Some(event)
+        };
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn write_script(name: &str, source: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rindexer_{}_{}.rhai", name, std::process::id()));
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_script_handler() {
+        let path = write_script(
+            "handler",
+            r#"
+            fn handle(event, tx) {
+                if parse_int(event.value) < 10 {
+                    return false;
+                }
+                emit("large_transfers", #{ holder: event.to, amount: event.value });
+                publish(#{ kind: "large", block: tx.block_number });
+                event.value_doubled = parse_int(event.value) * 2;
+                event
+            }
+            "#,
+        );
+        let handler = ScriptHandler::new(&path).unwrap();
+
+        let event = |value: &str| {
+            json!({
+                "to": "0x0338ce5020c447f7e668dc2ef778025ce3982662",
+                "value": value,
+                "transaction_information": { "block_number": 12 }
+            })
+        };
+
+        let output = handler.handle(&event("5")).unwrap();
+        assert!(output.event.is_none());
+        assert!(output.rows.is_empty());
+
+        let output = handler.handle(&event("20")).unwrap();
+        let handled = output.event.unwrap();
+        assert_eq!(handled["value_doubled"], json!(40));
+        assert_eq!(handled["transaction_information"]["block_number"], json!(12));
+        assert_eq!(output.rows[0].0, "large_transfers");
+        assert_eq!(output.rows[0].1["amount"], json!("20"));
+        assert_eq!(output.published, vec![json!({ "kind": "large", "block": 12 })]);
+
+        // a script which no longer compiles keeps the last good version
+        fs::write(&path, "fn handle(event, tx) {").unwrap();
+        handler.script.lock().unwrap().modified = None;
+        handler.reload_if_changed();
+        assert!(handler.handle(&event("20")).unwrap().event.is_some());
+
+        fs::write(&path, "fn handle(event, tx) { false }").unwrap();
+        handler.script.lock().unwrap().modified = None;
+        handler.reload_if_changed();
+        assert!(handler.handle(&event("20")).unwrap().event.is_none());
+
+        fs::write(&path, "fn other(event) { event }").unwrap();
+        assert!(matches!(ScriptHandler::new(&path), Err(ScriptError::HandleNotDefined(_))));
+        fs::write(&path, "fn handle(event, tx) { loop {} }").unwrap();
+        assert!(ScriptHandler::new(&path).unwrap().handle(&event("20")).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -10,11 +10,20 @@ use tokio::{
 use tracing::{error, info};
 
 use crate::{
-    database::postgres::client::PostgresConnectionError,
+    database::{
+        clickhouse::client::ClickhouseConnectionError,
+        postgres::{
+            client::PostgresConnectionError,
+            generate::generate_event_table_full_name,
+            retention::{get_retention_pruned_block, run_retention, RetentionTables},
+        },
+        sqlite::{client::SqliteConnectionError, setup::sqlite_database_path},
+    },
     event::{
         callback_registry::EventCallbackRegistry, config::EventProcessingConfig,
         contract_setup::NetworkContract,
     },
+    helpers::camel_to_snake,
     indexer::{
         dependency::ContractEventsDependenciesConfig,
         last_synced::{get_last_synced_block_number, SyncConfig},
@@ -27,7 +36,8 @@ use crate::{
         ContractEventDependencies,
     },
     manifest::core::Manifest,
-    PostgresClient,
+    simple_file_formatters::parquet::close_parquet_files,
+    ClickhouseClient, PostgresClient, SqliteClient,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -51,6 +61,12 @@ pub enum StartIndexingError {
     #[error("{0}")]
     PostgresConnectionError(#[from] PostgresConnectionError),
 
+    #[error("{0}")]
+    SqliteConnectionError(#[from] SqliteConnectionError),
+
+    #[error("{0}")]
+    ClickhouseConnectionError(#[from] ClickhouseConnectionError),
+
     #[error("Could not get block number from provider: {0}")]
     GetBlockNumberError(#[from] ProviderError),
 
@@ -85,6 +101,8 @@ pub async fn start_indexing(
     let start = Instant::now();
 
     let database = initialize_database(manifest).await?;
+    let sqlite = initialize_sqlite(manifest, project_path)?;
+    let clickhouse = initialize_clickhouse(manifest).await?;
     let event_progress_state = IndexingEventsProgressState::monitor(&registry.events).await;
 
     // we can bring this into the yaml file later if required
@@ -111,7 +129,11 @@ pub async fn start_indexing(
             let config = SyncConfig {
                 project_path,
                 database: &database,
+                sqlite: &sqlite,
+                clickhouse: &clickhouse,
                 csv_details: &manifest.storage.csv,
+                parquet_details: &manifest.storage.parquet,
+                ndjson_details: &manifest.storage.ndjson,
                 contract_csv_enabled: manifest.contract_csv_enabled(&event.contract.name),
                 stream_details: &stream_details,
                 indexer_name: &event.indexer_name,
@@ -156,8 +178,42 @@ pub async fn start_indexing(
                 None
             };
 
-            let start_block = last_known_start_block
+            let mut start_block = last_known_start_block
                 .unwrap_or(network_contract.start_block.unwrap_or(latest_block));
+
+            let event_table_name =
+                manifest.contracts.iter().find(|c| c.name == event.contract.name).map_or_else(
+                    || camel_to_snake(&event.event_name),
+                    |c| c.event_table_name(&event.event_name),
+                );
+
+            // pruned rows are never indexed again
+            if let Some(database) = &database {
+                let table_name = generate_event_table_full_name(
+                    &event.indexer_name,
+                    &event.contract.name,
+                    &event_table_name,
+                );
+                match get_retention_pruned_block(
+                    database,
+                    &event.indexer_name,
+                    &table_name,
+                    &network_contract.network,
+                )
+                .await
+                {
+                    Ok(Some(pruned_to_block)) if pruned_to_block > start_block => {
+                        info!(
+                            "{} - blocks before {} were pruned by the retention, starting from there",
+                            event.info_log_name(),
+                            pruned_to_block
+                        );
+                        start_block = pruned_to_block;
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Error fetching the retention pruned block: {:?}", e),
+                }
+            }
             let end_block =
                 std::cmp::min(network_contract.end_block.unwrap_or(latest_block), latest_block);
             if let Some(end_block) = network_contract.end_block {
@@ -188,6 +244,7 @@ pub async fn start_indexing(
                 info_log_name: event.info_log_name(),
                 topic_id: event.topic_id,
                 event_name: event.event_name.clone(),
+                event_table_name,
                 network_contract: Arc::new(network_contract.clone()),
                 start_block,
                 end_block,
@@ -195,7 +252,22 @@ pub async fn start_indexing(
                 registry: Arc::clone(&registry),
                 progress: Arc::clone(&event_progress_state),
                 database: database.clone(),
-                csv_details: manifest.storage.csv.clone(),
+                sqlite: sqlite.clone(),
+                clickhouse: clickhouse.clone(),
+                postgres_partitioning: manifest
+                    .contracts
+                    .iter()
+                    .find(|c| c.name == event.contract.name)
+                    .and_then(|c| c.postgres_partitioning(manifest.storage.postgres_partitioning()))
+                    .cloned(),
+                // the last synced block is only written where it is read back from
+                csv_details: manifest
+                    .storage
+                    .csv
+                    .clone()
+                    .filter(|_| manifest.contract_csv_enabled(&event.contract.name)),
+                parquet_details: manifest.storage.parquet.clone().filter(|p| p.enabled),
+                ndjson_details: manifest.storage.ndjson.clone().filter(|n| n.enabled),
                 stream_last_synced_block_file_path: stream_details
                     .as_ref()
                     .map(|s| s.get_streams_last_synced_block_path()),
@@ -244,6 +316,10 @@ pub async fn start_indexing(
         }
     }
 
+    if let Some(database) = &database {
+        start_retention(manifest, &registry, database);
+    }
+
     // apply dependency events config after processing to avoid ordering issues
     for apply in apply_cross_contract_dependency_events_config_after_processing {
         let (dependency_in_other_contract, event_processing_config) = apply;
@@ -287,6 +363,8 @@ pub async fn start_indexing(
         }
     }
 
+    close_parquet_files().await;
+
     let duration = start.elapsed();
 
     info!("Historical indexing complete - time taken: {:?}", duration);
@@ -294,6 +372,48 @@ pub async fn start_indexing(
     Ok(processed_network_contracts)
 }
 
+/// Prunes the event tables of every contract with a retention in the background
+fn start_retention(
+    manifest: &Manifest,
+    registry: &EventCallbackRegistry,
+    database: &Arc<PostgresClient>,
+) {
+    for contract in &manifest.contracts {
+        let Some(retention) = contract.storage.as_ref().and_then(|s| s.retention.clone()) else {
+            continue;
+        };
+
+        let events: Vec<_> =
+            registry.events.iter().filter(|e| e.contract.name == contract.name).collect();
+        let Some(first_event) = events.first() else {
+            continue;
+        };
+
+        let tables = RetentionTables {
+            contract_name: contract.name.clone(),
+            retention,
+            table_names: events
+                .iter()
+                .map(|event| {
+                    generate_event_table_full_name(
+                        &manifest.name,
+                        &contract.name,
+                        &contract.event_table_name(&event.event_name),
+                    )
+                })
+                .collect(),
+            networks: first_event
+                .contract
+                .details
+                .iter()
+                .map(|details| (details.network.clone(), Arc::clone(&details.cached_provider)))
+                .collect(),
+        };
+
+        tokio::spawn(run_retention(Arc::clone(database), manifest.name.clone(), tables));
+    }
+}
+
 async fn initialize_database(
     manifest: &Manifest,
 ) -> Result<Option<Arc<PostgresClient>>, StartIndexingError> {
@@ -310,6 +430,40 @@ async fn initialize_database(
     }
 }
 
+fn initialize_sqlite(
+    manifest: &Manifest,
+    project_path: &Path,
+) -> Result<Option<Arc<SqliteClient>>, StartIndexingError> {
+    match &manifest.storage.sqlite {
+        Some(details) if details.enabled => {
+            match SqliteClient::new(&sqlite_database_path(project_path, details)) {
+                Ok(sqlite) => Ok(Some(Arc::new(sqlite))),
+                Err(e) => {
+                    error!("Error opening Sqlite: {:?}", e);
+                    Err(StartIndexingError::SqliteConnectionError(e))
+                }
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn initialize_clickhouse(
+    manifest: &Manifest,
+) -> Result<Option<Arc<ClickhouseClient>>, StartIndexingError> {
+    if manifest.storage.clickhouse_enabled() {
+        match ClickhouseClient::new().await {
+            Ok(clickhouse) => Ok(Some(Arc::new(clickhouse))),
+            Err(e) => {
+                error!("Error connecting to Clickhouse: {:?}", e);
+                Err(StartIndexingError::ClickhouseConnectionError(e))
+            }
+        }
+    } else {
+        Ok(None)
+    }
+}
+
 async fn calculate_safe_block_number(
     reorg_safe_distance: bool,
     network_contract: &NetworkContract,
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::manifest::transform::{EventTransform, TransformFailurePolicy};
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransformError {
+    #[error("Request error: {0}")]
+    RequestError(#[from] reqwest::Error),
+
+    #[error("Transform endpoint responded with {0}")]
+    UnsuccessfulStatus(reqwest::StatusCode),
+
+    #[error("Transform endpoint response is invalid: {0}")]
+    InvalidResponse(String),
+}
+
+/// Calls the transform endpoint of an event with every batch of decoded events
+pub struct TransformHook {
+    client: Client,
+    transform: EventTransform,
+}
+
+/// The response must be `{ "events": [...] }` with an entry per event sent in the same order,
+/// either the event to keep, which may be changed, or `null` to drop it
+fn parse_transform_response(
+    response: Value,
+    sent: &[Value],
+) -> Result<Vec<Option<Value>>, TransformError> {
+    let Some(Value::Array(events)) = response.get("events") else {
+        return Err(TransformError::InvalidResponse("events must be an array".to_string()));
+    };
+
+    if events.len() != sent.len() {
+        return Err(TransformError::InvalidResponse(format!(
+            "{} events returned for {} sent",
+            events.len(),
+            sent.len()
+        )));
+    }
+
+    events
+        .iter()
+        .zip(sent)
+        .map(|(event, sent)| match event {
+            Value::Null => Ok(None),
+            Value::Object(event) => {
+                let mut event = event.clone();
+                // the transaction information is kept so streams can still tell events apart
+                if let Some(tx) = sent.get("transaction_information") {
+                    event.entry("transaction_information").or_insert_with(|| tx.clone());
+                }
+                Ok(Some(Value::Object(event)))
+            }
+            _ => Err(TransformError::InvalidResponse(
+                "every event must be an object or null".to_string(),
+            )),
+        })
+        .collect()
+}
+
+impl TransformHook {
+    pub fn new(transform: EventTransform) -> Self {
+        Self { client: Client::new(), transform }
+    }
+
+    async fn call(
+        &self,
+        body: &Value,
+        events: &[Value],
+    ) -> Result<Vec<Option<Value>>, TransformError> {
+        let mut request = self
+            .client
+            .post(&self.transform.endpoint)
+            .timeout(self.transform.timeout())
+            .header("Content-Type", "application/json");
+        if let Some(shared_secret) = &self.transform.shared_secret {
+            request = request.header("x-rindexer-shared-secret", shared_secret);
+        }
+
+        let response = request.json(body).send().await?;
+        if !response.status().is_success() {
+            return Err(TransformError::UnsuccessfulStatus(response.status()));
+        }
+
+        parse_transform_response(response.json().await?, events)
+    }
+
+    /// Sends the batch to the endpoint applying the failure policy, an error means the batch
+    /// must not be stored so the event stops progressing until it is transformed
+    pub async fn transform(
+        &self,
+        contract_name: &str,
+        event_name: &str,
+        network: &str,
+        events: Vec<Value>,
+    ) -> Result<Vec<Option<Value>>, TransformError> {
+        let body = json!({
+            "contract_name": contract_name,
+            "event_name": event_name,
+            "network": network,
+            "events": events,
+        });
+
+        let mut attempts = 0;
+        let mut delay = Duration::from_millis(500);
+        loop {
+            let error = match self.call(&body, &events).await {
+                Ok(transformed) => return Ok(transformed),
+                Err(e) => e,
+            };
+
+            match self.transform.on_failure() {
+                TransformFailurePolicy::Skip => {
+                    warn!(
+                        "{}::{} - transform failed so the batch is used as decoded: {}",
+                        contract_name, event_name, error
+                    );
+                    return Ok(events.into_iter().map(Some).collect());
+                }
+                TransformFailurePolicy::Retry if attempts < self.transform.max_retries() => {
+                    attempts += 1;
+                    warn!(
+                        "{}::{} - transform failed, retrying (attempt {}): {}",
+                        contract_name, event_name, attempts, error
+                    );
+                    sleep(delay).await;
+                    delay = (delay * 2).min(Duration::from_secs(15));
+                }
+                TransformFailurePolicy::Retry | TransformFailurePolicy::Halt => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transform_response() {
+        let sent = vec![
+            json!({ "value": "1", "transaction_information": { "log_index": "0x0" } }),
+            json!({ "value": "2", "transaction_information": { "log_index": "0x1" } }),
+        ];
+
+        let transformed = parse_transform_response(
+            json!({ "events": [{ "value": "1", "usd": 10 }, null] }),
+            &sent,
+        )
+        .unwrap();
+        assert_eq!(
+            transformed,
+            vec![
+                Some(json!({
+                    "value": "1",
+                    "usd": 10,
+                    "transaction_information": { "log_index": "0x0" }
+                })),
+                None
+            ]
+        );
+
+        assert!(parse_transform_response(json!({ "events": [null] }), &sent).is_err());
+        assert!(parse_transform_response(json!({ "events": [1, null] }), &sent).is_err());
+        assert!(parse_transform_response(json!([null, null]), &sent).is_err());
+    }
+}
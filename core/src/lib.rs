@@ -4,13 +4,22 @@ pub mod indexer;
 pub mod manifest;
 
 mod database;
-pub use database::postgres::{
-    client::PostgresClient, generate::drop_tables_for_indexer_sql, setup::setup_postgres,
-    sql_type_wrapper::EthereumSqlTypeWrapper,
+pub use database::{
+    clickhouse::client::ClickhouseClient,
+    postgres::{
+        client::{set_postgres_connection_details, PostgresClient},
+        generate::drop_tables_for_indexer_sql,
+        redecode::{redecode_raw_logs, RedecodedEventTable},
+        setup::setup_postgres,
+        sql_type_wrapper::EthereumSqlTypeWrapper,
+    },
+    sqlite::client::SqliteClient,
 };
 
 mod simple_file_formatters;
-pub use simple_file_formatters::csv::AsyncCsvAppender;
+pub use simple_file_formatters::{
+    csv::AsyncCsvAppender, ndjson::AsyncNdjsonAppender, parquet::AsyncParquetAppender,
+};
 
 mod helpers;
 pub use helpers::{
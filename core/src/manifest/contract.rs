@@ -12,7 +12,11 @@ use crate::{
         AddressDetails, ContractEventMapping, FilterDetails, IndexingContractSetup,
     },
     indexer::parse_topic,
-    manifest::{chat::ChatConfig, stream::StreamsConfig},
+    manifest::{
+        chat::ChatConfig,
+        storage::{ContractStorage, PostgresPartitioning, StorageEvent},
+        stream::StreamsConfig,
+    },
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -223,6 +227,9 @@ pub struct Contract {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub generate_csv: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<ContractStorage>,
+
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub streams: Option<StreamsConfig>,
 
@@ -291,6 +298,32 @@ impl Contract {
         }
     }
 
+    /// The storage options of the event, the defaults when it has none
+    pub fn storage_event(&self, event_name: &str) -> StorageEvent {
+        let mut storage_event = self
+            .storage
+            .as_ref()
+            .and_then(|storage| storage.find_event(event_name))
+            .cloned()
+            .unwrap_or_else(|| StorageEvent::new(event_name));
+        storage_event.store_raw_log =
+            self.storage.as_ref().is_some_and(|storage| storage.is_store_raw_log());
+        storage_event
+    }
+
+    /// The event table name without the schema
+    pub fn event_table_name(&self, event_name: &str) -> String {
+        self.storage_event(event_name).event_table_name()
+    }
+
+    /// The contract partitioning if set else the global postgres partitioning
+    pub fn postgres_partitioning<'a>(
+        &'a self,
+        global: Option<&'a PostgresPartitioning>,
+    ) -> Option<&'a PostgresPartitioning> {
+        self.storage.as_ref().and_then(|storage| storage.partitioning.as_ref()).or(global)
+    }
+
     pub fn identify_and_modify_filter(&mut self) -> bool {
         if self.is_filter() {
             self.override_name(self.contract_name_to_filter_name());
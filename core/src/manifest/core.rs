@@ -5,8 +5,8 @@ use serde_yaml::Value;
 use crate::{
     indexer::Indexer,
     manifest::{
-        contract::Contract, global::Global, graphql::GraphQLSettings, network::Network,
-        phantom::Phantom, storage::Storage,
+        contract::Contract, entity::Entity, global::Global, graphql::GraphQLSettings,
+        network::Network, phantom::Phantom, storage::Storage,
     },
 };
 
@@ -68,6 +68,10 @@ pub struct Manifest {
 
     pub contracts: Vec<Contract>,
 
+    /// Latest state tables kept up to date by rules applied to the events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<Entity>>,
+
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub phantom: Option<Phantom>,
 
@@ -80,7 +84,11 @@ pub struct Manifest {
 
 impl Manifest {
     pub fn to_indexer(&self) -> Indexer {
-        Indexer { name: self.name.clone(), contracts: self.contracts.clone() }
+        Indexer {
+            name: self.name.clone(),
+            contracts: self.contracts.clone(),
+            entities: self.entities.clone(),
+        }
     }
 
     pub fn has_any_contracts_live_indexing(&self) -> bool {
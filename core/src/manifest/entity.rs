@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{abi::ABIInput, manifest::storage::is_sql_identifier};
+
+/// Columns every entity table gets next to its keys and columns
+const ENTITY_BASE_COLUMNS: [&str; 2] = ["network", "block_number"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntityColumn {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+impl EntityColumn {
+    /// Integers are NUMERIC whatever their size so they can be incremented without overflowing
+    pub fn db_type(&self) -> Option<&'static str> {
+        entity_db_type(&self.type_)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntityEvent {
+    pub contract_name: String,
+
+    pub event_name: String,
+
+    /// Rules like `owner[tokenId] = to` or `balance[to] += value`, the inputs in brackets give
+    /// the entity keys in the order they are defined
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Entity {
+    pub name: String,
+
+    pub keys: Vec<EntityColumn>,
+
+    #[serde(default)]
+    pub columns: Vec<EntityColumn>,
+
+    pub events: Vec<EntityEvent>,
+}
+
+impl Entity {
+    pub fn find_column(&self, name: &str) -> Option<&EntityColumn> {
+        self.keys.iter().chain(self.columns.iter()).find(|column| column.name == name)
+    }
+
+    /// The rules of the entity applied by an event
+    pub fn event_rules(&self, contract_name: &str, event_name: &str) -> Vec<&String> {
+        self.events
+            .iter()
+            .filter(|e| e.contract_name == contract_name && e.event_name == event_name)
+            .flat_map(|e| &e.rules)
+            .collect()
+    }
+
+    /// `event_inputs` looks up the inputs of a contract event, `None` when it does not exist
+    pub fn validate<'a>(
+        &self,
+        event_inputs: impl Fn(&str, &str) -> Option<&'a [ABIInput]>,
+    ) -> Result<(), String> {
+        if !is_sql_identifier(&self.name) {
+            return Err("name must be lowercase letters, digits and underscores".to_string());
+        }
+
+        if self.keys.is_empty() {
+            return Err("at least one key is required".to_string());
+        }
+
+        let mut seen: Vec<&str> = ENTITY_BASE_COLUMNS.to_vec();
+        for column in self.keys.iter().chain(self.columns.iter()) {
+            if !is_sql_identifier(&column.name) {
+                return Err(format!(
+                    "column {} must be lowercase letters, digits and underscores",
+                    column.name
+                ));
+            }
+            if seen.contains(&column.name.as_str()) {
+                return Err(format!("column {} is used more than once", column.name));
+            }
+            if column.db_type().is_none() {
+                return Err(format!(
+                    "column {} is {} which can not be stored, use an address, bool, string, bytes or integer type",
+                    column.name, column.type_
+                ));
+            }
+            seen.push(&column.name);
+        }
+
+        for event in &self.events {
+            let inputs =
+                event_inputs(&event.contract_name, &event.event_name).ok_or_else(|| {
+                    format!(
+                        "event {} not found on contract {}",
+                        event.event_name, event.contract_name
+                    )
+                })?;
+
+            for rule in &event.rules {
+                EntityRule::parse(rule)?.validate(self, inputs).map_err(|e| {
+                    format!(
+                        "rule `{}` for {}::{}: {}",
+                        rule, event.contract_name, event.event_name, e
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityRuleOperation {
+    Set,
+    Increment,
+    Decrement,
+}
+
+/// A parsed rule, `balance[to] -= value` sets the `balance` column of the entity keyed by the
+/// `to` input to its current value minus the `value` input
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityRule {
+    pub column: String,
+
+    /// Event input paths in the order of the entity keys
+    pub keys: Vec<String>,
+
+    pub operation: EntityRuleOperation,
+
+    /// An event input path like `amount` or `order.amount`, or `contract_address`
+    pub value: String,
+}
+
+impl EntityRule {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (target, operation, value) = if let Some((target, value)) = rule.split_once("+=") {
+            (target, EntityRuleOperation::Increment, value)
+        } else if let Some((target, value)) = rule.split_once("-=") {
+            (target, EntityRuleOperation::Decrement, value)
+        } else if let Some((target, value)) = rule.split_once('=') {
+            (target, EntityRuleOperation::Set, value)
+        } else {
+            return Err(format!("rule `{}` must use =, += or -=", rule));
+        };
+
+        let (column, keys) = target
+            .trim()
+            .strip_suffix(']')
+            .and_then(|target| target.split_once('['))
+            .ok_or_else(|| format!("rule `{}` must look like column[key] = value", rule))?;
+
+        let keys: Vec<String> = keys.split(',').map(|key| key.trim().to_string()).collect();
+        let value = value.trim();
+        if column.trim().is_empty() || value.is_empty() || keys.iter().any(|key| key.is_empty()) {
+            return Err(format!("rule `{}` must look like column[key] = value", rule));
+        }
+
+        Ok(EntityRule {
+            column: column.trim().to_string(),
+            keys,
+            operation,
+            value: value.to_string(),
+        })
+    }
+
+    fn validate(&self, entity: &Entity, inputs: &[ABIInput]) -> Result<(), String> {
+        let column =
+            entity.columns.iter().find(|column| column.name == self.column).ok_or_else(|| {
+                format!("column {} is not a column of entity {}", self.column, entity.name)
+            })?;
+
+        if self.operation != EntityRuleOperation::Set && column.db_type() != Some("NUMERIC") {
+            return Err(format!("column {} must be an integer to be incremented", column.name));
+        }
+
+        if self.keys.len() != entity.keys.len() {
+            return Err(format!(
+                "{} keys given but entity {} has {}",
+                self.keys.len(),
+                entity.name,
+                entity.keys.len()
+            ));
+        }
+
+        let sources =
+            self.keys.iter().zip(&entity.keys).chain(std::iter::once((&self.value, column)));
+        for (path, column) in sources {
+            let input_type = source_type(inputs, path)
+                .ok_or_else(|| format!("input {} not found in the event", path))?;
+            if entity_db_type(input_type) != column.db_type() {
+                return Err(format!(
+                    "input {} is {} which does not fit column {} of type {}",
+                    path, input_type, column.name, column.type_
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn entity_db_type(type_: &str) -> Option<&'static str> {
+    let is_sized = |size: &str| size.is_empty() || size.parse::<u16>().is_ok();
+    match type_ {
+        "address" => Some("CHAR(42)"),
+        "bool" => Some("BOOLEAN"),
+        "string" => Some("TEXT"),
+        t if t.strip_prefix("bytes").is_some_and(is_sized) => Some("BYTEA"),
+        t if t.strip_prefix("uint").or(t.strip_prefix("int")).is_some_and(is_sized) => {
+            Some("NUMERIC")
+        }
+        _ => None,
+    }
+}
+
+/// The solidity type of a rule source, tuple fields are walked by their path
+pub(crate) fn source_type<'a>(inputs: &'a [ABIInput], path: &str) -> Option<&'a str> {
+    if path == "contract_address" {
+        return Some("address");
+    }
+
+    let mut inputs = inputs;
+    let mut found: Option<&ABIInput> = None;
+    for part in path.split('.') {
+        let input = inputs.iter().find(|input| input.name == part)?;
+        inputs = input.components.as_deref().unwrap_or(&[]);
+        found = Some(input);
+    }
+
+    found.map(|input| input.type_.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_rules() {
+        let input = |name: &str, type_: &str| ABIInput {
+            indexed: None,
+            name: name.to_string(),
+            type_: type_.to_string(),
+            components: None,
+        };
+        let inputs =
+            vec![input("from", "address"), input("to", "address"), input("value", "uint256")];
+
+        assert_eq!(
+            EntityRule::parse("balance[to] += value").unwrap(),
+            EntityRule {
+                column: "balance".to_string(),
+                keys: vec!["to".to_string()],
+                operation: EntityRuleOperation::Increment,
+                value: "value".to_string(),
+            }
+        );
+        assert_eq!(
+            EntityRule::parse("balance[contract_address, from] -= value").unwrap().keys,
+            ["contract_address", "from"]
+        );
+        assert!(EntityRule::parse("balance += value").is_err());
+
+        let entity: Entity = serde_yaml::from_str(
+            r#"
+            name: balances
+            keys:
+              - name: holder
+                type: address
+            columns:
+              - name: balance
+                type: uint256
+              - name: last_sender
+                type: address
+            events:
+              - contract_name: Token
+                event_name: Transfer
+                rules:
+                  - balance[to] += value
+                  - balance[from] -= value
+                  - last_sender[to] = from
+            "#,
+        )
+        .unwrap();
+        let event_inputs =
+            |_: &str, event_name: &str| Some(&inputs[..]).filter(|_| event_name == "Transfer");
+        assert!(entity.validate(event_inputs).is_ok());
+
+        let invalid = |rule: &str| {
+            let mut entity = entity.clone();
+            entity.events[0].rules = vec![rule.to_string()];
+            entity.validate(event_inputs).is_err()
+        };
+        assert!(invalid("last_sender[to] += value"));
+        assert!(invalid("balance[to] = from"));
+        assert!(invalid("balance[value] += value"));
+        assert!(invalid("balance[to] += amount"));
+        assert!(invalid("missing[to] = value"));
+    }
+}
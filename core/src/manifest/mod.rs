@@ -1,10 +1,15 @@
 pub mod chat;
 pub mod contract;
 pub mod core;
+pub mod entity;
 pub mod global;
 pub mod graphql;
 pub mod network;
 pub mod phantom;
+pub mod retention;
+pub mod rollup;
+pub mod script;
 pub mod storage;
 pub mod stream;
+pub mod transform;
 pub mod yaml;
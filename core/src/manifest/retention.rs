@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::storage::PostgresPartitioning;
+
+/// How old rows leave the event tables
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionMode {
+    /// The rows are deleted
+    #[default]
+    Delete,
+
+    /// Block partitions which only hold old rows are detached and kept as standalone tables in
+    /// `rindexer_internal`
+    Detach,
+}
+
+/// Keeps only the most recent blocks or time of the contract event tables in postgres
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Retention {
+    /// Rows more than this many blocks behind the latest block are pruned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks: Option<u64>,
+
+    /// Rows in blocks older than this, like `30d` or `12h`, are pruned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<RetentionMode>,
+
+    /// How often the tables are pruned, defaults to `1h`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+}
+
+/// A duration like `90s`, `30m`, `12h`, `30d` or `2w`
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let invalid = || format!("{} must be a number followed by s, m, h, d or w", duration);
+
+    let duration = duration.trim();
+    let unit_index = duration.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = duration.split_at(unit_index);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    Ok(Duration::from_secs(amount * seconds))
+}
+
+impl Retention {
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age.as_deref().and_then(|max_age| parse_duration(max_age).ok())
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+            .as_deref()
+            .and_then(|interval| parse_duration(interval).ok())
+            .unwrap_or(Duration::from_secs(60 * 60))
+    }
+
+    pub fn mode(&self) -> RetentionMode {
+        self.mode.unwrap_or_default()
+    }
+
+    pub fn validate(&self, partitioning: Option<&PostgresPartitioning>) -> Result<(), String> {
+        if self.max_blocks.is_none() && self.max_age.is_none() {
+            return Err("max_blocks or max_age must be set".to_string());
+        }
+
+        if self.max_blocks == Some(0) {
+            return Err("max_blocks must be greater than 0".to_string());
+        }
+
+        for duration in [&self.max_age, &self.interval].into_iter().flatten() {
+            if parse_duration(duration)?.is_zero() {
+                return Err(format!("{} must be greater than 0", duration));
+            }
+        }
+
+        if self.mode() == RetentionMode::Detach &&
+            !matches!(partitioning, Some(PostgresPartitioning::BlockNumber { .. }))
+        {
+            return Err("detach needs the tables to be partitioned by block_number".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_validate() {
+        assert_eq!(parse_duration("30d").unwrap(), Duration::from_secs(30 * 24 * 60 * 60));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("1y").is_err());
+
+        let retention: Retention = serde_yaml::from_str("max_age: 7d\nmode: detach").unwrap();
+        assert_eq!(retention.max_age(), Some(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert_eq!(retention.interval(), Duration::from_secs(60 * 60));
+        assert!(retention.validate(None).is_err());
+        assert!(retention
+            .validate(Some(&PostgresPartitioning::BlockNumber { blocks_per_partition: 1000 }))
+            .is_ok());
+        assert!(retention.validate(Some(&PostgresPartitioning::Network)).is_err());
+
+        let retention: Retention = serde_yaml::from_str("interval: 10m").unwrap();
+        assert!(retention.validate(None).is_err());
+    }
+}
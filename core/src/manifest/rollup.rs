@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    abi::ABIInput,
+    manifest::{
+        entity::{entity_db_type, source_type},
+        storage::{input_path_to_column_name, is_sql_identifier},
+    },
+};
+
+/// Columns every rollup table gets next to its group by columns and aggregates
+const ROLLUP_BASE_COLUMNS: [&str; 2] = ["network", "bucket"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupInterval {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl RollupInterval {
+    /// The postgres `date_trunc` field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupInterval::Minute => "minute",
+            RollupInterval::Hour => "hour",
+            RollupInterval::Day => "day",
+            RollupInterval::Week => "week",
+            RollupInterval::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupFunction {
+    Count,
+    Sum,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RollupAggregate {
+    pub name: String,
+
+    pub function: RollupFunction,
+
+    /// The event input aggregated, not used by `count`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rollup {
+    /// The table name in the contract schema
+    pub name: String,
+
+    pub interval: RollupInterval,
+
+    /// Event input paths like `to` or `order.maker`, or `contract_address`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<Vec<String>>,
+
+    pub aggregates: Vec<RollupAggregate>,
+}
+
+impl Rollup {
+    pub fn group_by(&self) -> &[String] {
+        self.group_by.as_deref().unwrap_or_default()
+    }
+
+    /// The group by column names in the order of `group_by`
+    pub fn group_by_column_names(&self) -> Vec<String> {
+        self.group_by().iter().map(|path| input_path_to_column_name(path)).collect()
+    }
+
+    /// The postgres type of a group by column, `None` when the input can not be grouped on
+    pub fn group_by_db_type(&self, inputs: &[ABIInput], path: &str) -> Option<&'static str> {
+        source_type(inputs, path).and_then(entity_db_type)
+    }
+
+    pub fn validate(&self, inputs: &[ABIInput]) -> Result<(), String> {
+        if !is_sql_identifier(&self.name) {
+            return Err("name must be lowercase letters, digits and underscores".to_string());
+        }
+
+        if self.aggregates.is_empty() {
+            return Err("at least one aggregate is required".to_string());
+        }
+
+        let mut seen: Vec<String> = ROLLUP_BASE_COLUMNS.iter().map(|c| c.to_string()).collect();
+        for (path, column) in self.group_by().iter().zip(self.group_by_column_names()) {
+            let input_type = source_type(inputs, path)
+                .ok_or_else(|| format!("group_by input {} not found in the event", path))?;
+            if entity_db_type(input_type).is_none() {
+                return Err(format!(
+                    "group_by input {} is {} which can not be grouped on",
+                    path, input_type
+                ));
+            }
+            if seen.contains(&column) {
+                return Err(format!("column {} is used more than once", column));
+            }
+            seen.push(column);
+        }
+
+        for aggregate in &self.aggregates {
+            if !is_sql_identifier(&aggregate.name) {
+                return Err(format!(
+                    "aggregate {} must be lowercase letters, digits and underscores",
+                    aggregate.name
+                ));
+            }
+            if seen.contains(&aggregate.name) {
+                return Err(format!("column {} is used more than once", aggregate.name));
+            }
+            seen.push(aggregate.name.clone());
+
+            match (aggregate.function, &aggregate.input) {
+                (RollupFunction::Count, None) => {}
+                (RollupFunction::Count, Some(_)) => {
+                    return Err(format!(
+                        "aggregate {} counts events and takes no input",
+                        aggregate.name
+                    ));
+                }
+                (_, None) => {
+                    return Err(format!("aggregate {} needs an input", aggregate.name));
+                }
+                (_, Some(input)) => {
+                    let input_type = source_type(inputs, input)
+                        .ok_or_else(|| format!("input {} not found in the event", input))?;
+                    if entity_db_type(input_type) != Some("NUMERIC") {
+                        return Err(format!(
+                            "aggregate {} needs an integer input but {} is {}",
+                            aggregate.name, input, input_type
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_validate() {
+        let input = |name: &str, type_: &str| ABIInput {
+            indexed: None,
+            name: name.to_string(),
+            type_: type_.to_string(),
+            components: None,
+        };
+        let inputs = vec![
+            input("from", "address"),
+            input("to", "address"),
+            input("value", "uint256"),
+            input("ids", "uint256[]"),
+        ];
+
+        let rollup: Rollup = serde_yaml::from_str(
+            r#"
+            name: hourly_volume
+            interval: hour
+            group_by:
+              - to
+            aggregates:
+              - name: transfers
+                function: count
+              - name: volume
+                function: sum
+                input: value
+              - name: largest
+                function: max
+                input: value
+            "#,
+        )
+        .unwrap();
+        assert!(rollup.validate(&inputs).is_ok());
+        assert_eq!(rollup.group_by_column_names(), ["to"]);
+
+        let invalid = |change: fn(&mut Rollup)| {
+            let mut rollup = rollup.clone();
+            change(&mut rollup);
+            rollup.validate(&inputs).is_err()
+        };
+        assert!(invalid(|r| r.group_by = Some(vec!["ids".to_string()])));
+        assert!(invalid(|r| r.group_by = Some(vec!["missing".to_string()])));
+        assert!(invalid(|r| r.aggregates[0].input = Some("value".to_string())));
+        assert!(invalid(|r| r.aggregates[1].input = Some("from".to_string())));
+        assert!(invalid(|r| r.aggregates[2].input = None));
+        assert!(invalid(|r| r.aggregates[1].name = "to".to_string()));
+        assert!(invalid(|r| r.aggregates.clear()));
+    }
+}
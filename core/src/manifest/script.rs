@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::{entity::EntityColumn, storage::is_sql_identifier};
+
+/// Columns every script table gets next to the columns it defines
+pub const SCRIPT_TABLE_BASE_COLUMNS: [&str; 4] =
+    ["rindexer_id", "network", "block_number", "tx_hash"];
+
+/// A table a script can emit rows into, it lives in the contract schema next to the event tables
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptTable {
+    pub name: String,
+
+    pub columns: Vec<EntityColumn>,
+}
+
+impl ScriptTable {
+    pub fn find_column(&self, name: &str) -> Option<&EntityColumn> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_sql_identifier(&self.name) {
+            return Err(format!(
+                "table {} must be lowercase letters, digits and underscores",
+                self.name
+            ));
+        }
+
+        let mut seen: Vec<&str> = SCRIPT_TABLE_BASE_COLUMNS.to_vec();
+        for column in &self.columns {
+            if !is_sql_identifier(&column.name) {
+                return Err(format!(
+                    "column {} of table {} must be lowercase letters, digits and underscores",
+                    column.name, self.name
+                ));
+            }
+            if seen.contains(&column.name.as_str()) {
+                return Err(format!(
+                    "column {} of table {} is used more than once",
+                    column.name, self.name
+                ));
+            }
+            if column.db_type().is_none() {
+                return Err(format!(
+                    "column {} of table {} is {} which can not be stored, use an address, bool, string, bytes or integer type",
+                    column.name, self.name, column.type_
+                ));
+            }
+            seen.push(&column.name);
+        }
+
+        Ok(())
+    }
+}
+
+/// A script handling every event before it is stored and published
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventScript {
+    /// A rhai file relative to the project path
+    pub path: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tables: Option<Vec<ScriptTable>>,
+}
+
+impl EventScript {
+    pub fn tables(&self) -> &[ScriptTable] {
+        self.tables.as_deref().unwrap_or_default()
+    }
+
+    pub fn find_table(&self, name: &str) -> Option<&ScriptTable> {
+        self.tables().iter().find(|table| table.name == name)
+    }
+}
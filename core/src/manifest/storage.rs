@@ -1,10 +1,13 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use tracing::info;
 
 use crate::{
+    abi::ABIInput,
     database::postgres::{
+        generate::generate_column_names_only_with_base_properties,
         indexes::{
             drop_last_known_indexes, prepare_indexes, DropLastKnownIndexesError,
             PostgresIndexResult, PrepareIndexesError,
@@ -14,9 +17,21 @@ use crate::{
             DropLastKnownRelationshipsError, Relationship,
         },
     },
-    manifest::contract::Contract,
+    event::parse_expression,
+    helpers::camel_to_snake,
+    manifest::{
+        contract::Contract, retention::Retention, rollup::Rollup, script::EventScript,
+        transform::EventTransform,
+    },
 };
 
+/// Lowercase letters, digits and underscores within the postgres identifier length
+pub fn is_sql_identifier(name: &str) -> bool {
+    name.len() <= 63 &&
+        name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_') &&
+        name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ForeignKey {
     pub contract_name: String,
@@ -72,6 +87,99 @@ pub struct PostgresIndexes {
     pub contracts: Option<Vec<ContractEventsIndexes>>,
 }
 
+fn default_blocks_per_partition() -> u64 {
+    1_000_000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum PostgresPartitioning {
+    BlockNumber {
+        #[serde(default = "default_blocks_per_partition")]
+        blocks_per_partition: u64,
+    },
+    Network,
+}
+
+impl PostgresPartitioning {
+    pub fn partition_key(&self) -> &'static str {
+        match self {
+            PostgresPartitioning::BlockNumber { .. } => "block_number",
+            PostgresPartitioning::Network => "network",
+        }
+    }
+
+    /// How the table is partitioned, recorded so a change in the layout can be detected
+    pub fn describe(&self) -> String {
+        match self {
+            PostgresPartitioning::BlockNumber { blocks_per_partition } => {
+                format!("RANGE (block_number) every {} blocks", blocks_per_partition)
+            }
+            PostgresPartitioning::Network => "LIST (network)".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PostgresSslMode {
+    Disable,
+    Prefer,
+    Require,
+}
+
+impl PostgresSslMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostgresSslMode::Disable => "disable",
+            PostgresSslMode::Prefer => "prefer",
+            PostgresSslMode::Require => "require",
+        }
+    }
+}
+
+/// Overrides `DATABASE_URL`, values can be env references like `${DATABASE_PASSWORD}`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PostgresConnectionDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_size: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement_timeout_ms: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_mode: Option<PostgresSslMode>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_certificate_path: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub application_name: Option<String>,
+
+    /// Used by the indexer, defaults to `DATABASE_URL`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_url: Option<String>,
+
+    /// Used by the GraphQL server, defaults to the write connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_url: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PostgresDetails {
     pub enabled: bool,
@@ -87,12 +195,63 @@ pub struct PostgresDetails {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub disable_create_tables: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<PostgresPartitioning>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<PostgresConnectionDetails>,
+}
+
+fn default_sqlite_path() -> String {
+    "./rindexer.db".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SqliteDetails {
+    pub enabled: bool,
+
+    #[serde(default = "default_sqlite_path")]
+    pub path: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drop_each_run: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Vec<ForeignKeys>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexes: Option<PostgresIndexes>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClickhouseDetails {
+    pub enabled: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drop_each_run: Option<bool>,
 }
 
 fn default_csv_path() -> String {
     "./generated_csv".to_string()
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvQuoteStyle {
+    Always,
+    Necessary,
+    NonNumeric,
+    Never,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvCompression {
+    Gzip,
+    Zstd,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CsvDetails {
     pub enabled: bool,
@@ -102,6 +261,367 @@ pub struct CsvDetails {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub disable_create_headers: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<char>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_style: Option<CsvQuoteStyle>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks_per_file: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size_mb: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CsvCompression>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fsync: Option<bool>,
+}
+
+fn default_parquet_path() -> String {
+    "./generated_parquet".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParquetDetails {
+    pub enabled: bool,
+
+    #[serde(default = "default_parquet_path")]
+    pub path: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks_per_file: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size_mb: Option<u64>,
+}
+
+impl ParquetDetails {
+    pub fn max_blocks_per_file(&self) -> u64 {
+        self.max_blocks_per_file.unwrap_or(10_000)
+    }
+
+    pub fn max_file_size_bytes(&self) -> u64 {
+        self.max_file_size_mb.unwrap_or(128) * 1024 * 1024
+    }
+}
+
+fn default_ndjson_path() -> String {
+    "./generated_ndjson".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NdjsonDetails {
+    pub enabled: bool,
+
+    #[serde(default = "default_ndjson_path")]
+    pub path: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks_per_file: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size_mb: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gzip: Option<bool>,
+}
+
+/// Where the decimals used to format an integer input come from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputFormat {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+
+    /// `contract_address` or the name of an address input of the event, the decimals are read
+    /// from the token contract at that address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals_from: Option<String>,
+}
+
+impl InputFormat {
+    /// Only top level integer inputs can be formatted
+    pub fn validate(&self, input_name: &str, inputs: &[ABIInput]) -> Result<(), String> {
+        let input = inputs
+            .iter()
+            .find(|input| input.name == input_name)
+            .ok_or_else(|| format!("input {} not found in the event", input_name))?;
+
+        if !(input.type_.starts_with("uint") || input.type_.starts_with("int")) ||
+            input.type_.ends_with(']')
+        {
+            return Err(format!(
+                "input {} is {} and only integers can be formatted",
+                input_name, input.type_
+            ));
+        }
+
+        match (&self.decimals, &self.decimals_from) {
+            (Some(_), None) => Ok(()),
+            (None, Some(decimals_from)) => {
+                if decimals_from == "contract_address" ||
+                    inputs.iter().any(|i| &i.name == decimals_from && i.type_ == "address")
+                {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "decimals_from {} must be contract_address or an address input of the event",
+                        decimals_from
+                    ))
+                }
+            }
+            _ => Err(format!(
+                "input {} must set exactly one of decimals or decimals_from",
+                input_name
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputedColumnType {
+    Number,
+    Bool,
+    String,
+}
+
+/// A column holding the result of an expression over the event inputs and its
+/// `transaction_information`, null when the expression can not be evaluated for an event
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ComputedColumn {
+    pub name: String,
+
+    pub expression: String,
+
+    #[serde(rename = "type")]
+    pub type_: ComputedColumnType,
+
+    /// Numbers are integers, with decimals the result is shown divided by 10^decimals so
+    /// `amount1 * 1e18 / amount0` with 18 decimals keeps 18 digits of the price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+}
+
+impl ComputedColumn {
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_sql_identifier(&self.name) {
+            return Err(format!(
+                "computed column {} must be lowercase letters, digits and underscores",
+                self.name
+            ));
+        }
+
+        if self.decimals.is_some() && self.type_ != ComputedColumnType::Number {
+            return Err(format!("computed column {} can only set decimals on a number", self.name));
+        }
+
+        parse_expression(&self.expression, None)
+            .map(|_| ())
+            .map_err(|e| format!("computed column {} has an invalid expression: {}", self.name, e))
+    }
+}
+
+/// `order.maker` is stored in the `order_maker` column
+pub fn input_path_to_column_name(input_path: &str) -> String {
+    input_path.split('.').map(camel_to_snake).collect::<Vec<_>>().join("_")
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StorageEvent {
+    pub event_name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<Map<String, Value>>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuples_as_jsonb: Option<bool>,
+
+    /// Event inputs which get an extra `{input}_formatted` column holding the value divided by
+    /// its decimals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<BTreeMap<String, InputFormat>>,
+
+    /// Overrides the table name which is the event name in snake case by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_name: Option<String>,
+
+    /// Renames the column of an event input, tuple fields are named by their path like
+    /// `order.maker`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<BTreeMap<String, String>>,
+
+    /// Columns derived from the event by expressions, evaluated in order so later ones can use
+    /// earlier ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub computed_columns: Option<Vec<ComputedColumn>>,
+
+    /// A script which can filter and transform the event, emit rows into its own tables and
+    /// publish extra messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<EventScript>,
+
+    /// An http endpoint every batch of the event is sent to after the script, it decides which
+    /// events are stored and what is published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<EventTransform>,
+
+    /// Time bucketed aggregates of the event kept up to date in their own postgres tables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollups: Option<Vec<Rollup>>,
+
+    /// Set from the contract `store_raw_log` so the event knows to add the raw log columns
+    #[serde(skip)]
+    pub store_raw_log: bool,
+}
+
+impl StorageEvent {
+    pub fn new(event_name: &str) -> Self {
+        StorageEvent { event_name: event_name.to_string(), ..Default::default() }
+    }
+
+    pub fn is_tuples_as_jsonb(&self) -> bool {
+        self.tuples_as_jsonb.unwrap_or(false)
+    }
+
+    pub fn rollups(&self) -> &[Rollup] {
+        self.rollups.as_deref().unwrap_or_default()
+    }
+
+    /// The table name without the schema
+    pub fn event_table_name(&self) -> String {
+        self.table_name.clone().unwrap_or_else(|| camel_to_snake(&self.event_name))
+    }
+
+    /// Table and column names must be plain lowercase identifiers so they never need quoting in
+    /// graphql or raw sql, and every column of the table must be unique
+    pub fn validate_naming(&self, inputs: &[ABIInput]) -> Result<(), String> {
+        if let Some(table_name) = &self.table_name {
+            if !is_sql_identifier(table_name) {
+                return Err(format!(
+                    "table_name {} must be lowercase letters, digits and underscores",
+                    table_name
+                ));
+            }
+        }
+
+        let default_columns = generate_column_names_only_with_base_properties(
+            inputs,
+            &StorageEvent { columns: None, ..self.clone() },
+        );
+        for (input_path, column) in self.columns.iter().flatten() {
+            if !default_columns.contains(&input_path_to_column_name(input_path)) {
+                return Err(format!("input {} in columns not found in the event", input_path));
+            }
+            if !is_sql_identifier(column) {
+                return Err(format!(
+                    "column {} must be lowercase letters, digits and underscores",
+                    column
+                ));
+            }
+        }
+
+        for computed_column in self.computed_columns() {
+            computed_column.validate()?;
+        }
+
+        let mut seen = vec!["rindexer_id".to_string()];
+        for column in generate_column_names_only_with_base_properties(inputs, self) {
+            if seen.contains(&column) {
+                return Err(format!("column {} is used more than once", column));
+            }
+            seen.push(column);
+        }
+
+        Ok(())
+    }
+
+    /// The column name of an input from the column name rindexer would give it
+    pub fn column_name(&self, default_column_name: &str) -> String {
+        self.columns
+            .iter()
+            .flatten()
+            .find(|(input_path, _)| input_path_to_column_name(input_path) == default_column_name)
+            .map_or_else(|| default_column_name.to_string(), |(_, column)| column.clone())
+    }
+
+    /// The formatted inputs in the order of the event inputs
+    pub fn formatted_inputs<'a>(
+        &'a self,
+        inputs: &'a [ABIInput],
+    ) -> Vec<(&'a ABIInput, &'a InputFormat)> {
+        let Some(format) = &self.format else {
+            return vec![];
+        };
+
+        inputs
+            .iter()
+            .filter_map(|input| format.get(&input.name).map(|input_format| (input, input_format)))
+            .collect()
+    }
+
+    /// The formatted columns are written after the event inputs
+    pub fn formatted_column_names(&self, inputs: &[ABIInput]) -> Vec<String> {
+        self.formatted_inputs(inputs)
+            .into_iter()
+            .map(|(input, _)| {
+                format!("{}_formatted", self.column_name(&camel_to_snake(&input.name)))
+            })
+            .collect()
+    }
+
+    pub fn computed_columns(&self) -> &[ComputedColumn] {
+        self.computed_columns.as_deref().unwrap_or_default()
+    }
+
+    /// The computed columns are written after the formatted columns
+    pub fn computed_column_names(&self) -> Vec<String> {
+        self.computed_columns().iter().map(|column| column.name.clone()).collect()
+    }
+
+    /// The raw log columns are written after the computed columns
+    pub fn raw_log_column_names(&self) -> Vec<String> {
+        if self.store_raw_log {
+            vec!["topics".to_string(), "data".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractStorage {
+    #[serde(default)]
+    pub events: Vec<StorageEvent>,
+
+    /// Overrides the global postgres partitioning for the contract tables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<PostgresPartitioning>,
+
+    /// Stores the raw log topics and data next to the decoded columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store_raw_log: Option<bool>,
+
+    /// Prunes the rows of the contract event tables once they are older than a block age or time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
+}
+
+impl ContractStorage {
+    pub fn find_event(&self, event_name: &str) -> Option<&StorageEvent> {
+        self.events.iter().find(|e| e.event_name == event_name)
+    }
+
+    pub fn is_store_raw_log(&self) -> bool {
+        self.store_raw_log.unwrap_or(false)
+    }
+
+    pub fn event_conditions(&self, event_name: &str) -> Option<&Vec<Map<String, Value>>> {
+        self.find_event(event_name).and_then(|e| e.conditions.as_ref())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -111,6 +631,18 @@ pub struct Storage {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub csv: Option<CsvDetails>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlite: Option<SqliteDetails>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clickhouse: Option<ClickhouseDetails>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parquet: Option<ParquetDetails>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ndjson: Option<NdjsonDetails>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -156,6 +688,46 @@ impl Storage {
         self.postgres.as_ref().map_or(false, |details| details.drop_each_run.unwrap_or_default())
     }
 
+    pub fn postgres_partitioning(&self) -> Option<&PostgresPartitioning> {
+        self.postgres.as_ref().and_then(|details| details.partitioning.as_ref())
+    }
+
+    pub fn postgres_connection(&self) -> Option<PostgresConnectionDetails> {
+        self.postgres.as_ref().and_then(|details| details.connection.clone())
+    }
+
+    pub fn sqlite_enabled(&self) -> bool {
+        match &self.sqlite {
+            Some(details) => details.enabled,
+            None => false,
+        }
+    }
+
+    pub fn sqlite_drop_each_run(&self) -> bool {
+        let enabled = self.sqlite_enabled();
+        if !enabled {
+            return false;
+        }
+
+        self.sqlite.as_ref().is_some_and(|details| details.drop_each_run.unwrap_or_default())
+    }
+
+    pub fn clickhouse_enabled(&self) -> bool {
+        match &self.clickhouse {
+            Some(details) => details.enabled,
+            None => false,
+        }
+    }
+
+    pub fn clickhouse_drop_each_run(&self) -> bool {
+        let enabled = self.clickhouse_enabled();
+        if !enabled {
+            return false;
+        }
+
+        self.clickhouse.as_ref().is_some_and(|details| details.drop_each_run.unwrap_or_default())
+    }
+
     pub fn csv_enabled(&self) -> bool {
         match &self.csv {
             Some(details) => details.enabled,
@@ -174,6 +746,20 @@ impl Storage {
             .map_or(false, |details| details.disable_create_headers.unwrap_or_default())
     }
 
+    pub fn parquet_enabled(&self) -> bool {
+        match &self.parquet {
+            Some(details) => details.enabled,
+            None => false,
+        }
+    }
+
+    pub fn ndjson_enabled(&self) -> bool {
+        match &self.ndjson {
+            Some(details) => details.enabled,
+            None => false,
+        }
+    }
+
     pub async fn create_relationships_and_indexes(
         &self,
         project_path: &Path,
@@ -213,8 +799,14 @@ impl Storage {
                 drop_last_known_indexes(manifest_name).await?;
 
                 if let Some(indexes) = &storage.indexes {
-                    let indexes_result =
-                        prepare_indexes(project_path, manifest_name, indexes, contracts).await;
+                    let indexes_result = prepare_indexes(
+                        project_path,
+                        manifest_name,
+                        indexes,
+                        contracts,
+                        storage.partitioning.as_ref(),
+                    )
+                    .await;
 
                     match indexes_result {
                         Ok(result) => {
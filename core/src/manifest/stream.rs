@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 use lapin::ExchangeKind;
 use serde::{Deserialize, Deserializer, Serialize};
@@ -29,14 +29,83 @@ pub struct SNSStreamConfig {
     pub topics: Vec<SNSStreamTopicConfig>,
 }
 
+/// Headers rindexer sets on every webhook request which can not be given in `headers`
+const WEBHOOK_RESERVED_HEADERS: [&str; 5] = [
+    "content-type",
+    "x-rindexer-id",
+    "x-rindexer-timestamp",
+    "x-rindexer-signature",
+    "x-rindexer-shared-secret",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookTlsConfig {
+    /// Path to the PEM client certificate
+    pub client_cert: String,
+
+    /// Path to the PKCS#8 PEM private key of the client certificate
+    pub client_key: String,
+
+    /// Path to a PEM CA certificate to trust on top of the system roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WebhookStreamConfig {
     pub endpoint: String,
+
+    /// Signs every request, also sent as is in `x-rindexer-shared-secret` if opted in
     pub shared_secret: String,
+
+    /// Secrets every request is also signed with while receivers move over to `shared_secret`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotating_secrets: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_shared_secret_header: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<WebhookTlsConfig>,
+
     pub networks: Vec<String>,
     pub events: Vec<StreamEvent>,
 }
 
+impl WebhookStreamConfig {
+    pub fn signing_secrets(&self) -> Vec<&str> {
+        std::iter::once(self.shared_secret.as_str())
+            .chain(self.rotating_secrets.iter().flatten().map(|secret| secret.as_str()))
+            .collect()
+    }
+
+    pub fn send_shared_secret_header(&self) -> bool {
+        self.send_shared_secret_header.unwrap_or(false)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.signing_secrets().iter().any(|secret| secret.is_empty()) {
+            return Err(format!("webhook {} secrets can not be empty", self.endpoint));
+        }
+
+        for (name, value) in self.headers.iter().flatten() {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() ||
+                reqwest::header::HeaderValue::from_str(value).is_err()
+            {
+                return Err(format!("webhook {} header {} is not valid", self.endpoint, name));
+            }
+            if WEBHOOK_RESERVED_HEADERS.contains(&name.to_lowercase().as_str()) {
+                return Err(format!("webhook {} header {} is set by rindexer", self.endpoint, name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct ExchangeKindWrapper(pub ExchangeKind);
 
@@ -133,6 +202,189 @@ pub struct KafkaStreamConfig {
     pub topics: Vec<KafkaStreamQueueConfig>,
 }
 
+/// Placeholders a NATS subject or redis key can use to give each event and network its own target
+const STREAM_TARGET_PLACEHOLDERS: [&str; 2] = ["{network}", "{event_name}"];
+
+fn render_stream_target(template: &str, network: &str, event_name: &str) -> String {
+    template.replace("{network}", network).replace("{event_name}", event_name)
+}
+
+/// Errors when any `{...}` other than the placeholders is left in the template
+fn validate_stream_target_placeholders(template: &str) -> Result<(), String> {
+    let rendered = render_stream_target(template, "", "");
+    if rendered.contains(['{', '}']) {
+        return Err(format!(
+            "{} can only use {} as placeholders",
+            template,
+            STREAM_TARGET_PLACEHOLDERS.join(" and ")
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NatsStreamSubjectConfig {
+    /// The subject to publish on, `{network}` and `{event_name}` are replaced for each message
+    pub subject: String,
+    pub networks: Vec<String>,
+    pub events: Vec<StreamEvent>,
+}
+
+impl NatsStreamSubjectConfig {
+    pub fn subject_for(&self, network: &str, event_name: &str) -> String {
+        render_stream_target(&self.subject, network, event_name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NatsStreamConfig {
+    pub url: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials_file: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    pub subjects: Vec<NatsStreamSubjectConfig>,
+}
+
+impl NatsStreamConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.subjects.is_empty() {
+            return Err("No subjects defined in NATS config".to_string());
+        }
+
+        if self.credentials_file.is_some() && self.token.is_some() {
+            return Err("NATS config can only use one of credentials_file or token".to_string());
+        }
+
+        for config in &self.subjects {
+            validate_stream_target_placeholders(&config.subject)
+                .map_err(|e| format!("NATS subject {}", e))?;
+
+            // wildcards are only valid when subscribing
+            let subject = config.subject_for("x", "x");
+            if subject.split('.').any(|token| {
+                token.is_empty() ||
+                    token == "*" ||
+                    token == ">" ||
+                    token.contains(char::is_whitespace)
+            }) {
+                return Err(format!(
+                    "NATS subject {} must be dot separated tokens without wildcards",
+                    config.subject
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedisStreamKeyConfig {
+    /// The stream key to add to, `{network}` and `{event_name}` are replaced for each message
+    pub key: String,
+
+    /// Trims the stream to about this many entries on every add
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<usize>,
+
+    /// Created on the stream from its first entry if it does not exist yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consumer_group: Option<String>,
+
+    pub networks: Vec<String>,
+    pub events: Vec<StreamEvent>,
+}
+
+impl RedisStreamKeyConfig {
+    pub fn key_for(&self, network: &str, event_name: &str) -> String {
+        render_stream_target(&self.key, network, event_name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedisStreamConfig {
+    pub url: String,
+    pub keys: Vec<RedisStreamKeyConfig>,
+}
+
+impl RedisStreamConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.keys.is_empty() {
+            return Err("No keys defined in redis config".to_string());
+        }
+
+        for config in &self.keys {
+            if config.key.is_empty() {
+                return Err("redis key can not be empty".to_string());
+            }
+            validate_stream_target_placeholders(&config.key)
+                .map_err(|e| format!("redis key {}", e))?;
+
+            if config.max_len == Some(0) {
+                return Err(format!("redis key {} max_len must be above 0", config.key));
+            }
+
+            if config.consumer_group.as_ref().is_some_and(|group| group.is_empty()) {
+                return Err(format!("redis key {} consumer_group can not be empty", config.key));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What is sent when a message is over the 8000 byte NOTIFY payload limit
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PostgresNotifyLargePayload {
+    /// The message is stored in a table and its id is notified
+    #[default]
+    Reference,
+
+    /// The message is notified in numbered parts within one transaction
+    Split,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostgresNotifyStreamChannelConfig {
+    pub channel: String,
+    pub networks: Vec<String>,
+    pub events: Vec<StreamEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostgresNotifyStreamConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub large_payloads: Option<PostgresNotifyLargePayload>,
+
+    pub channels: Vec<PostgresNotifyStreamChannelConfig>,
+}
+
+impl PostgresNotifyStreamConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.channels.is_empty() {
+            return Err("No channels defined in postgres_notify config".to_string());
+        }
+
+        for config in &self.channels {
+            // channels are identifiers so postgres truncates anything longer
+            if config.channel.is_empty() || config.channel.len() > 63 {
+                return Err(format!(
+                    "postgres_notify channel {} must be between 1 and 63 bytes",
+                    config.channel
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StreamsConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -146,12 +398,37 @@ pub struct StreamsConfig {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub kafka: Option<KafkaStreamConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postgres_notify: Option<PostgresNotifyStreamConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nats: Option<NatsStreamConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis: Option<RedisStreamConfig>,
 }
 
 impl StreamsConfig {
     pub fn validate(&self) -> Result<(), String> {
+        for webhook in self.webhooks.iter().flatten() {
+            webhook.validate()?;
+        }
+
         if let Some(rabbitmq) = &self.rabbitmq {
-            return rabbitmq.validate();
+            rabbitmq.validate()?;
+        }
+
+        if let Some(postgres_notify) = &self.postgres_notify {
+            postgres_notify.validate()?;
+        }
+
+        if let Some(nats) = &self.nats {
+            nats.validate()?;
+        }
+
+        if let Some(redis) = &self.redis {
+            redis.validate()?;
         }
 
         Ok(())
@@ -167,6 +444,12 @@ impl StreamsConfig {
             path.push_str("webhooks_");
         } else if self.kafka.is_some() {
             path.push_str("kafka_");
+        } else if self.postgres_notify.is_some() {
+            path.push_str("postgres_notify_");
+        } else if self.nats.is_some() {
+            path.push_str("nats_");
+        } else if self.redis.is_some() {
+            path.push_str("redis_");
         }
 
         path.trim_end_matches('_').to_string()
@@ -187,3 +470,87 @@ impl StreamsConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nats_subjects() {
+        let config = |subject: &str| NatsStreamConfig {
+            url: "nats://localhost:4222".to_string(),
+            credentials_file: None,
+            token: None,
+            subjects: vec![NatsStreamSubjectConfig {
+                subject: subject.to_string(),
+                networks: vec!["ethereum".to_string()],
+                events: vec![],
+            }],
+        };
+
+        let valid = config("rindexer.{network}.{event_name}");
+        assert!(valid.validate().is_ok());
+        assert_eq!(
+            valid.subjects[0].subject_for("ethereum", "Transfer"),
+            "rindexer.ethereum.Transfer"
+        );
+
+        assert!(config("rindexer.transfers").validate().is_ok());
+        assert!(config("rindexer.*").validate().is_err());
+        assert!(config("rindexer.>").validate().is_err());
+        assert!(config("rindexer..transfers").validate().is_err());
+        assert!(config("rindexer.{contract}").validate().is_err());
+        assert!(config("rindexer transfers").validate().is_err());
+    }
+
+    #[test]
+    fn test_webhook_headers() {
+        let mut config: WebhookStreamConfig = serde_yaml::from_str(
+            r#"
+            endpoint: https://example.com/webhook
+            shared_secret: whsec_new
+            rotating_secrets:
+              - whsec_old
+            headers:
+              x-api-key: abc
+            networks:
+              - ethereum
+            events:
+              - event_name: Transfer
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.signing_secrets(), ["whsec_new", "whsec_old"]);
+        assert!(!config.send_shared_secret_header());
+
+        config.headers =
+            Some(BTreeMap::from([("X-Rindexer-Signature".to_string(), "v1=abc".to_string())]));
+        assert!(config.validate().is_err());
+
+        config.headers = Some(BTreeMap::from([("bad header".to_string(), "abc".to_string())]));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_redis_keys() {
+        let config = |key: &str, max_len: Option<usize>| RedisStreamConfig {
+            url: "redis://localhost:6379".to_string(),
+            keys: vec![RedisStreamKeyConfig {
+                key: key.to_string(),
+                max_len,
+                consumer_group: None,
+                networks: vec!["ethereum".to_string()],
+                events: vec![],
+            }],
+        };
+
+        let valid = config("rindexer:{network}:{event_name}", Some(10000));
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.keys[0].key_for("base", "Transfer"), "rindexer:base:Transfer");
+
+        assert!(config("rindexer:{event}", None).validate().is_err());
+        assert!(config("", None).validate().is_err());
+        assert!(config("rindexer", Some(0)).validate().is_err());
+    }
+}
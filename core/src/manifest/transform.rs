@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// What happens to a batch when the transform endpoint fails or times out
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TransformFailurePolicy {
+    /// The batch is stored and published as it was decoded
+    Skip,
+
+    /// The call is retried with a backoff up to `max_retries` times before halting
+    Retry,
+
+    /// The batch is retried and the event does not progress so nothing is stored without being
+    /// transformed
+    #[default]
+    Halt,
+}
+
+/// An endpoint every batch of an event is sent to before it is stored and published, it answers
+/// with the events to keep
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventTransform {
+    pub endpoint: String,
+
+    /// Sent in the `x-rindexer-shared-secret` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_secret: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<TransformFailurePolicy>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+}
+
+impl EventTransform {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(10_000))
+    }
+
+    pub fn on_failure(&self) -> TransformFailurePolicy {
+        self.on_failure.unwrap_or_default()
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(3)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.endpoint.starts_with("http://") && !self.endpoint.starts_with("https://") {
+            return Err(format!("endpoint {} must be an http or https url", self.endpoint));
+        }
+
+        if self.timeout_ms == Some(0) {
+            return Err("timeout_ms must be greater than 0".to_string());
+        }
+
+        if self.max_retries.is_some() && self.on_failure() != TransformFailurePolicy::Retry {
+            return Err("max_retries can only be set when on_failure is retry".to_string());
+        }
+
+        Ok(())
+    }
+}
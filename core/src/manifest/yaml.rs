@@ -6,12 +6,18 @@ use std::{
 };
 
 use regex::{Captures, Regex};
+use serde_json::{Map, Value};
 use tracing::error;
 
 use crate::{
     abi::ABIItem,
+    event::validate_conditions,
     helpers::replace_env_variable_to_raw_name,
-    manifest::core::{Manifest, ProjectType},
+    indexer::script::ScriptHandler,
+    manifest::{
+        contract::Contract,
+        core::{Manifest, ProjectType},
+    },
 };
 
 pub const YAML_CONFIG_NAME: &str = "rindexer.yaml";
@@ -59,6 +65,179 @@ pub enum ValidateManifestError {
 
     #[error("Streams config is invalid: {0}")]
     StreamsConfigValidationError(String),
+
+    #[error("Storage event {0} not found in ABI for contract {1}")]
+    StorageEventNotFoundInABI(String, String),
+
+    #[error("Invalid conditions for event {0} on contract {1}: {2}")]
+    InvalidConditions(String, String, String),
+
+    #[error("Storage event {0} for contract {1} uses tuples_as_jsonb which is only supported in no-code projects")]
+    TuplesAsJsonbNotSupportedInRustProject(String, String),
+
+    #[error("Storage event {0} for contract {1} uses format which is only supported in no-code projects")]
+    FormatNotSupportedInRustProject(String, String),
+
+    #[error("Storage event {0} for contract {1} uses conditions which are only supported in no-code projects")]
+    ConditionsNotSupportedInRustProject(String, String),
+
+    #[error("Contract {0} uses store_raw_log which is only supported in no-code projects")]
+    StoreRawLogNotSupportedInRustProject(String),
+
+    #[error("Invalid format for event {0} on contract {1}: {2}")]
+    InvalidInputFormat(String, String, String),
+
+    #[error("Invalid naming for event {0} on contract {1}: {2}")]
+    InvalidStorageNaming(String, String, String),
+
+    #[error("Table name {0} is used by more than one event on contract {1}")]
+    DuplicateEventTableName(String, String),
+
+    #[error(
+        "Computed columns on event {0} of contract {1} are only supported in no-code projects"
+    )]
+    ComputedColumnsNotSupportedInRustProject(String, String),
+
+    #[error("Rollups on event {0} of contract {1} are only supported in no-code projects")]
+    RollupsNotSupportedInRustProject(String, String),
+
+    #[error("Rollups are stored in postgres so postgres storage must be enabled")]
+    RollupsRequirePostgres,
+
+    #[error("Invalid rollup {0} for event {1} on contract {2}: {3}")]
+    InvalidRollup(String, String, String, String),
+
+    #[error("Rollup {0} on contract {1} uses a table name which is already taken")]
+    DuplicateRollupTableName(String, String),
+
+    #[error("Scripts on event {0} of contract {1} are only supported in no-code projects")]
+    ScriptsNotSupportedInRustProject(String, String),
+
+    #[error("Script tables are stored in postgres so postgres storage must be enabled")]
+    ScriptTablesRequirePostgres,
+
+    #[error("Invalid script for event {0} on contract {1}: {2}")]
+    InvalidScript(String, String, String),
+
+    #[error("Script table {0} on contract {1} uses a table name which is already taken")]
+    DuplicateScriptTableName(String, String),
+
+    #[error("Transforms on event {0} of contract {1} are only supported in no-code projects")]
+    TransformsNotSupportedInRustProject(String, String),
+
+    #[error("Invalid transform for event {0} on contract {1}: {2}")]
+    InvalidTransform(String, String, String),
+
+    #[error(
+        "Retention on contract {0} prunes postgres tables so postgres storage must be enabled"
+    )]
+    RetentionRequiresPostgres(String),
+
+    #[error("Invalid retention for contract {0}: {1}")]
+    InvalidRetention(String, String),
+
+    #[error("Entities are only supported in no-code projects")]
+    EntitiesNotSupportedInRustProject,
+
+    #[error("Entities are stored in postgres so postgres storage must be enabled")]
+    EntitiesRequirePostgres,
+
+    #[error("Entity {0} is defined more than once")]
+    DuplicateEntityName(String),
+
+    #[error("Invalid entity {0}: {1}")]
+    InvalidEntity(String, String),
+
+    #[error("Sqlite storage is only supported in no-code projects")]
+    SqliteNotSupportedInRustProject,
+
+    #[error("Clickhouse storage is only supported in no-code projects")]
+    ClickhouseNotSupportedInRustProject,
+
+    #[error("Parquet storage is only supported in no-code projects")]
+    ParquetNotSupportedInRustProject,
+
+    #[error("Ndjson storage is only supported in no-code projects")]
+    NdjsonNotSupportedInRustProject,
+
+    #[error("Parquet storage can not be used with postgres, sqlite, clickhouse or csv storage as they track the last synced block separately")]
+    ParquetWithOtherStorage,
+
+    #[error("The csv delimiter must be a single ascii character: {0}")]
+    InvalidCsvDelimiter(char),
+
+    #[error("Postgres relationships are not supported on partitioned tables, contract {0} is partitioned")]
+    RelationshipOnPartitionedContract(String),
+}
+
+/// All the conditions defined on a contract with the event they apply to
+fn contract_conditions(contract: &Contract) -> Vec<(&String, &Vec<Map<String, Value>>)> {
+    let mut conditions = vec![];
+
+    if let Some(storage) = &contract.storage {
+        conditions.extend(
+            storage.events.iter().filter_map(|e| e.conditions.as_ref().map(|c| (&e.event_name, c))),
+        );
+    }
+
+    if let Some(streams) = &contract.streams {
+        let mut stream_events = vec![];
+        if let Some(sns) = &streams.sns {
+            stream_events.extend(sns.topics.iter().flat_map(|t| &t.events));
+        }
+        if let Some(webhooks) = &streams.webhooks {
+            stream_events.extend(webhooks.iter().flat_map(|w| &w.events));
+        }
+        if let Some(rabbitmq) = &streams.rabbitmq {
+            stream_events.extend(rabbitmq.exchanges.iter().flat_map(|e| &e.events));
+        }
+        if let Some(kafka) = &streams.kafka {
+            stream_events.extend(kafka.topics.iter().flat_map(|t| &t.events));
+        }
+        if let Some(postgres_notify) = &streams.postgres_notify {
+            stream_events.extend(postgres_notify.channels.iter().flat_map(|c| &c.events));
+        }
+        if let Some(nats) = &streams.nats {
+            stream_events.extend(nats.subjects.iter().flat_map(|s| &s.events));
+        }
+        if let Some(redis) = &streams.redis {
+            stream_events.extend(redis.keys.iter().flat_map(|k| &k.events));
+        }
+        conditions.extend(
+            stream_events
+                .into_iter()
+                .filter_map(|e| e.conditions.as_ref().map(|c| (&e.event_name, c))),
+        );
+    }
+
+    if let Some(chat) = &contract.chat {
+        if let Some(telegram) = &chat.telegram {
+            conditions.extend(
+                telegram
+                    .iter()
+                    .flat_map(|t| &t.messages)
+                    .filter_map(|m| m.conditions.as_ref().map(|c| (&m.event_name, c))),
+            );
+        }
+        if let Some(discord) = &chat.discord {
+            conditions.extend(
+                discord
+                    .iter()
+                    .flat_map(|d| &d.messages)
+                    .filter_map(|m| m.conditions.as_ref().map(|c| (&m.event_name, c))),
+            );
+        }
+        if let Some(slack) = &chat.slack {
+            conditions.extend(
+                slack
+                    .iter()
+                    .flat_map(|s| &s.messages)
+                    .filter_map(|m| m.conditions.as_ref().map(|c| (&m.event_name, c))),
+            );
+        }
+    }
+
+    conditions
 }
 
 fn validate_manifest(
@@ -131,35 +310,334 @@ fn validate_manifest(
             // TODO - validate the events all exist in the contract ABIs
         }
 
-        if let Some(streams) = &contract.streams {
-            if let Err(e) = streams.validate() {
-                return Err(ValidateManifestError::StreamsConfigValidationError(e));
+        if let Some(storage) = &contract.storage {
+            let mut table_names: Vec<(String, &str)> = vec![];
+            for event in events.iter().filter(|e| e.type_ == "event") {
+                let table_name = contract.event_table_name(&event.name);
+                if table_names.iter().any(|(name, event_name)| {
+                    *name == table_name && *event_name != event.name.as_str()
+                }) {
+                    return Err(ValidateManifestError::DuplicateEventTableName(
+                        table_name,
+                        contract.name.clone(),
+                    ));
+                }
+                table_names.push((table_name, &event.name));
+
+                if let Err(e) = contract.storage_event(&event.name).validate_naming(&event.inputs) {
+                    return Err(ValidateManifestError::InvalidStorageNaming(
+                        event.name.clone(),
+                        contract.name.clone(),
+                        e,
+                    ));
+                }
             }
-        }
-    }
 
-    if let Some(postgres) = &manifest.storage.postgres {
-        if let Some(relationships) = &postgres.relationships {
-            for relationship in relationships {
-                if !manifest.contracts.iter().any(|c| c.name == relationship.contract_name) {
-                    return Err(ValidateManifestError::RelationshipContractNotFound(
-                        relationship.contract_name.clone(),
+            if storage.is_store_raw_log() && manifest.project_type == ProjectType::Rust {
+                return Err(ValidateManifestError::StoreRawLogNotSupportedInRustProject(
+                    contract.name.clone(),
+                ));
+            }
+
+            if let Some(retention) = &storage.retention {
+                if !manifest.storage.postgres_enabled() {
+                    return Err(ValidateManifestError::RetentionRequiresPostgres(
+                        contract.name.clone(),
+                    ));
+                }
+
+                if let Err(e) = retention.validate(
+                    contract.postgres_partitioning(manifest.storage.postgres_partitioning()),
+                ) {
+                    return Err(ValidateManifestError::InvalidRetention(contract.name.clone(), e));
+                }
+            }
+
+            for storage_event in &storage.events {
+                if !events.iter().any(|e| e.name == storage_event.event_name && e.type_ == "event")
+                {
+                    return Err(ValidateManifestError::StorageEventNotFoundInABI(
+                        storage_event.event_name.clone(),
+                        contract.name.clone(),
+                    ));
+                }
+
+                if storage_event.tuples_as_jsonb.unwrap_or(false) &&
+                    manifest.project_type == ProjectType::Rust
+                {
+                    return Err(ValidateManifestError::TuplesAsJsonbNotSupportedInRustProject(
+                        storage_event.event_name.clone(),
+                        contract.name.clone(),
+                    ));
+                }
+
+                if storage_event.conditions.is_some() && manifest.project_type == ProjectType::Rust
+                {
+                    return Err(ValidateManifestError::ConditionsNotSupportedInRustProject(
+                        storage_event.event_name.clone(),
+                        contract.name.clone(),
+                    ));
+                }
+
+                if let Some(format) = &storage_event.format {
+                    if manifest.project_type == ProjectType::Rust {
+                        return Err(ValidateManifestError::FormatNotSupportedInRustProject(
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
+                        ));
+                    }
+
+                    let inputs = events
+                        .iter()
+                        .find(|e| e.name == storage_event.event_name && e.type_ == "event")
+                        .map_or(&[][..], |e| &e.inputs);
+                    for (input_name, input_format) in format {
+                        if let Err(e) = input_format.validate(input_name, inputs) {
+                            return Err(ValidateManifestError::InvalidInputFormat(
+                                storage_event.event_name.clone(),
+                                contract.name.clone(),
+                                e,
+                            ));
+                        }
+                    }
+                }
+
+                if storage_event.computed_columns.is_some() &&
+                    manifest.project_type == ProjectType::Rust
+                {
+                    return Err(ValidateManifestError::ComputedColumnsNotSupportedInRustProject(
+                        storage_event.event_name.clone(),
+                        contract.name.clone(),
                     ));
                 }
 
-                for foreign_key in &relationship.foreign_keys {
-                    if !manifest.contracts.iter().any(|c| c.name == foreign_key.contract_name) {
-                        return Err(ValidateManifestError::RelationshipForeignKeyContractNotFound(
-                            foreign_key.contract_name.clone(),
+                if storage_event.rollups.is_some() {
+                    if manifest.project_type == ProjectType::Rust {
+                        return Err(ValidateManifestError::RollupsNotSupportedInRustProject(
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
                         ));
                     }
+
+                    if !manifest.storage.postgres_enabled() {
+                        return Err(ValidateManifestError::RollupsRequirePostgres);
+                    }
                 }
 
-                // TODO - Add validation for the event names and event inputs match the ABIs
+                let inputs = events
+                    .iter()
+                    .find(|e| e.name == storage_event.event_name && e.type_ == "event")
+                    .map_or(&[][..], |e| &e.inputs);
+                for rollup in storage_event.rollups() {
+                    if let Err(e) = rollup.validate(inputs) {
+                        return Err(ValidateManifestError::InvalidRollup(
+                            rollup.name.clone(),
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
+                            e,
+                        ));
+                    }
+
+                    // rollup tables live in the contract schema next to the event tables
+                    if table_names.iter().any(|(name, _)| *name == rollup.name) {
+                        return Err(ValidateManifestError::DuplicateRollupTableName(
+                            rollup.name.clone(),
+                            contract.name.clone(),
+                        ));
+                    }
+                    table_names.push((rollup.name.clone(), &storage_event.event_name));
+                }
+
+                if let Some(script) = &storage_event.script {
+                    if manifest.project_type == ProjectType::Rust {
+                        return Err(ValidateManifestError::ScriptsNotSupportedInRustProject(
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
+                        ));
+                    }
+
+                    if !script.tables().is_empty() && !manifest.storage.postgres_enabled() {
+                        return Err(ValidateManifestError::ScriptTablesRequirePostgres);
+                    }
+
+                    let invalid_script = |e: String| {
+                        ValidateManifestError::InvalidScript(
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
+                            e,
+                        )
+                    };
+                    ScriptHandler::new(&project_path.join(&script.path))
+                        .map_err(|e| invalid_script(e.to_string()))?;
+
+                    for table in script.tables() {
+                        table.validate().map_err(invalid_script)?;
+
+                        // script tables live in the contract schema next to the event tables
+                        if table_names.iter().any(|(name, _)| *name == table.name) {
+                            return Err(ValidateManifestError::DuplicateScriptTableName(
+                                table.name.clone(),
+                                contract.name.clone(),
+                            ));
+                        }
+                        table_names.push((table.name.clone(), &storage_event.event_name));
+                    }
+                }
+
+                if let Some(transform) = &storage_event.transform {
+                    if manifest.project_type == ProjectType::Rust {
+                        return Err(ValidateManifestError::TransformsNotSupportedInRustProject(
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
+                        ));
+                    }
+
+                    if let Err(e) = transform.validate() {
+                        return Err(ValidateManifestError::InvalidTransform(
+                            storage_event.event_name.clone(),
+                            contract.name.clone(),
+                            e,
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (event_name, conditions) in contract_conditions(contract) {
+            if let Err(e) = validate_conditions(conditions) {
+                return Err(ValidateManifestError::InvalidConditions(
+                    event_name.clone(),
+                    contract.name.clone(),
+                    e.to_string(),
+                ));
+            }
+        }
+
+        if let Some(streams) = &contract.streams {
+            if let Err(e) = streams.validate() {
+                return Err(ValidateManifestError::StreamsConfigValidationError(e));
             }
         }
     }
 
+    if manifest.storage.sqlite_enabled() && manifest.project_type == ProjectType::Rust {
+        return Err(ValidateManifestError::SqliteNotSupportedInRustProject);
+    }
+
+    if manifest.storage.clickhouse_enabled() && manifest.project_type == ProjectType::Rust {
+        return Err(ValidateManifestError::ClickhouseNotSupportedInRustProject);
+    }
+
+    if manifest.storage.parquet_enabled() && manifest.project_type == ProjectType::Rust {
+        return Err(ValidateManifestError::ParquetNotSupportedInRustProject);
+    }
+
+    // an unfinished parquet file rewinds its own last synced block, another storage would index
+    // the rewound blocks again into rows it already has
+    if manifest.storage.parquet_enabled() &&
+        (manifest.storage.postgres_enabled() ||
+            manifest.storage.sqlite_enabled() ||
+            manifest.storage.clickhouse_enabled() ||
+            manifest.storage.csv_enabled())
+    {
+        return Err(ValidateManifestError::ParquetWithOtherStorage);
+    }
+
+    if manifest.storage.ndjson_enabled() && manifest.project_type == ProjectType::Rust {
+        return Err(ValidateManifestError::NdjsonNotSupportedInRustProject);
+    }
+
+    if let Some(delimiter) = manifest.storage.csv.as_ref().and_then(|csv| csv.delimiter) {
+        if !delimiter.is_ascii() {
+            return Err(ValidateManifestError::InvalidCsvDelimiter(delimiter));
+        }
+    }
+
+    if let Some(entities) = &manifest.entities {
+        if manifest.project_type == ProjectType::Rust {
+            return Err(ValidateManifestError::EntitiesNotSupportedInRustProject);
+        }
+
+        if !entities.is_empty() && !manifest.storage.postgres_enabled() {
+            return Err(ValidateManifestError::EntitiesRequirePostgres);
+        }
+
+        let mut contract_abi_items = vec![];
+        for contract in &manifest.contracts {
+            let abi_items = ABIItem::read_abi_items(project_path, contract).map_err(|e| {
+                ValidateManifestError::InvalidABI(contract.name.clone(), e.to_string())
+            })?;
+            contract_abi_items.push((contract.name.as_str(), abi_items));
+        }
+        let event_inputs = |contract_name: &str, event_name: &str| {
+            contract_abi_items
+                .iter()
+                .find(|(name, _)| *name == contract_name)
+                .and_then(|(_, abi_items)| {
+                    abi_items.iter().find(|item| item.type_ == "event" && item.name == event_name)
+                })
+                .map(|item| item.inputs.as_slice())
+        };
+
+        for (index, entity) in entities.iter().enumerate() {
+            if entities[..index].iter().any(|other| other.name == entity.name) {
+                return Err(ValidateManifestError::DuplicateEntityName(entity.name.clone()));
+            }
+
+            if let Err(e) = entity.validate(event_inputs) {
+                return Err(ValidateManifestError::InvalidEntity(entity.name.clone(), e));
+            }
+        }
+    }
+
+    let relationships = manifest
+        .storage
+        .postgres
+        .iter()
+        .flat_map(|postgres| postgres.relationships.iter().flatten())
+        .chain(
+            manifest.storage.sqlite.iter().flat_map(|sqlite| sqlite.relationships.iter().flatten()),
+        );
+
+    // unique constraints on a partitioned table must include the partition key
+    let partitioned_contracts: Vec<&String> = manifest
+        .contracts
+        .iter()
+        .filter(|c| c.postgres_partitioning(manifest.storage.postgres_partitioning()).is_some())
+        .map(|c| &c.name)
+        .collect();
+    for relationship in
+        manifest.storage.postgres.iter().flat_map(|p| p.relationships.iter().flatten())
+    {
+        let contract_names = std::iter::once(&relationship.contract_name)
+            .chain(relationship.foreign_keys.iter().map(|f| &f.contract_name));
+        for contract_name in contract_names {
+            if partitioned_contracts.contains(&contract_name) {
+                return Err(ValidateManifestError::RelationshipOnPartitionedContract(
+                    contract_name.clone(),
+                ));
+            }
+        }
+    }
+
+    for relationship in relationships {
+        if !manifest.contracts.iter().any(|c| c.name == relationship.contract_name) {
+            return Err(ValidateManifestError::RelationshipContractNotFound(
+                relationship.contract_name.clone(),
+            ));
+        }
+
+        for foreign_key in &relationship.foreign_keys {
+            if !manifest.contracts.iter().any(|c| c.name == foreign_key.contract_name) {
+                return Err(ValidateManifestError::RelationshipForeignKeyContractNotFound(
+                    foreign_key.contract_name.clone(),
+                ));
+            }
+        }
+
+        // TODO - Add validation for the event names and event inputs match the ABIs
+    }
+
     Ok(())
 }
 
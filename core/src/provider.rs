@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -7,8 +8,9 @@ use ethers::{
     middleware::Middleware,
     prelude::Log,
     providers::{Http, Provider, ProviderError, RetryClient, RetryClientBuilder},
-    types::{Block, BlockNumber, H256, U256, U64},
+    types::{Address, Block, BlockNumber, Bytes, TransactionRequest, H256, U256, U64},
 };
+use futures::{stream, StreamExt, TryStreamExt};
 use reqwest::header::HeaderMap;
 use thiserror::Error;
 use tokio::sync::Mutex;
@@ -16,10 +18,18 @@ use url::Url;
 
 use crate::{event::RindexerEventFilter, manifest::core::Manifest};
 
+/// How many blocks are fetched at once when reading block timestamps
+const BLOCK_TIMESTAMP_CONCURRENCY: usize = 10;
+
+/// How long a token whose `decimals()` call failed is skipped before it is called again
+const FAILED_TOKEN_DECIMALS_TTL: Duration = Duration::from_secs(600);
+
 #[derive(Debug)]
 pub struct JsonRpcCachedProvider {
     provider: Arc<Provider<RetryClient<Http>>>,
     cache: Mutex<Option<(Instant, Arc<Block<H256>>)>>,
+    /// `None` when the call failed, kept with the time it was read so failures are retried
+    token_decimals: Mutex<HashMap<Address, (Option<u8>, Instant)>>,
     pub max_block_range: Option<U64>,
 }
 
@@ -28,6 +38,7 @@ impl JsonRpcCachedProvider {
         JsonRpcCachedProvider {
             provider: Arc::new(provider),
             cache: Mutex::new(None),
+            token_decimals: Mutex::new(HashMap::new()),
             max_block_range,
         }
     }
@@ -66,6 +77,79 @@ impl JsonRpcCachedProvider {
         self.provider.get_chainid().await
     }
 
+    /// Calls `decimals()` on the token, the decimals of a token never change so each token is only
+    /// ever called once, a failed call is not repeated until `FAILED_TOKEN_DECIMALS_TTL` passed
+    pub async fn get_token_decimals(&self, token: Address) -> Result<u8, ProviderError> {
+        match self.token_decimals.lock().await.get(&token) {
+            Some((Some(decimals), _)) => return Ok(*decimals),
+            Some((None, failed_at)) if failed_at.elapsed() < FAILED_TOKEN_DECIMALS_TTL => {
+                return Err(ProviderError::CustomError(format!(
+                    "{:?} failed to return decimals recently, it is called again in {}s",
+                    token,
+                    (FAILED_TOKEN_DECIMALS_TTL - failed_at.elapsed()).as_secs()
+                )));
+            }
+            _ => {}
+        }
+
+        let result = self.call_token_decimals(token).await;
+        self.token_decimals
+            .lock()
+            .await
+            .insert(token, (result.as_ref().ok().copied(), Instant::now()));
+
+        result
+    }
+
+    async fn call_token_decimals(&self, token: Address) -> Result<u8, ProviderError> {
+        // keccak256("decimals()")[..4]
+        let call =
+            TransactionRequest::new().to(token).data(Bytes::from_static(&[0x31, 0x3c, 0xe5, 0x67]));
+        let result = self.provider.call(&call.into(), None).await?;
+
+        if result.len() < 32 {
+            return Err(ProviderError::CustomError(format!(
+                "{:?} did not return decimals, is it a token contract?",
+                token
+            )));
+        }
+        let decimals = U256::from_big_endian(&result[..32]);
+        if decimals > U256::from(u8::MAX) {
+            return Err(ProviderError::CustomError(format!(
+                "{:?} returned invalid decimals {}",
+                token, decimals
+            )));
+        }
+
+        Ok(decimals.as_u32() as u8)
+    }
+
+    /// The timestamp of a block, used to bucket events by time
+    pub async fn get_block_timestamp(&self, block_number: U64) -> Result<U256, ProviderError> {
+        match self.provider.get_block(block_number).await? {
+            Some(block) => Ok(block.timestamp),
+            None => Err(ProviderError::CustomError(format!("Block {} not found", block_number))),
+        }
+    }
+
+    /// The timestamps of the distinct blocks given, a few blocks are fetched at a time
+    pub async fn get_block_timestamps(
+        &self,
+        block_numbers: &[U64],
+    ) -> Result<HashMap<U64, U256>, ProviderError> {
+        let mut block_numbers = block_numbers.to_vec();
+        block_numbers.sort();
+        block_numbers.dedup();
+
+        let timestamps: Vec<U256> = stream::iter(block_numbers.clone())
+            .map(|block_number| self.get_block_timestamp(block_number))
+            .buffered(BLOCK_TIMESTAMP_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        Ok(block_numbers.into_iter().zip(timestamps).collect())
+    }
+
     pub fn get_inner_provider(&self) -> Arc<Provider<RetryClient<Http>>> {
         Arc::clone(&self.provider)
     }
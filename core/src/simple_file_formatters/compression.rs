@@ -0,0 +1,80 @@
+use std::{
+    fs,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCompression {
+    Gzip,
+    Zstd,
+}
+
+impl FileCompression {
+    fn extension(&self) -> &'static str {
+        match self {
+            FileCompression::Gzip => "gz",
+            FileCompression::Zstd => "zst",
+        }
+    }
+}
+
+/// Compresses a closed file next to it and removes the original, the compressed file is written
+/// under a temp name first so a partial file is never left with the final name
+pub fn compress_file(path: &Path, compression: FileCompression) -> io::Result<PathBuf> {
+    let compressed_path = PathBuf::from(format!("{}.{}", path.display(), compression.extension()));
+    let temp_path = PathBuf::from(format!("{}.tmp", compressed_path.display()));
+
+    let mut source = File::open(path)?;
+    let target = File::create(&temp_path)?;
+    let target = match compression {
+        FileCompression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(target, flate2::Compression::default());
+            io::copy(&mut source, &mut encoder)?;
+            encoder.finish()?
+        }
+        FileCompression::Zstd => {
+            let mut encoder = zstd::Encoder::new(target, 0)?;
+            io::copy(&mut source, &mut encoder)?;
+            encoder.finish()?
+        }
+    };
+    target.sync_all()?;
+
+    fs::rename(&temp_path, &compressed_path)?;
+    fs::remove_file(path)?;
+
+    Ok(compressed_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_compress_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("events.csv");
+
+        for compression in [FileCompression::Gzip, FileCompression::Zstd] {
+            fs::write(&path, "a,b\n1,2\n").unwrap();
+            let compressed_path = compress_file(&path, compression).unwrap();
+            assert!(!path.exists());
+
+            let file = File::open(&compressed_path).unwrap();
+            let mut content = String::new();
+            match compression {
+                FileCompression::Gzip => {
+                    flate2::read::GzDecoder::new(file).read_to_string(&mut content).unwrap();
+                }
+                FileCompression::Zstd => {
+                    zstd::Decoder::new(file).unwrap().read_to_string(&mut content).unwrap();
+                }
+            };
+            assert_eq!(content, "a,b\n1,2\n");
+        }
+    }
+}
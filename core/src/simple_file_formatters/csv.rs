@@ -1,75 +1,326 @@
 use std::{
+    collections::BTreeMap,
+    fs,
     fs::File,
-    path::{Path, PathBuf},
-    sync::Arc,
+    path::PathBuf,
+    sync::{Arc, Mutex},
 };
 
-use csv::Writer;
-use tokio::sync::Mutex;
+use csv::{QuoteStyle, ReaderBuilder, Writer, WriterBuilder};
+use ethers::types::U64;
+use tracing::info;
 
+use crate::simple_file_formatters::compression::{compress_file, FileCompression};
+
+pub struct CsvFileSettings {
+    pub delimiter: u8,
+    pub quote_style: QuoteStyle,
+    /// Written at the top of every new file, needed when files are rotated
+    pub header: Option<Vec<String>>,
+    pub max_blocks_per_file: Option<u64>,
+    pub max_file_size_bytes: Option<u64>,
+    pub compression: Option<FileCompression>,
+    /// Sync the file to disk after every write so the last synced block never gets ahead of it
+    pub fsync: bool,
+}
+
+impl Default for CsvFileSettings {
+    fn default() -> Self {
+        CsvFileSettings {
+            delimiter: b',',
+            quote_style: QuoteStyle::Necessary,
+            header: None,
+            max_blocks_per_file: None,
+            max_file_size_bytes: None,
+            compression: None,
+            fsync: false,
+        }
+    }
+}
+
+struct CsvAppenderState {
+    path: PathBuf,
+    settings: CsvFileSettings,
+    writer: Option<Writer<File>>,
+    /// The first and last block written per network to the open file
+    networks: BTreeMap<String, (U64, U64)>,
+}
+
+impl CsvAppenderState {
+    /// The network and block of the first row in a file left from a previous run
+    fn read_first_row_block(&self) -> Option<(String, U64)> {
+        let header = self.settings.header.as_ref()?;
+        let network_index = header.iter().position(|h| h == "network")?;
+        let block_number_index = header.iter().position(|h| h == "block_number")?;
+
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.settings.delimiter)
+            .has_headers(true)
+            .from_path(&self.path)
+            .ok()?;
+        let row = reader.records().next()?.ok()?;
+
+        Some((
+            row.get(network_index)?.to_string(),
+            U64::from_dec_str(row.get(block_number_index)?).ok()?,
+        ))
+    }
+
+    fn writer(&mut self) -> Result<&mut Writer<File>, csv::Error> {
+        if self.writer.is_none() {
+            let new_file = !fs::metadata(&self.path).is_ok_and(|m| m.len() > 0);
+            if !new_file {
+                if let Some((network, block)) = self.read_first_row_block() {
+                    self.networks.insert(network, (block, block));
+                }
+            }
+
+            let file = File::options().create(true).append(true).open(&self.path)?;
+            let mut writer = WriterBuilder::new()
+                .delimiter(self.settings.delimiter)
+                .quote_style(self.settings.quote_style)
+                .from_writer(file);
+
+            if new_file {
+                if let Some(header) = &self.settings.header {
+                    writer.write_record(header)?;
+                }
+            }
+
+            self.writer = Some(writer);
+        }
+
+        Ok(self.writer.as_mut().expect("writer is set above"))
+    }
+
+    fn write(&mut self, records: Vec<Vec<String>>) -> Result<(), csv::Error> {
+        let fsync = self.settings.fsync;
+        let writer = self.writer()?;
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        // flushed before the last synced block moves past these rows
+        writer.flush()?;
+        if fsync {
+            writer.get_ref().sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    fn should_rotate(&self) -> Result<bool, csv::Error> {
+        if let Some(max) = self.settings.max_file_size_bytes {
+            if fs::metadata(&self.path)?.len() >= max {
+                return Ok(true);
+            }
+        }
+
+        Ok(self.settings.max_blocks_per_file.is_some_and(|max| {
+            self.networks.values().any(|(from, to)| (*to - *from).as_u64() + 1 >= max)
+        }))
+    }
+
+    fn rotate(&mut self) -> Result<(), csv::Error> {
+        let Some(writer) = self.writer.take() else {
+            return Ok(());
+        };
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        let networks = std::mem::take(&mut self.networks);
+        let from_block = networks.values().map(|(from, _)| *from).min().unwrap_or_default();
+        let to_block = networks.values().map(|(_, to)| *to).max().unwrap_or_default();
+
+        let stem = self.path.with_extension("");
+        let mut path = PathBuf::from(format!("{}-{}-{}.csv", stem.display(), from_block, to_block));
+        fs::rename(&self.path, &path)?;
+
+        if let Some(compression) = self.settings.compression {
+            path = compress_file(&path, compression)?;
+        }
+
+        info!("Rotated csv file to {}", path.display());
+
+        Ok(())
+    }
+}
+
+/// Keeps a buffered writer open to the csv file, when a block range is given with the rows the
+/// file can be rotated to `{name}-{from}-{to}.csv` once it reaches the configured limits
 pub struct AsyncCsvAppender {
-    path: Arc<Path>,
-    writer_lock: Arc<Mutex<()>>,
+    state: Arc<Mutex<CsvAppenderState>>,
 }
 
 impl AsyncCsvAppender {
     pub fn new(file_path: &str) -> Self {
+        Self::with_settings(file_path, CsvFileSettings::default())
+    }
+
+    pub fn with_settings(file_path: &str, settings: CsvFileSettings) -> Self {
         AsyncCsvAppender {
-            path: Arc::from(PathBuf::from(file_path)),
-            writer_lock: Arc::new(Mutex::new(())),
+            state: Arc::new(Mutex::new(CsvAppenderState {
+                path: PathBuf::from(file_path),
+                settings,
+                writer: None,
+                networks: BTreeMap::new(),
+            })),
         }
     }
 
-    pub async fn append(&self, data: Vec<String>) -> Result<(), csv::Error> {
-        let lock = Arc::clone(&self.writer_lock);
-        let path = Arc::clone(&self.path);
+    async fn run<F>(&self, f: F) -> Result<(), csv::Error>
+    where
+        F: FnOnce(&mut CsvAppenderState) -> Result<(), csv::Error> + Send + 'static,
+    {
+        let state = Arc::clone(&self.state);
 
         tokio::task::spawn_blocking(move || {
-            let _guard = lock.lock();
-            let file = File::options().create(true).append(true).open(path)?;
-            let mut writer = Writer::from_writer(file);
-
-            writer.write_record(data)?;
-
-            Ok(())
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut state)
         })
         .await
         .expect("Failed to run CSV write operation")
     }
 
+    pub async fn append(&self, data: Vec<String>) -> Result<(), csv::Error> {
+        self.run(move |state| state.write(vec![data])).await
+    }
+
     pub async fn append_bulk(&self, records: Vec<Vec<String>>) -> Result<(), csv::Error> {
-        let lock = Arc::clone(&self.writer_lock);
-        let path = Arc::clone(&self.path);
+        self.run(move |state| state.write(records)).await
+    }
 
-        tokio::task::spawn_blocking(move || {
-            let _guard = lock.lock();
-            let file = File::options().create(true).append(true).open(&path)?;
-            let mut writer = Writer::from_writer(file);
+    /// Appends the rows indexed between the blocks and rotates the file if it reached its limits
+    pub async fn append_bulk_in_block_range(
+        &self,
+        network: String,
+        from_block: U64,
+        to_block: U64,
+        records: Vec<Vec<String>>,
+    ) -> Result<(), csv::Error> {
+        self.run(move |state| {
+            state.write(records)?;
+
+            let (_, to) = state.networks.entry(network).or_insert((from_block, to_block));
+            *to = (*to).max(to_block);
 
-            for record in records {
-                writer.write_record(record)?;
+            if state.should_rotate()? {
+                state.rotate()?;
             }
 
             Ok(())
         })
         .await
-        .expect("Failed to run CSV bulk write operation")
     }
 
     pub async fn append_header(&self, header: Vec<String>) -> Result<(), csv::Error> {
-        let lock = Arc::clone(&self.writer_lock);
-        let path = Arc::clone(&self.path);
+        self.run(move |state| state.write(vec![header])).await
+    }
+}
 
-        tokio::task::spawn_blocking(move || {
-            let _guard = lock.lock();
-            let file = File::options().create(true).append(true).open(&path)?;
-            let mut writer = Writer::from_writer(file);
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
 
-            writer.write_record(header)?;
+    use super::*;
 
-            Ok(())
-        })
-        .await
-        .expect("Failed to run CSV write operation")
+    fn row(block_number: u64) -> Vec<String> {
+        vec!["a;b".to_string(), block_number.to_string(), "ethereum".to_string()]
+    }
+
+    #[tokio::test]
+    async fn test_rotation_with_header_and_delimiter() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("token-transfer.csv");
+        let settings = CsvFileSettings {
+            delimiter: b';',
+            header: Some(vec![
+                "value".to_string(),
+                "block_number".to_string(),
+                "network".to_string(),
+            ]),
+            max_blocks_per_file: Some(100),
+            compression: Some(FileCompression::Gzip),
+            ..Default::default()
+        };
+        let appender = AsyncCsvAppender::with_settings(path.to_str().unwrap(), settings);
+
+        appender
+            .append_bulk_in_block_range(
+                "ethereum".to_string(),
+                U64::from(1),
+                U64::from(50),
+                vec![row(10)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "value;block_number;network\n\"a;b\";10;ethereum\n"
+        );
+
+        appender
+            .append_bulk_in_block_range(
+                "ethereum".to_string(),
+                U64::from(51),
+                U64::from(100),
+                vec![row(60)],
+            )
+            .await
+            .unwrap();
+        assert!(!path.exists());
+
+        let mut rotated = String::new();
+        flate2::read::GzDecoder::new(
+            File::open(directory.path().join("token-transfer-1-100.csv.gz")).unwrap(),
+        )
+        .read_to_string(&mut rotated)
+        .unwrap();
+        assert_eq!(rotated.lines().count(), 3);
+
+        // the next file gets its own header
+        appender
+            .append_bulk_in_block_range(
+                "ethereum".to_string(),
+                U64::from(101),
+                U64::from(110),
+                vec![row(105)],
+            )
+            .await
+            .unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("value;block_number;network\n"));
+    }
+
+    #[tokio::test]
+    async fn test_resumes_block_range_from_existing_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("token-transfer.csv");
+        fs::write(&path, "value,block_number,network\nx,40,ethereum\n").unwrap();
+
+        let settings = CsvFileSettings {
+            header: Some(vec![
+                "value".to_string(),
+                "block_number".to_string(),
+                "network".to_string(),
+            ]),
+            max_blocks_per_file: Some(100),
+            ..Default::default()
+        };
+        let appender = AsyncCsvAppender::with_settings(path.to_str().unwrap(), settings);
+
+        appender
+            .append_bulk_in_block_range(
+                "ethereum".to_string(),
+                U64::from(120),
+                U64::from(140),
+                vec![vec!["y".to_string(), "130".to_string(), "ethereum".to_string()]],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(directory.path().join("token-transfer-40-140.csv")).unwrap(),
+            "value,block_number,network\nx,40,ethereum\ny,130,ethereum\n"
+        );
     }
 }
@@ -1 +1,4 @@
+pub mod compression;
 pub mod csv;
+pub mod ndjson;
+pub mod parquet;
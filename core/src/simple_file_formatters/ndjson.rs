@@ -0,0 +1,251 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    fs::File,
+    io,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use ethers::types::U64;
+use serde_json::Value;
+use tracing::info;
+
+use crate::simple_file_formatters::compression::{compress_file, FileCompression};
+
+#[derive(thiserror::Error, Debug)]
+pub enum NdjsonAppenderError {
+    #[error("File IO error: {0}")]
+    FileIo(#[from] io::Error),
+
+    #[error("Could not serialize event: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+pub struct NdjsonFileSettings {
+    pub max_blocks_per_file: Option<u64>,
+    pub max_file_size_bytes: Option<u64>,
+    pub gzip: bool,
+}
+
+struct ActiveFile {
+    writer: BufWriter<File>,
+    size: u64,
+    /// The first and last block written per network
+    networks: BTreeMap<String, (U64, U64)>,
+}
+
+struct NdjsonAppenderState {
+    folder: PathBuf,
+    contract_name: String,
+    event_name: String,
+    file_prefix: String,
+    settings: NdjsonFileSettings,
+    active: Option<ActiveFile>,
+}
+
+/// The network and block of the first event in a file left from a previous run
+fn read_first_event_block(path: &Path) -> Option<(String, U64)> {
+    let mut line = String::new();
+    BufReader::new(File::open(path).ok()?).read_line(&mut line).ok()?;
+
+    let event: Value = serde_json::from_str(&line).ok()?;
+    let transaction_information = event.get("transaction_information")?;
+    let network = transaction_information.get("network")?.as_str()?.to_string();
+    let block_number = serde_json::from_value(transaction_information.get("block_number")?.clone());
+
+    Some((network, block_number.ok()?))
+}
+
+impl NdjsonAppenderState {
+    fn active_path(&self) -> PathBuf {
+        self.folder.join(format!("{}.ndjson", self.file_prefix))
+    }
+
+    fn open(&self) -> io::Result<ActiveFile> {
+        let path = self.active_path();
+        let mut networks = BTreeMap::new();
+        if let Some((network, block)) = read_first_event_block(&path) {
+            networks.insert(network, (block, block));
+        }
+
+        let file = File::options().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(ActiveFile { writer: BufWriter::new(file), size, networks })
+    }
+
+    fn should_rotate(&self, file: &ActiveFile) -> bool {
+        self.settings.max_file_size_bytes.is_some_and(|max| file.size >= max) ||
+            self.settings.max_blocks_per_file.is_some_and(|max| {
+                file.networks.values().any(|(from, to)| (*to - *from).as_u64() + 1 >= max)
+            })
+    }
+
+    fn write(
+        &mut self,
+        network: String,
+        from_block: U64,
+        to_block: U64,
+        events: Vec<Value>,
+    ) -> Result<(), NdjsonAppenderError> {
+        let mut file = match self.active.take() {
+            Some(file) => file,
+            None => self.open()?,
+        };
+
+        let mut lines = Vec::new();
+        for event in &events {
+            serde_json::to_writer(&mut lines, event)?;
+            lines.push(b'\n');
+        }
+        file.writer.write_all(&lines)?;
+        // flushed before the last synced block moves past these events
+        file.writer.flush()?;
+        file.size += lines.len() as u64;
+
+        let (_, to) = file.networks.entry(network).or_insert((from_block, to_block));
+        *to = (*to).max(to_block);
+
+        if self.should_rotate(&file) {
+            self.rotate(file)?;
+        } else {
+            self.active = Some(file);
+        }
+
+        Ok(())
+    }
+
+    fn rotate(&self, file: ActiveFile) -> io::Result<()> {
+        let from_block = file.networks.values().map(|(from, _)| *from).min().unwrap_or_default();
+        let to_block = file.networks.values().map(|(_, to)| *to).max().unwrap_or_default();
+
+        file.writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        let mut path =
+            self.folder.join(format!("{}-{}-{}.ndjson", self.file_prefix, from_block, to_block));
+        fs::rename(self.active_path(), &path)?;
+
+        if self.settings.gzip {
+            path = compress_file(&path, FileCompression::Gzip)?;
+        }
+
+        info!(
+            "{}::{} - written ndjson file {}",
+            self.contract_name,
+            self.event_name,
+            path.display()
+        );
+
+        Ok(())
+    }
+}
+
+/// Writes an event to `{path}/{contract}/{contract}-{event}.ndjson`, when a rotation limit is set
+/// the file is moved to `{contract}-{event}-{from}-{to}.ndjson` once it reaches it
+pub struct AsyncNdjsonAppender {
+    state: Arc<Mutex<NdjsonAppenderState>>,
+}
+
+impl AsyncNdjsonAppender {
+    pub fn new(
+        full_path: &Path,
+        contract_name: &str,
+        event_name: &str,
+        settings: NdjsonFileSettings,
+    ) -> io::Result<Self> {
+        let folder = full_path.join(contract_name);
+        fs::create_dir_all(folder.join("last-synced-blocks"))?;
+
+        let state = NdjsonAppenderState {
+            folder,
+            contract_name: contract_name.to_string(),
+            event_name: event_name.to_string(),
+            file_prefix: format!("{}-{}", contract_name, event_name).to_lowercase(),
+            settings,
+            active: None,
+        };
+
+        Ok(AsyncNdjsonAppender { state: Arc::new(Mutex::new(state)) })
+    }
+
+    pub async fn append_bulk(
+        &self,
+        network: String,
+        from_block: U64,
+        to_block: U64,
+        events: Vec<Value>,
+    ) -> Result<(), NdjsonAppenderError> {
+        let state = Arc::clone(&self.state);
+
+        tokio::task::spawn_blocking(move || {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.write(network, from_block, to_block, events)
+        })
+        .await
+        .expect("Failed to run ndjson write operation")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(block_number: u64) -> Value {
+        json!({
+            "value": "1",
+            "transaction_information": {
+                "network": "ethereum",
+                "block_number": U64::from(block_number),
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_rotation_and_gzip() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = NdjsonFileSettings {
+            max_blocks_per_file: Some(100),
+            max_file_size_bytes: None,
+            gzip: true,
+        };
+        let appender =
+            AsyncNdjsonAppender::new(directory.path(), "Token", "Transfer", settings).unwrap();
+
+        appender
+            .append_bulk("ethereum".to_string(), U64::from(1), U64::from(50), vec![event(10)])
+            .await
+            .unwrap();
+        let folder = directory.path().join("Token");
+        let active = fs::read_to_string(folder.join("token-transfer.ndjson")).unwrap();
+        assert_eq!(active.lines().count(), 1);
+
+        appender
+            .append_bulk("ethereum".to_string(), U64::from(51), U64::from(100), vec![event(60)])
+            .await
+            .unwrap();
+        assert!(!folder.join("token-transfer.ndjson").exists());
+
+        let mut rotated = String::new();
+        GzDecoder::new(File::open(folder.join("token-transfer-1-100.ndjson.gz")).unwrap())
+            .read_to_string(&mut rotated)
+            .unwrap();
+        let lines: Vec<Value> = rotated.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines, vec![event(10), event(60)]);
+    }
+
+    #[test]
+    fn test_read_first_event_block() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("token-transfer.ndjson");
+        fs::write(&path, format!("{}\n{}\n", event(42), event(43))).unwrap();
+
+        assert_eq!(read_first_event_block(&path), Some(("ethereum".to_string(), U64::from(42))));
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+};
+
+use arrow_array::RecordBatch;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use ethers::types::U64;
+use lazy_static::lazy_static;
+use parquet::{
+    arrow::ArrowWriter, basic::Compression, errors::ParquetError,
+    file::properties::WriterProperties,
+};
+use serde_json::{Map, Value};
+use tracing::{error, info};
+
+use crate::{
+    abi::{ABIInput, EventInfo, GenerateAbiPropertiesType},
+    database::postgres::sql_type_wrapper::{
+        map_ethereum_wrapper_to_typed_json_value, EthereumSqlTypeWrapper,
+    },
+    indexer::last_synced::build_last_synced_block_number_file,
+    manifest::storage::{ComputedColumnType, StorageEvent},
+};
+
+lazy_static! {
+    // files still being written are closed once historic indexing completes
+    static ref OPEN_PARQUET_APPENDERS: Mutex<Vec<Weak<Mutex<ParquetAppenderState>>>> =
+        Mutex::new(Vec::new());
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParquetAppenderError {
+    #[error("File IO error: {0}")]
+    FileIo(#[from] std::io::Error),
+
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Could not read or write the in progress parquet state: {0}")]
+    InProgressState(#[from] serde_json::Error),
+}
+
+fn list(inner: DataType) -> DataType {
+    DataType::List(Arc::new(Field::new("item", inner, true)))
+}
+
+/// Numbers wider than 64 bits are stored as decimal strings as most parquet readers only support
+/// decimals up to 38 digits, hashes, addresses and bytes are 0x prefixed hex
+pub fn ethereum_sql_type_wrapper_to_arrow_type(wrapper: &EthereumSqlTypeWrapper) -> DataType {
+    match wrapper {
+        EthereumSqlTypeWrapper::U8(_) => DataType::UInt8,
+        EthereumSqlTypeWrapper::VecU8(_) => list(DataType::UInt8),
+        EthereumSqlTypeWrapper::U16(_) => DataType::UInt16,
+        EthereumSqlTypeWrapper::VecU16(_) => list(DataType::UInt16),
+        EthereumSqlTypeWrapper::U32(_) => DataType::UInt32,
+        EthereumSqlTypeWrapper::VecU32(_) => list(DataType::UInt32),
+        EthereumSqlTypeWrapper::U64(_) => DataType::UInt64,
+        EthereumSqlTypeWrapper::VecU64(_) => list(DataType::UInt64),
+        EthereumSqlTypeWrapper::I8(_) => DataType::Int8,
+        EthereumSqlTypeWrapper::VecI8(_) => list(DataType::Int8),
+        EthereumSqlTypeWrapper::I16(_) => DataType::Int16,
+        EthereumSqlTypeWrapper::VecI16(_) => list(DataType::Int16),
+        EthereumSqlTypeWrapper::I32(_) => DataType::Int32,
+        EthereumSqlTypeWrapper::VecI32(_) => list(DataType::Int32),
+        EthereumSqlTypeWrapper::I64(_) => DataType::Int64,
+        EthereumSqlTypeWrapper::VecI64(_) => list(DataType::Int64),
+        EthereumSqlTypeWrapper::Bool(_) | EthereumSqlTypeWrapper::NullableBool(_) => {
+            DataType::Boolean
+        }
+        EthereumSqlTypeWrapper::VecBool(_) => list(DataType::Boolean),
+        EthereumSqlTypeWrapper::VecU128(_) |
+        EthereumSqlTypeWrapper::VecU256(_) |
+        EthereumSqlTypeWrapper::VecU512(_) |
+        EthereumSqlTypeWrapper::VecI128(_) |
+        EthereumSqlTypeWrapper::VecI256(_) |
+        EthereumSqlTypeWrapper::VecH128(_) |
+        EthereumSqlTypeWrapper::VecH160(_) |
+        EthereumSqlTypeWrapper::VecH256(_) |
+        EthereumSqlTypeWrapper::VecH512(_) |
+        EthereumSqlTypeWrapper::VecAddress(_) |
+        EthereumSqlTypeWrapper::VecString(_) |
+        EthereumSqlTypeWrapper::VecBytes(_) |
+        EthereumSqlTypeWrapper::RawTopics(_) => list(DataType::Utf8),
+        // tuples stored as json are kept as the raw json string
+        _ => DataType::Utf8,
+    }
+}
+
+/// The schema of an event file, columns are in the same order as the postgres table
+pub fn generate_parquet_event_schema(
+    event_info: &EventInfo,
+    storage_event: &StorageEvent,
+) -> Schema {
+    let mut fields = vec![Field::new("contract_address", DataType::Utf8, false)];
+
+    fields.extend(
+        ABIInput::generate_abi_name_properties(
+            &event_info.inputs,
+            &GenerateAbiPropertiesType::PostgresColumnsNamesOnly,
+            None,
+            storage_event.is_tuples_as_jsonb(),
+        )
+        .into_iter()
+        .map(|property| {
+            Field::new(
+                storage_event.column_name(&property.value),
+                property
+                    .ethereum_sql_type_wrapper
+                    .as_ref()
+                    .map_or(DataType::Utf8, ethereum_sql_type_wrapper_to_arrow_type),
+                false,
+            )
+        }),
+    );
+
+    fields.extend(
+        storage_event
+            .formatted_column_names(&event_info.inputs)
+            .into_iter()
+            .map(|name| Field::new(name, DataType::Utf8, true)),
+    );
+
+    fields.extend(storage_event.computed_columns().iter().map(|computed_column| {
+        let data_type = match computed_column.type_ {
+            ComputedColumnType::Bool => DataType::Boolean,
+            ComputedColumnType::Number | ComputedColumnType::String => DataType::Utf8,
+        };
+        Field::new(&computed_column.name, data_type, true)
+    }));
+
+    if storage_event.store_raw_log {
+        fields.push(Field::new("topics", list(DataType::Utf8), false));
+        fields.push(Field::new("data", DataType::Utf8, false));
+    }
+
+    for (name, data_type) in [
+        ("tx_hash", DataType::Utf8),
+        ("block_number", DataType::UInt64),
+        ("block_hash", DataType::Utf8),
+        ("network", DataType::Utf8),
+        ("tx_index", DataType::UInt64),
+        ("log_index", DataType::Utf8),
+    ] {
+        fields.push(Field::new(name, data_type, false));
+    }
+
+    Schema::new(fields)
+}
+
+fn generate_record_batch(
+    schema: &SchemaRef,
+    rows: &[Vec<EthereumSqlTypeWrapper>],
+) -> Result<Option<RecordBatch>, ArrowError> {
+    let rows: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let object: Map<String, Value> = schema
+                .fields()
+                .iter()
+                .zip(row)
+                .map(|(field, wrapper)| {
+                    (field.name().clone(), map_ethereum_wrapper_to_typed_json_value(wrapper))
+                })
+                .collect();
+            Value::Object(object)
+        })
+        .collect();
+
+    let mut decoder = arrow_json::ReaderBuilder::new(Arc::clone(schema)).build_decoder()?;
+    decoder.serialize(&rows)?;
+    decoder.flush()
+}
+
+pub struct ParquetFileSettings {
+    pub max_blocks_per_file: u64,
+    pub max_file_size_bytes: u64,
+    /// A file is closed once a network reaches its end block
+    pub end_blocks: HashMap<String, U64>,
+}
+
+struct InProgressFile {
+    writer: ArrowWriter<File>,
+    /// The first and last block written per network
+    networks: BTreeMap<String, (U64, U64)>,
+}
+
+struct ParquetAppenderState {
+    full_path: PathBuf,
+    folder: PathBuf,
+    contract_name: String,
+    event_name: String,
+    file_prefix: String,
+    schema: SchemaRef,
+    settings: ParquetFileSettings,
+    in_progress: Option<InProgressFile>,
+}
+
+impl ParquetAppenderState {
+    fn in_progress_path(&self) -> PathBuf {
+        self.folder.join(format!("{}.parquet.inprogress", self.file_prefix))
+    }
+
+    /// The first block each network wrote to the in progress file, used to rewind the last synced
+    /// block if the file was never closed
+    fn in_progress_state_path(&self) -> PathBuf {
+        self.folder.join(format!("{}.parquet.inprogress.json", self.file_prefix))
+    }
+
+    fn write_last_synced_block(&self, network: &str, block: U64) -> std::io::Result<()> {
+        let file_path = build_last_synced_block_number_file(
+            &self.full_path,
+            &self.contract_name,
+            network,
+            &self.event_name,
+        );
+        let temp_file_path = format!("{}.tmp", file_path);
+        fs::write(&temp_file_path, block.to_string())?;
+        fs::rename(temp_file_path, file_path)
+    }
+
+    fn read_last_synced_block(&self, network: &str) -> Option<U64> {
+        let file_path = build_last_synced_block_number_file(
+            &self.full_path,
+            &self.contract_name,
+            network,
+            &self.event_name,
+        );
+        fs::read_to_string(file_path).ok().and_then(|value| U64::from_dec_str(value.trim()).ok())
+    }
+
+    /// A file without a footer can not be read so the rows in it are dropped and the networks
+    /// written to it are rewound to resync them into a new file
+    fn recover_in_progress_file(&self) -> Result<(), ParquetAppenderError> {
+        let state_path = self.in_progress_state_path();
+        if state_path.exists() {
+            let first_blocks: BTreeMap<String, u64> =
+                serde_json::from_str(&fs::read_to_string(&state_path)?)?;
+
+            for (network, first_block) in first_blocks {
+                let rewind_to = U64::from(first_block.saturating_sub(1));
+                if self.read_last_synced_block(&network).is_some_and(|last| last > rewind_to) {
+                    info!(
+                        "{}::{} - rewinding {} to block {} as its parquet file was not completed",
+                        self.contract_name, self.event_name, network, rewind_to
+                    );
+                    self.write_last_synced_block(&network, rewind_to)?;
+                }
+            }
+
+            fs::remove_file(&state_path)?;
+        }
+
+        let in_progress_path = self.in_progress_path();
+        if in_progress_path.exists() {
+            fs::remove_file(in_progress_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_in_progress_state(&self, file: &InProgressFile) -> Result<(), ParquetAppenderError> {
+        let first_blocks: BTreeMap<&String, u64> =
+            file.networks.iter().map(|(network, (from, _))| (network, from.as_u64())).collect();
+        fs::write(self.in_progress_state_path(), serde_json::to_string(&first_blocks)?)?;
+        Ok(())
+    }
+
+    fn open(&self) -> Result<InProgressFile, ParquetAppenderError> {
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = ArrowWriter::try_new(
+            File::create(self.in_progress_path())?,
+            Arc::clone(&self.schema),
+            Some(properties),
+        )?;
+
+        Ok(InProgressFile { writer, networks: BTreeMap::new() })
+    }
+
+    fn should_close(&self, file: &InProgressFile) -> bool {
+        file.writer.bytes_written() as u64 >= self.settings.max_file_size_bytes ||
+            file.networks.iter().any(|(network, (from, to))| {
+                (*to - *from).as_u64() + 1 >= self.settings.max_blocks_per_file ||
+                    self.settings.end_blocks.get(network).is_some_and(|end| to >= end)
+            })
+    }
+
+    fn write(
+        &mut self,
+        network: String,
+        from_block: U64,
+        to_block: U64,
+        rows: Vec<Vec<EthereumSqlTypeWrapper>>,
+    ) -> Result<(), ParquetAppenderError> {
+        let Some(batch) = generate_record_batch(&self.schema, &rows)? else {
+            return Ok(());
+        };
+
+        let mut file = match self.in_progress.take() {
+            Some(file) => file,
+            None => self.open()?,
+        };
+
+        match file.networks.get_mut(&network) {
+            Some((_, to)) => *to = (*to).max(to_block),
+            None => {
+                file.networks.insert(network, (from_block, to_block));
+                self.write_in_progress_state(&file)?;
+            }
+        }
+
+        // each indexed block range is flushed as its own row group
+        file.writer.write(&batch)?;
+        file.writer.flush()?;
+
+        if self.should_close(&file) {
+            self.close(file)
+        } else {
+            self.in_progress = Some(file);
+            Ok(())
+        }
+    }
+
+    fn close(&self, file: InProgressFile) -> Result<(), ParquetAppenderError> {
+        let from_block = file.networks.values().map(|(from, _)| *from).min().unwrap_or_default();
+        let to_block = file.networks.values().map(|(_, to)| *to).max().unwrap_or_default();
+
+        let inner = file.writer.into_inner()?;
+        inner.sync_all()?;
+
+        let path =
+            self.folder.join(format!("{}-{}-{}.parquet", self.file_prefix, from_block, to_block));
+        fs::rename(self.in_progress_path(), &path)?;
+        fs::remove_file(self.in_progress_state_path())?;
+
+        info!(
+            "{}::{} - written parquet file {}",
+            self.contract_name,
+            self.event_name,
+            path.display()
+        );
+
+        Ok(())
+    }
+
+    fn close_in_progress(&mut self) -> Result<(), ParquetAppenderError> {
+        match self.in_progress.take() {
+            Some(file) => self.close(file),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes an event to `{path}/{contract}/{contract}-{event}-{from}-{to}.parquet` files, the file
+/// being written is kept as `.parquet.inprogress` until it is rolled over
+pub struct AsyncParquetAppender {
+    state: Arc<Mutex<ParquetAppenderState>>,
+}
+
+impl AsyncParquetAppender {
+    pub fn new(
+        full_path: &Path,
+        contract_name: &str,
+        event_name: &str,
+        schema: Schema,
+        settings: ParquetFileSettings,
+    ) -> Result<Self, ParquetAppenderError> {
+        let folder = full_path.join(contract_name);
+        fs::create_dir_all(folder.join("last-synced-blocks"))?;
+
+        let state = ParquetAppenderState {
+            full_path: full_path.to_path_buf(),
+            folder,
+            contract_name: contract_name.to_string(),
+            event_name: event_name.to_string(),
+            file_prefix: format!("{}-{}", contract_name, event_name).to_lowercase(),
+            schema: Arc::new(schema),
+            settings,
+            in_progress: None,
+        };
+        state.recover_in_progress_file()?;
+
+        let state = Arc::new(Mutex::new(state));
+        OPEN_PARQUET_APPENDERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::downgrade(&state));
+
+        Ok(AsyncParquetAppender { state })
+    }
+
+    pub async fn append_bulk(
+        &self,
+        network: String,
+        from_block: U64,
+        to_block: U64,
+        rows: Vec<Vec<EthereumSqlTypeWrapper>>,
+    ) -> Result<(), ParquetAppenderError> {
+        let state = Arc::clone(&self.state);
+
+        tokio::task::spawn_blocking(move || {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.write(network, from_block, to_block, rows)
+        })
+        .await
+        .expect("Failed to run parquet write operation")
+    }
+}
+
+/// Closes every in progress parquet file so all the indexed data is readable
+pub async fn close_parquet_files() {
+    let states: Vec<_> = OPEN_PARQUET_APPENDERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+
+    if states.is_empty() {
+        return;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        for state in states {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = state.close_in_progress() {
+                error!(
+                    "{}::{} - Error closing parquet file: {}",
+                    state.contract_name, state.event_name, e
+                );
+            }
+        }
+    })
+    .await
+    .expect("Failed to run parquet close operation")
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Array, StringArray, UInt64Array};
+    use ethers::types::{Address, U256};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("from", DataType::Utf8, false),
+            Field::new("value", DataType::Utf8, false),
+            Field::new("block_number", DataType::UInt64, false),
+        ])
+    }
+
+    fn row(block_number: u64) -> Vec<EthereumSqlTypeWrapper> {
+        vec![
+            EthereumSqlTypeWrapper::Address(Address::repeat_byte(1)),
+            EthereumSqlTypeWrapper::U256(U256::MAX),
+            EthereumSqlTypeWrapper::U64(U64::from(block_number)),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_row_groups_and_rollover() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = ParquetFileSettings {
+            max_blocks_per_file: 100,
+            max_file_size_bytes: u64::MAX,
+            end_blocks: HashMap::new(),
+        };
+        let appender =
+            AsyncParquetAppender::new(directory.path(), "Token", "Transfer", schema(), settings)
+                .unwrap();
+
+        appender
+            .append_bulk(
+                "ethereum".to_string(),
+                U64::from(1),
+                U64::from(50),
+                vec![row(10), row(20)],
+            )
+            .await
+            .unwrap();
+        let folder = directory.path().join("Token");
+        assert!(folder.join("token-transfer.parquet.inprogress").exists());
+
+        appender
+            .append_bulk("ethereum".to_string(), U64::from(51), U64::from(100), vec![row(60)])
+            .await
+            .unwrap();
+        assert!(!folder.join("token-transfer.parquet.inprogress").exists());
+
+        let file = File::open(folder.join("token-transfer-1-100.parquet")).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(builder.metadata().num_row_groups(), 2);
+
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        let values = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(values.value(0), U256::MAX.to_string());
+        let blocks = batch.column(2).as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks.value(2), 60);
+    }
+
+    #[test]
+    fn test_recover_in_progress_file_rewinds_last_synced_block() {
+        let directory = tempfile::tempdir().unwrap();
+        let folder = directory.path().join("Token");
+        fs::create_dir_all(folder.join("last-synced-blocks")).unwrap();
+        fs::write(folder.join("last-synced-blocks/token-ethereum-transfer.txt"), "500").unwrap();
+        fs::write(folder.join("token-transfer.parquet.inprogress"), "").unwrap();
+        fs::write(folder.join("token-transfer.parquet.inprogress.json"), r#"{"ethereum":200}"#)
+            .unwrap();
+
+        let settings = ParquetFileSettings {
+            max_blocks_per_file: 100,
+            max_file_size_bytes: u64::MAX,
+            end_blocks: HashMap::new(),
+        };
+        AsyncParquetAppender::new(directory.path(), "Token", "Transfer", schema(), settings)
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(folder.join("last-synced-blocks/token-ethereum-transfer.txt"))
+                .unwrap(),
+            "199"
+        );
+        assert!(!folder.join("token-transfer.parquet.inprogress").exists());
+        assert!(!folder.join("token-transfer.parquet.inprogress.json").exists());
+    }
+}
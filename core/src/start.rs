@@ -6,7 +6,7 @@ use tracing::{error, info};
 use crate::{
     api::{start_graphql_server, GraphqlOverrideSettings, StartGraphqlServerError},
     database::postgres::{
-        client::PostgresConnectionError,
+        client::{set_postgres_connection_details, PostgresConnectionError},
         indexes::{ApplyPostgresIndexesError, PostgresIndexResult},
         relationship::{ApplyAllRelationships, Relationship},
         setup::{setup_postgres, SetupPostgresError},
@@ -28,6 +28,8 @@ use crate::{
 
 pub struct IndexingDetails {
     pub registry: EventCallbackRegistry,
+    /// Drop and resync tables which no longer match the ABI instead of refusing to start
+    pub allow_destructive_migrations: bool,
 }
 
 pub struct StartDetails<'a> {
@@ -80,6 +82,7 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
         Some(project_path) => {
             load_env_from_path(project_path);
             let manifest = Arc::new(read_manifest(details.manifest_path)?);
+            set_postgres_connection_details(manifest.storage.postgres_connection());
 
             if manifest.project_type != ProjectType::NoCode {
                 setup_info_logger();
@@ -113,7 +116,12 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
 
                 // setup postgres is already called in no-code startup
                 if manifest.project_type != ProjectType::NoCode && *postgres_enabled {
-                    setup_postgres(project_path, &manifest).await?;
+                    setup_postgres(
+                        project_path,
+                        &manifest,
+                        indexing_details.allow_destructive_migrations,
+                    )
+                    .await?;
                 }
 
                 let (relationships, postgres_indexes) = manifest
@@ -207,6 +215,8 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
 
 pub struct IndexerNoCodeDetails {
     pub enabled: bool,
+    /// Drop and resync tables which no longer match the ABI instead of refusing to start
+    pub allow_destructive_migrations: bool,
 }
 
 pub struct StartNoCodeDetails<'a> {
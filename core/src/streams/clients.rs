@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use aws_sdk_sns::{config::http::HttpResponse, error::SdkError, operation::publish::PublishError};
 use futures::future::join_all;
@@ -13,12 +13,15 @@ use tracing::error;
 use crate::{
     event::{filter_event_data_by_conditions, EventMessage},
     manifest::stream::{
-        KafkaStreamConfig, KafkaStreamQueueConfig, RabbitMQStreamConfig, RabbitMQStreamQueueConfig,
-        SNSStreamTopicConfig, StreamEvent, StreamsConfig, WebhookStreamConfig,
+        KafkaStreamConfig, KafkaStreamQueueConfig, NatsStreamConfig, NatsStreamSubjectConfig,
+        PostgresNotifyStreamChannelConfig, PostgresNotifyStreamConfig, RabbitMQStreamConfig,
+        RabbitMQStreamQueueConfig, RedisStreamConfig, RedisStreamKeyConfig, SNSStreamTopicConfig,
+        StreamEvent, StreamsConfig, WebhookStreamConfig,
     },
     streams::{
         kafka::{Kafka, KafkaError},
-        RabbitMQ, RabbitMQError, Webhook, WebhookError, SNS,
+        Nats, NatsError, PostgresNotify, PostgresNotifyError, RabbitMQ, RabbitMQError, Redis,
+        RedisStreamError, Webhook, WebhookError, SNS,
     },
 };
 
@@ -48,13 +51,22 @@ pub enum StreamError {
     #[error("Kafka could not publish: {0}")]
     KafkaCouldNotPublish(#[from] KafkaError),
 
+    #[error("Postgres notify could not publish: {0}")]
+    PostgresNotifyCouldNotPublish(#[from] PostgresNotifyError),
+
+    #[error("NATS could not publish: {0}")]
+    NatsCouldNotPublish(#[from] NatsError),
+
+    #[error("Redis could not publish: {0}")]
+    RedisCouldNotPublish(#[from] RedisStreamError),
+
     #[error("Task failed: {0}")]
     JoinError(JoinError),
 }
 
 #[derive(Debug, Clone)]
 struct WebhookStream {
-    config: Vec<WebhookStreamConfig>,
+    config: WebhookStreamConfig,
     client: Arc<Webhook>,
 }
 
@@ -68,15 +80,33 @@ pub struct KafkaStream {
     client: Arc<Kafka>,
 }
 
+pub struct PostgresNotifyStream {
+    config: PostgresNotifyStreamConfig,
+    client: Arc<PostgresNotify>,
+}
+
+pub struct NatsStream {
+    config: NatsStreamConfig,
+    client: Arc<Nats>,
+}
+
+pub struct RedisStream {
+    config: RedisStreamConfig,
+    client: Arc<Redis>,
+}
+
 pub struct StreamsClients {
     sns: Option<SNSStream>,
-    webhook: Option<WebhookStream>,
+    webhook: Option<Vec<WebhookStream>>,
     rabbitmq: Option<RabbitMQStream>,
     kafka: Option<KafkaStream>,
+    postgres_notify: Option<PostgresNotifyStream>,
+    nats: Option<NatsStream>,
+    redis: Option<RedisStream>,
 }
 
 impl StreamsClients {
-    pub async fn new(stream_config: StreamsConfig) -> Self {
+    pub async fn new(stream_config: StreamsConfig, project_path: &Path) -> Self {
         let sns = if let Some(config) = &stream_config.sns {
             Some(SNSStream {
                 config: config.topics.clone(),
@@ -86,9 +116,16 @@ impl StreamsClients {
             None
         };
 
-        let webhook = stream_config.webhooks.as_ref().map(|config| WebhookStream {
-            config: config.clone(),
-            client: Arc::new(Webhook::new()),
+        let webhook = stream_config.webhooks.as_ref().map(|configs| {
+            configs
+                .iter()
+                .map(|config| WebhookStream {
+                    config: config.clone(),
+                    client: Arc::new(Webhook::new(config, project_path).unwrap_or_else(|e| {
+                        panic!("Failed to create webhook client for {}: {:?}", config.endpoint, e)
+                    })),
+                })
+                .collect()
         });
 
         let rabbitmq = if let Some(config) = stream_config.rabbitmq.as_ref() {
@@ -113,14 +150,54 @@ impl StreamsClients {
             None
         };
 
-        Self { sns, webhook, rabbitmq, kafka }
+        let postgres_notify = if let Some(config) = stream_config.postgres_notify.as_ref() {
+            Some(PostgresNotifyStream {
+                config: config.clone(),
+                client: PostgresNotify::shared()
+                    .await
+                    .unwrap_or_else(|e| panic!("Failed to create postgres notify client: {:?}", e)),
+            })
+        } else {
+            None
+        };
+
+        let nats = if let Some(config) = stream_config.nats.as_ref() {
+            Some(NatsStream {
+                config: config.clone(),
+                client: Arc::new(
+                    Nats::new(config, project_path)
+                        .await
+                        .unwrap_or_else(|e| panic!("Failed to create NATS client: {:?}", e)),
+                ),
+            })
+        } else {
+            None
+        };
+
+        let redis = if let Some(config) = stream_config.redis.as_ref() {
+            Some(RedisStream {
+                config: config.clone(),
+                client: Arc::new(
+                    Redis::new(&config.url)
+                        .await
+                        .unwrap_or_else(|e| panic!("Failed to create redis client: {:?}", e)),
+                ),
+            })
+        } else {
+            None
+        };
+
+        Self { sns, webhook, rabbitmq, kafka, postgres_notify, nats, redis }
     }
 
     fn has_any_streams(&self) -> bool {
         self.sns.is_some() ||
             self.webhook.is_some() ||
             self.rabbitmq.is_some() ||
-            self.kafka.is_some()
+            self.kafka.is_some() ||
+            self.postgres_notify.is_some() ||
+            self.nats.is_some() ||
+            self.redis.is_some()
     }
 
     fn chunk_data(&self, data_array: &Vec<Value>) -> Vec<Vec<Value>> {
@@ -264,14 +341,11 @@ impl StreamsClients {
 
                 let publish_message_id = self.generate_publish_message_id(id, index, &None);
                 let endpoint = config.endpoint.clone();
-                let shared_secret = config.shared_secret.clone();
                 let client = Arc::clone(&client);
                 let publish_message =
                     self.create_chunk_message_json(event_message, &filtered_chunk);
                 task::spawn(async move {
-                    client
-                        .publish(&publish_message_id, &endpoint, &shared_secret, &publish_message)
-                        .await?;
+                    client.publish(&publish_message_id, &endpoint, &publish_message).await?;
 
                     Ok(filtered_chunk.len())
                 })
@@ -359,6 +433,117 @@ impl StreamsClients {
         tasks
     }
 
+    fn postgres_notify_stream_tasks(
+        &self,
+        config: &PostgresNotifyStreamChannelConfig,
+        stream: &PostgresNotifyStream,
+        id: &str,
+        event_message: &EventMessage,
+        chunks: Arc<Vec<Vec<Value>>>,
+    ) -> StreamPublishes {
+        let tasks: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
+                    &config.events,
+                    event_message,
+                    chunk,
+                );
+
+                let publish_message_id = self.generate_publish_message_id(id, index, &None);
+                let client = Arc::clone(&stream.client);
+                let channel = config.channel.clone();
+                let large_payloads = stream.config.large_payloads.unwrap_or_default();
+                let publish_message =
+                    self.create_chunk_message_json(event_message, &filtered_chunk);
+                task::spawn(async move {
+                    client
+                        .publish(&publish_message_id, &channel, large_payloads, &publish_message)
+                        .await?;
+                    Ok(filtered_chunk.len())
+                })
+            })
+            .collect();
+        tasks
+    }
+
+    fn nats_stream_tasks(
+        &self,
+        config: &NatsStreamSubjectConfig,
+        client: Arc<Nats>,
+        id: &str,
+        event_message: &EventMessage,
+        chunks: Arc<Vec<Vec<Value>>>,
+    ) -> StreamPublishes {
+        let subject = config.subject_for(&event_message.network, &event_message.event_name);
+        let tasks: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
+                    &config.events,
+                    event_message,
+                    chunk,
+                );
+
+                let publish_message_id = self.generate_publish_message_id(id, index, &None);
+                let client = Arc::clone(&client);
+                let subject = subject.clone();
+                let publish_message =
+                    self.create_chunk_message_json(event_message, &filtered_chunk);
+                task::spawn(async move {
+                    client.publish(&publish_message_id, &subject, &publish_message).await?;
+                    Ok(filtered_chunk.len())
+                })
+            })
+            .collect();
+        tasks
+    }
+
+    fn redis_stream_tasks(
+        &self,
+        config: &RedisStreamKeyConfig,
+        client: Arc<Redis>,
+        id: &str,
+        event_message: &EventMessage,
+        chunks: Arc<Vec<Vec<Value>>>,
+    ) -> StreamPublishes {
+        let key = config.key_for(&event_message.network, &event_message.event_name);
+        let tasks: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
+                    &config.events,
+                    event_message,
+                    chunk,
+                );
+
+                let publish_message_id = self.generate_publish_message_id(id, index, &None);
+                let client = Arc::clone(&client);
+                let key = key.clone();
+                let max_len = config.max_len;
+                let consumer_group = config.consumer_group.clone();
+                let publish_message =
+                    self.create_chunk_message_json(event_message, &filtered_chunk);
+                task::spawn(async move {
+                    client
+                        .publish(
+                            &publish_message_id,
+                            &key,
+                            max_len,
+                            &consumer_group,
+                            &publish_message,
+                        )
+                        .await?;
+                    Ok(filtered_chunk.len())
+                })
+            })
+            .collect();
+        tasks
+    }
+
     pub async fn stream(
         &self,
         id: String,
@@ -391,7 +576,8 @@ impl StreamsClients {
             };
 
             if let Some(webhook) = &self.webhook {
-                for config in &webhook.config {
+                for webhook in webhook {
+                    let config = &webhook.config;
                     if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
                         config.networks.contains(&event_message.network)
                     {
@@ -438,6 +624,54 @@ impl StreamsClients {
                 }
             }
 
+            if let Some(postgres_notify) = &self.postgres_notify {
+                for config in &postgres_notify.config.channels {
+                    if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
+                        config.networks.contains(&event_message.network)
+                    {
+                        streams.push(self.postgres_notify_stream_tasks(
+                            config,
+                            postgres_notify,
+                            &id,
+                            event_message,
+                            Arc::clone(&chunks),
+                        ));
+                    }
+                }
+            }
+
+            if let Some(nats) = &self.nats {
+                for config in &nats.config.subjects {
+                    if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
+                        config.networks.contains(&event_message.network)
+                    {
+                        streams.push(self.nats_stream_tasks(
+                            config,
+                            Arc::clone(&nats.client),
+                            &id,
+                            event_message,
+                            Arc::clone(&chunks),
+                        ));
+                    }
+                }
+            }
+
+            if let Some(redis) = &self.redis {
+                for config in &redis.config.keys {
+                    if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
+                        config.networks.contains(&event_message.network)
+                    {
+                        streams.push(self.redis_stream_tasks(
+                            config,
+                            Arc::clone(&redis.client),
+                            &id,
+                            event_message,
+                            Arc::clone(&chunks),
+                        ));
+                    }
+                }
+            }
+
             let mut streamed_total = 0;
 
             if index_event_in_order {
@@ -9,6 +9,15 @@ pub use rabbitmq::{RabbitMQ, RabbitMQError};
 
 mod kafka;
 
+mod nats;
+pub use nats::{Nats, NatsError};
+
+mod redis;
+pub use redis::{Redis, RedisStreamError};
+
+mod postgres_notify;
+pub use postgres_notify::{PostgresNotify, PostgresNotifyError};
+
 mod clients;
 pub use clients::StreamsClients;
 
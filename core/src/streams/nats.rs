@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use async_nats::{
+    header::NATS_MESSAGE_ID,
+    jetstream::{self, context::PublishError},
+    ConnectError, ConnectOptions, HeaderMap,
+};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::debug;
+
+use crate::{manifest::stream::NatsStreamConfig, streams::STREAM_MESSAGE_ID_KEY};
+
+#[derive(Error, Debug)]
+pub enum NatsError {
+    #[error("Could not connect to NATS: {0}")]
+    ConnectionError(#[from] ConnectError),
+
+    #[error("Could not read NATS credentials file: {0}")]
+    CredentialsError(std::io::Error),
+
+    #[error("JetStream publish was not acknowledged: {0}")]
+    PublishError(#[from] PublishError),
+
+    #[error("Could not parse message: {0}")]
+    CouldNotParseMessage(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct Nats {
+    jetstream: jetstream::Context,
+}
+
+impl Nats {
+    pub async fn new(config: &NatsStreamConfig, project_path: &Path) -> Result<Self, NatsError> {
+        let mut options = ConnectOptions::new().name("rindexer");
+        if let Some(credentials_file) = &config.credentials_file {
+            options = options
+                .credentials_file(project_path.join(credentials_file))
+                .await
+                .map_err(NatsError::CredentialsError)?;
+        }
+        if let Some(token) = &config.token {
+            options = options.token(token.clone());
+        }
+
+        let client = options.connect(&config.url).await?;
+
+        Ok(Self { jetstream: jetstream::new(client) })
+    }
+
+    /// Publishes to the stream bound to the subject and waits for its ack, the message id is
+    /// sent as `Nats-Msg-Id` so JetStream drops a message published again after a restart
+    pub async fn publish(&self, id: &str, subject: &str, message: &Value) -> Result<(), NatsError> {
+        let message_body = serde_json::to_vec(message)?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_MESSAGE_ID, id);
+        headers.insert(STREAM_MESSAGE_ID_KEY, id);
+
+        let ack = self
+            .jetstream
+            .publish_with_headers(subject.to_string(), headers, message_body.into())
+            .await?
+            .await?;
+
+        if ack.duplicate {
+            debug!("NATS message {} was already in stream {}", id, ack.stream);
+        }
+
+        Ok(())
+    }
+}
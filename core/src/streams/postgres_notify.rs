@@ -0,0 +1,218 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use crate::{
+    database::postgres::client::{PostgresClient, PostgresConnectionError, PostgresError},
+    manifest::stream::PostgresNotifyLargePayload,
+};
+
+/// Postgres rejects NOTIFY payloads of 8000 bytes or more
+const MAX_NOTIFY_PAYLOAD_SIZE: usize = 7999;
+
+/// Room left in a split part for the envelope around the data
+const MAX_SPLIT_PART_SIZE: usize = 7000;
+
+const NOTIFY_PAYLOADS_TABLE: &str = "rindexer_internal.stream_notify_payloads";
+
+/// How long a referenced payload is kept for consumers to read it
+const NOTIFY_PAYLOADS_RETENTION_HOURS: u32 = 24;
+
+/// How often the expired payloads are deleted
+const NOTIFY_PAYLOADS_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// every contract event publishes through the same client instead of opening its own pool
+static SHARED_POSTGRES_NOTIFY: OnceCell<Arc<PostgresNotify>> = OnceCell::const_new();
+
+#[derive(Error, Debug)]
+pub enum PostgresNotifyError {
+    #[error("{0}")]
+    ConnectionError(#[from] PostgresConnectionError),
+
+    #[error("{0}")]
+    PostgresError(#[from] PostgresError),
+
+    #[error("{0}")]
+    PgError(#[from] tokio_postgres::Error),
+
+    #[error("Could not serialize message: {0}")]
+    CouldNotSerialize(#[from] serde_json::Error),
+}
+
+pub struct PostgresNotify {
+    client: PostgresClient,
+    last_pruned: Mutex<Option<Instant>>,
+}
+
+/// How many bytes the character takes once escaped inside a json string
+fn escaped_len(c: char) -> usize {
+    match c {
+        '"' | '\\' => 2,
+        c if (c as u32) < 0x20 => 6,
+        c => c.len_utf8(),
+    }
+}
+
+/// Splits the message into numbered parts each small enough for a single NOTIFY, consumers join
+/// the `data` of the parts in order
+fn split_notify_payload(id: &str, message: &str) -> Vec<String> {
+    let mut parts: Vec<&str> = vec![];
+    let mut start = 0;
+    let mut size = 0;
+    for (index, c) in message.char_indices() {
+        if size + escaped_len(c) > MAX_SPLIT_PART_SIZE {
+            parts.push(&message[start..index]);
+            start = index;
+            size = 0;
+        }
+        size += escaped_len(c);
+    }
+    parts.push(&message[start..]);
+
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(part, data)| {
+            json!({ "id": id, "part": part, "parts": total, "data": data }).to_string()
+        })
+        .collect()
+}
+
+impl PostgresNotify {
+    pub async fn new() -> Result<Self, PostgresNotifyError> {
+        let client = PostgresClient::new().await?;
+        client
+            .batch_execute(&format!(
+                r#"
+                CREATE SCHEMA IF NOT EXISTS rindexer_internal;
+                CREATE TABLE IF NOT EXISTS {} (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "message_id" TEXT NOT NULL,
+                    "channel" TEXT NOT NULL,
+                    "payload" JSONB NOT NULL,
+                    "created_at" TIMESTAMPTZ NOT NULL DEFAULT now()
+                );
+            "#,
+                NOTIFY_PAYLOADS_TABLE
+            ))
+            .await?;
+
+        Ok(Self { client, last_pruned: Mutex::new(None) })
+    }
+
+    /// The client shared by every stream, created on first use
+    pub async fn shared() -> Result<Arc<Self>, PostgresNotifyError> {
+        SHARED_POSTGRES_NOTIFY
+            .get_or_try_init(|| async { Ok(Arc::new(Self::new().await?)) })
+            .await
+            .cloned()
+    }
+
+    /// Deletes the referenced payloads older than the retention, at most once an interval
+    async fn prune_payloads(&self) {
+        {
+            let mut last_pruned = self.last_pruned.lock().unwrap_or_else(|e| e.into_inner());
+            if last_pruned.is_some_and(|pruned| pruned.elapsed() < NOTIFY_PAYLOADS_PRUNE_INTERVAL) {
+                return;
+            }
+            *last_pruned = Some(Instant::now());
+        }
+
+        if let Err(e) = self
+            .client
+            .execute(
+                &format!(
+                    "DELETE FROM {} WHERE \"created_at\" < now() - interval '{} hours'",
+                    NOTIFY_PAYLOADS_TABLE, NOTIFY_PAYLOADS_RETENTION_HOURS
+                ),
+                &[],
+            )
+            .await
+        {
+            warn!("Could not delete expired postgres notify payloads: {}", e);
+        }
+    }
+
+    /// Notifies `{ "id": ..., "message": ... }`, messages over the payload limit are sent as
+    /// `{ "id": ..., "payload_id": ... }` pointing at the stored message or in parts
+    pub async fn publish(
+        &self,
+        id: &str,
+        channel: &str,
+        large_payloads: PostgresNotifyLargePayload,
+        message: &Value,
+    ) -> Result<(), PostgresNotifyError> {
+        let payload = json!({ "id": id, "message": message }).to_string();
+        if payload.len() <= MAX_NOTIFY_PAYLOAD_SIZE {
+            self.client.execute("SELECT pg_notify($1, $2)", &[&channel, &payload]).await?;
+            return Ok(());
+        }
+
+        let transaction = self.client.transaction().await?;
+        match large_payloads {
+            PostgresNotifyLargePayload::Reference => {
+                let row = transaction
+                    .transaction
+                    .query_one(
+                        &format!(
+                            "INSERT INTO {} (\"message_id\", \"channel\", \"payload\") VALUES ($1, $2, $3) RETURNING \"id\"",
+                            NOTIFY_PAYLOADS_TABLE
+                        ),
+                        &[&id, &channel, message],
+                    )
+                    .await?;
+                let payload_id: i64 = row.get(0);
+                let reference = json!({ "id": id, "payload_id": payload_id }).to_string();
+                transaction
+                    .transaction
+                    .execute("SELECT pg_notify($1, $2)", &[&channel, &reference])
+                    .await?;
+            }
+            PostgresNotifyLargePayload::Split => {
+                // notifications are delivered on commit in the order they were sent
+                for part in split_notify_payload(id, &serde_json::to_string(message)?) {
+                    transaction
+                        .transaction
+                        .execute("SELECT pg_notify($1, $2)", &[&channel, &part])
+                        .await?;
+                }
+            }
+        }
+        transaction.transaction.commit().await?;
+
+        if large_payloads == PostgresNotifyLargePayload::Reference {
+            self.prune_payloads().await;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_notify_payload() {
+        let message = json!({ "event_data": vec!["0x\"quoted\"\\"; 2000] }).to_string();
+        let parts = split_notify_payload("rindexer_stream__transfer-chunk-0", &message);
+        assert!(parts.len() > 1);
+
+        let mut joined = String::new();
+        for (index, part) in parts.iter().enumerate() {
+            assert!(part.len() <= MAX_NOTIFY_PAYLOAD_SIZE);
+            let part: Value = serde_json::from_str(part).unwrap();
+            assert_eq!(part["part"], json!(index));
+            assert_eq!(part["parts"], json!(parts.len()));
+            joined.push_str(part["data"].as_str().unwrap());
+        }
+        assert_eq!(joined, message);
+    }
+}
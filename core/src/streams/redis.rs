@@ -0,0 +1,79 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use redis::{aio::ConnectionManager, streams::StreamMaxlen, AsyncCommands, RedisError};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::streams::STREAM_MESSAGE_ID_KEY;
+
+#[derive(Error, Debug)]
+pub enum RedisStreamError {
+    #[error("Redis error: {0}")]
+    RedisError(#[from] RedisError),
+
+    #[error("Could not parse message: {0}")]
+    CouldNotParseMessage(#[from] serde_json::Error),
+}
+
+pub struct Redis {
+    connection: ConnectionManager,
+
+    /// Stream keys the consumer groups have been created on by this process
+    consumer_groups: Mutex<HashSet<(String, String)>>,
+}
+
+impl Redis {
+    pub async fn new(url: &str) -> Result<Self, RedisStreamError> {
+        let client = redis::Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+
+        Ok(Self { connection, consumer_groups: Mutex::new(HashSet::new()) })
+    }
+
+    async fn create_consumer_group(&self, key: &str, group: &str) -> Result<(), RedisError> {
+        let entry = (key.to_string(), group.to_string());
+        if self.consumer_groups.lock().unwrap().contains(&entry) {
+            return Ok(());
+        }
+
+        let mut connection = self.connection.clone();
+        let result: Result<(), RedisError> =
+            connection.xgroup_create_mkstream(key, group, "0").await;
+        match result {
+            Ok(()) => {}
+            // the group is already there from another run
+            Err(e) if e.code() == Some("BUSYGROUP") => {}
+            Err(e) => return Err(e),
+        }
+
+        self.consumer_groups.lock().unwrap().insert(entry);
+        Ok(())
+    }
+
+    pub async fn publish(
+        &self,
+        id: &str,
+        key: &str,
+        max_len: Option<usize>,
+        consumer_group: &Option<String>,
+        message: &Value,
+    ) -> Result<(), RedisStreamError> {
+        if let Some(group) = consumer_group {
+            self.create_consumer_group(key, group).await?;
+        }
+
+        let message_body = serde_json::to_string(message)?;
+        let fields = [(STREAM_MESSAGE_ID_KEY, id), ("message", message_body.as_str())];
+
+        let mut connection = self.connection.clone();
+        let _: String = match max_len {
+            // approximate trimming lets redis drop whole nodes which is much cheaper
+            Some(max_len) => {
+                connection.xadd_maxlen(key, StreamMaxlen::Approx(max_len), "*", &fields).await?
+            }
+            None => connection.xadd(key, "*", &fields).await?,
+        };
+
+        Ok(())
+    }
+}
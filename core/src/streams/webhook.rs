@@ -1,8 +1,20 @@
-use reqwest::Client;
+use std::{fs, path::Path};
+
+use hmac::{Hmac, Mac};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Certificate, Client, Identity,
+};
 use serde_json::Value;
+use sha2::Sha256;
+
+use crate::{manifest::stream::WebhookStreamConfig, streams::STREAM_MESSAGE_ID_KEY};
 
-use crate::streams::STREAM_MESSAGE_ID_KEY;
+const WEBHOOK_TIMESTAMP_KEY: &str = "x-rindexer-timestamp";
+const WEBHOOK_SIGNATURE_KEY: &str = "x-rindexer-signature";
+const WEBHOOK_SHARED_SECRET_KEY: &str = "x-rindexer-shared-secret";
 
+#[allow(clippy::enum_variant_names)]
 #[derive(thiserror::Error, Debug)]
 pub enum WebhookError {
     #[error("Request error: {0}")]
@@ -10,34 +22,102 @@ pub enum WebhookError {
 
     #[error("Webhook error: {0}")]
     WebhookError(String),
+
+    #[error("Could not read {0}: {1}")]
+    CouldNotReadCertificate(String, std::io::Error),
+
+    #[error("Could not parse message: {0}")]
+    CouldNotParseMessage(#[from] serde_json::Error),
+}
+
+/// Signs `{timestamp}.{body}` with each secret, receivers recompute it with the secret they
+/// know and reject old timestamps so a seen request can not be replayed or forged
+pub fn sign_webhook_payload(secrets: &[&str], timestamp: i64, body: &[u8]) -> String {
+    secrets
+        .iter()
+        .map(|secret| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .expect("HMAC can take a key of any size");
+            mac.update(format!("{}.", timestamp).as_bytes());
+            mac.update(body);
+            format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 #[derive(Debug, Clone)]
 pub struct Webhook {
     client: Client,
+    shared_secret: String,
+    signing_secrets: Vec<String>,
+    send_shared_secret_header: bool,
+    headers: HeaderMap,
 }
 
 impl Webhook {
-    pub fn new() -> Self {
-        Self { client: Client::new() }
+    pub fn new(config: &WebhookStreamConfig, project_path: &Path) -> Result<Self, WebhookError> {
+        let mut builder = Client::builder();
+        if let Some(tls) = &config.tls {
+            let read = |path: &str| {
+                fs::read(project_path.join(path))
+                    .map_err(|e| WebhookError::CouldNotReadCertificate(path.to_string(), e))
+            };
+
+            builder = builder.use_native_tls().identity(Identity::from_pkcs8_pem(
+                &read(&tls.client_cert)?,
+                &read(&tls.client_key)?,
+            )?);
+            if let Some(ca_cert) = &tls.ca_cert {
+                builder = builder.add_root_certificate(Certificate::from_pem(&read(ca_cert)?)?);
+            }
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in config.headers.iter().flatten() {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                WebhookError::WebhookError(format!("Invalid header {}: {}", name, e))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                WebhookError::WebhookError(format!("Invalid header {}: {}", name, e))
+            })?;
+            headers.insert(name, value);
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            shared_secret: config.shared_secret.clone(),
+            signing_secrets: config.signing_secrets().into_iter().map(String::from).collect(),
+            send_shared_secret_header: config.send_shared_secret_header(),
+            headers,
+        })
     }
 
     pub async fn publish(
         &self,
         id: &str,
         endpoint: &str,
-        shared_secret: &str,
         message: &Value,
     ) -> Result<(), WebhookError> {
-        let response = self
+        let body = serde_json::to_vec(message)?;
+        let timestamp = chrono::Utc::now().timestamp();
+        let secrets: Vec<&str> = self.signing_secrets.iter().map(String::as_str).collect();
+        let signature = sign_webhook_payload(&secrets, timestamp, &body);
+
+        let mut request = self
             .client
             .post(endpoint)
-            .header("Content-Type", "application/json")
-            .header("x-rindexer-shared-secret", shared_secret)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json")
             .header(STREAM_MESSAGE_ID_KEY, id)
-            .json(message)
-            .send()
-            .await?;
+            .header(WEBHOOK_TIMESTAMP_KEY, timestamp)
+            .header(WEBHOOK_SIGNATURE_KEY, signature);
+
+        if self.send_shared_secret_header {
+            request = request.header(WEBHOOK_SHARED_SECRET_KEY, &self.shared_secret);
+        }
+
+        let response = request.body(body).send().await?;
 
         if response.status().is_success() {
             Ok(())
@@ -49,3 +129,27 @@ impl Webhook {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_webhook_payload() {
+        let body = br#"{"event_name":"Transfer"}"#;
+
+        assert_eq!(
+            sign_webhook_payload(&["whsec_new"], 1700000000, body),
+            "v1=e55972bb9641a046e58589d0f20cf238e40a84a802b728c15271e72805cf9115"
+        );
+        assert_eq!(
+            sign_webhook_payload(&["whsec_new", "whsec_old"], 1700000000, body),
+            "v1=e55972bb9641a046e58589d0f20cf238e40a84a802b728c15271e72805cf9115,\
+             v1=30a1164e44d1a7c6035f458586e67b06571570a1605e2e63841863ab20bf227d"
+        );
+        assert_ne!(
+            sign_webhook_payload(&["whsec_new"], 1700000001, body),
+            sign_webhook_payload(&["whsec_new"], 1700000000, body)
+        );
+    }
+}
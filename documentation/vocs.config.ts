@@ -50,6 +50,7 @@ export default defineConfig({
             { text: 'Networks', link: '/docs/start-building/yaml-config/networks' },
             { text: 'Storage', link: '/docs/start-building/yaml-config/storage' },
             { text: 'Contracts', link: '/docs/start-building/yaml-config/contracts' },
+            { text: 'Entities', link: '/docs/start-building/yaml-config/entities' },
             { text: 'GraphQL', link: '/docs/start-building/yaml-config/graphql' },
             { text: 'Global', link: '/docs/start-building/yaml-config/global' },
           ],
@@ -87,6 +88,9 @@ export default defineConfig({
             { text: 'Kafka', link: '/docs/start-building/streams/kafka' },
             { text: 'Rabbitmq', link: '/docs/start-building/streams/rabbitmq' },
             { text: 'SNS/SQS', link: '/docs/start-building/streams/sns' },
+            { text: 'Postgres NOTIFY', link: '/docs/start-building/streams/postgres-notify' },
+            { text: 'NATS', link: '/docs/start-building/streams/nats' },
+            { text: 'Redis', link: '/docs/start-building/streams/redis' },
           ],
         },
         {
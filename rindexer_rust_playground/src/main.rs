@@ -12,6 +12,7 @@ async fn main() {
 
     let mut enable_graphql = false;
     let mut enable_indexer = false;
+    let mut allow_destructive_migrations = false;
 
     let mut port: Option<u16> = None;
 
@@ -19,6 +20,7 @@ async fn main() {
         match arg.as_str() {
             "--graphql" => enable_graphql = true,
             "--indexer" => enable_indexer = true,
+            "--allow-destructive" => allow_destructive_migrations = true,
             _ if arg.starts_with("--port=") || arg.starts_with("--p") => {
                 if let Some(value) = arg.split('=').nth(1) {
                     let overridden_port = value.parse::<u16>();
@@ -47,7 +49,10 @@ async fn main() {
                 manifest_path: &manifest_path,
                 indexing_details: if enable_indexer {
                     // EventCallbackRegistry { events: vec![] }
-                    Some(IndexingDetails { registry: register_all_handlers(&manifest_path).await })
+                    Some(IndexingDetails {
+                        registry: register_all_handlers(&manifest_path).await,
+                        allow_destructive_migrations,
+                    })
                 } else {
                     None
                 },
@@ -1,6 +1,9 @@
 use std::path::PathBuf;
 
-use rindexer::event::callback_registry::EventCallbackRegistry;
+use rindexer::{
+    event::callback_registry::EventCallbackRegistry, manifest::yaml::read_manifest,
+    set_postgres_connection_details,
+};
 
 use super::rindexer_playground::{
     erc_20_filter::erc_20_filter_handlers, rocket_pool_eth::rocket_pool_eth_handlers,
@@ -8,6 +11,9 @@ use super::rindexer_playground::{
 };
 
 pub async fn register_all_handlers(manifest_path: &PathBuf) -> EventCallbackRegistry {
+    // the handlers connect to postgres when they are created
+    let manifest = read_manifest(manifest_path).expect("Failed to read rindexer.yaml");
+    set_postgres_connection_details(manifest.storage.postgres_connection());
     let mut registry = EventCallbackRegistry::new();
     rocket_pool_eth_handlers(manifest_path, &mut registry).await;
     erc_20_filter_handlers(manifest_path, &mut registry).await;